@@ -1,118 +1,1036 @@
+use std::io::{IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use inquire::error::InquireError;
 use inquire::PasswordDisplayMode;
 
-use translator_mangler::get_random_lang_path;
-use translator_mangler::mangle;
+use translator_mangler::cassette::RecordingTranslator;
+use translator_mangler::credentials::{
+    Backend, ChainProvider, CredentialError, CredentialProvider, Credentials, EnvProvider,
+    FileProvider, Secret,
+};
+use translator_mangler::layer::{
+    CacheLayer, MeterLayer, RateLimitLayer, RetryLayer, TranslatorBuilder,
+};
+use translator_mangler::metrics::Similarity;
+use translator_mangler::strategy::{
+    CycleBank, LanguageGraph, LazyPath, MaxDistance, NoRepeatWindow, PathExpr, PathStrategy,
+    RoundTrip, UniformRandom,
+};
 use translator_mangler::Translator;
 use translator_mangler::TranslatorBaidu;
 use translator_mangler::TranslatorGoogleCloud;
 use translator_mangler::TranslatorYoudao;
+use translator_mangler::{
+    assign_translators, common_lang, mangle_checkpointed, mangle_mixed_best_of, mangle_mixed_until,
+    mangle_mixed_with_trace, resume_mangle_mixed, resume_mangle_until, BestOf, BestOfOutcome,
+    CancellationToken, Candidate, Checkpoint, Execution, HopFailure, MangleError, MangleObserver,
+    MangleOptions, MangleStep, MangleTrace, MixedJob, Objective, Routing, StopRule,
+    TranslationDirection, TranslationError,
+};
+use translator_mangler::{TranslateOption, TranslateOptions};
 
-fn prompt_baidu_api() -> inquire::error::InquireResult<Box<dyn Translator>> {
-    let input_api_key = inquire::Password::new("API key for Baidu API?")
-        .without_confirmation()
-        .with_display_mode(PasswordDisplayMode::Masked)
-        .prompt()?;
-    let input_app_id = inquire::Text::new("App ID for Baidu API?").prompt()?;
+/// Options given on the command line.
+struct Args {
+    /// Most verbose level of the diagnostics printed to stderr.
+    log_level: tracing::Level,
+    /// File to write Prometheus metrics to after each run.
+    #[cfg(feature = "metrics")]
+    metrics_file: Option<String>,
+    /// File to save the progress of each run to, after every hop.
+    checkpoint_file: Option<String>,
+    /// File of a checkpoint to resume instead of mangling new texts.
+    resume_file: Option<String>,
+}
 
-    Ok(Box::new(TranslatorBaidu::new(
-        &input_app_id,
-        &input_api_key,
-    )))
+impl Args {
+    /// Parse the command line arguments.
+    ///
+    /// `-v`/`--verbose` prints debug diagnostics, `--log-level <LEVEL>` picks
+    /// any level. Only warnings and errors are printed by default.
+    /// `--checkpoint <FILE>` saves the progress of each run of a single
+    /// plan, and
+    /// `--resume <FILE>` continues the run saved in a checkpoint.
+    fn parse() -> Result<Args, String> {
+        let mut parsed = Args {
+            log_level: tracing::Level::WARN,
+            #[cfg(feature = "metrics")]
+            metrics_file: None,
+            checkpoint_file: None,
+            resume_file: None,
+        };
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-v" | "--verbose" => parsed.log_level = tracing::Level::DEBUG,
+                "--log-level" => {
+                    let level = args.next().ok_or("--log-level needs a value")?;
+                    parsed.log_level = level
+                        .parse()
+                        .map_err(|_| format!("unknown log level: {}", level))?;
+                }
+                #[cfg(feature = "metrics")]
+                "--metrics" => {
+                    parsed.metrics_file = Some(args.next().ok_or("--metrics needs a file")?);
+                }
+                "--checkpoint" => {
+                    parsed.checkpoint_file = Some(args.next().ok_or("--checkpoint needs a file")?);
+                }
+                "--resume" => {
+                    parsed.resume_file = Some(args.next().ok_or("--resume needs a file")?);
+                }
+                _ => return Err(format!("unknown argument: {}", arg)),
+            }
+        }
+        Ok(parsed)
+    }
 }
 
-fn prompt_google_cloud_api() -> inquire::error::InquireResult<Box<dyn Translator>> {
-    let input_api_key = inquire::Password::new("API key for Google Cloud API?")
-        .without_confirmation()
-        .with_display_mode(PasswordDisplayMode::Masked)
-        .prompt()?;
+/// A provider asking the user for credentials in the terminal.
+struct PromptProvider;
+
+impl CredentialProvider for PromptProvider {
+    fn credentials(&self, backend: Backend) -> Result<Option<Credentials>, CredentialError> {
+        let to_error = |e: InquireError| CredentialError {
+            message: e.to_string(),
+        };
+        let (id_prompt, secret_prompt) = match backend {
+            Backend::Baidu => (Some("App ID for Baidu API?"), "API key for Baidu API?"),
+            Backend::GoogleCloud => (None, "API key for Google Cloud API?"),
+            Backend::Youdao => (
+                Some("App key for Youdao AI API?"),
+                "App secret for Youdao AI API?",
+            ),
+        };
+
+        let secret = inquire::Password::new(secret_prompt)
+            .without_confirmation()
+            .with_display_mode(PasswordDisplayMode::Masked)
+            .prompt()
+            .map_err(to_error)?;
+        let secret = Secret::new(secret);
+        let id = match id_prompt {
+            Some(id_prompt) => Some(inquire::Text::new(id_prompt).prompt().map_err(to_error)?),
+            None => None,
+        };
+
+        Ok(Some(Credentials { id, secret }))
+    }
+}
+
+/// Find credentials for `backend` in the environment or the credentials
+/// file, and ask the user if there are none.
+fn load_credentials(backend: Backend) -> Result<Credentials, CredentialError> {
+    let mut stored: Vec<Box<dyn CredentialProvider>> = vec![Box::new(EnvProvider)];
+    if let Some(file) = FileProvider::from_default_path() {
+        stored.push(Box::new(file));
+    }
+
+    match ChainProvider::new(stored).credentials(backend) {
+        Ok(Some(credentials)) => {
+            println!("[i] Using stored credentials for {} API.", backend.name());
+            return Ok(credentials);
+        }
+        Ok(None) => {}
+        Err(e) => println!("[!] {}", e.message),
+    }
+    PromptProvider
+        .credentials(backend)?
+        .ok_or_else(|| CredentialError {
+            message: format!("No credentials for {} API", backend.name()),
+        })
+}
 
-    Ok(Box::new(TranslatorGoogleCloud::new(&input_api_key)))
+fn create_translator(backend: Backend) -> Result<Box<dyn Translator>, CredentialError> {
+    let credentials = load_credentials(backend)?;
+    Ok(match backend {
+        Backend::Baidu => Box::new(TranslatorBaidu::from_credentials(credentials)?),
+        Backend::GoogleCloud => Box::new(TranslatorGoogleCloud::from_credentials(credentials)),
+        Backend::Youdao => Box::new(TranslatorYoudao::from_credentials(credentials)?),
+    })
+}
+
+/// A progress bar with the current text, redrawn in place on one line.
+///
+/// Nothing is drawn unless stdout is a terminal, so that redirected output
+/// holds only the results.
+struct ProgressBar {
+    /// Whether stdout is a terminal.
+    visible: bool,
+    /// The number of hops in the run.
+    total: usize,
+    /// The text after the last completed hop.
+    text: Mutex<String>,
+}
+
+impl ProgressBar {
+    /// Width of the bar in characters.
+    const BAR_WIDTH: usize = 20;
+    /// Characters of the current text shown after the bar.
+    const TEXT_WIDTH: usize = 40;
+
+    fn new(original_text: &str, total: usize) -> ProgressBar {
+        ProgressBar {
+            visible: std::io::stdout().is_terminal(),
+            total,
+            text: Mutex::new(original_text.to_string()),
+        }
+    }
+
+    fn draw(&self, done: usize, status: &str) {
+        if !self.visible {
+            return;
+        }
+        let filled = (done * Self::BAR_WIDTH)
+            .checked_div(self.total)
+            .unwrap_or(0);
+        let text = self.text.lock().unwrap().replace('\n', " / ");
+        let mut preview = text.chars().take(Self::TEXT_WIDTH).collect::<String>();
+        if text.chars().count() > Self::TEXT_WIDTH {
+            preview += "...";
+        }
+        print!(
+            "\r\x1b[2K[{}{}] {}: {}",
+            "#".repeat(filled),
+            "-".repeat(Self::BAR_WIDTH - filled),
+            status,
+            preview
+        );
+        let _ = std::io::stdout().flush();
+    }
+
+    /// Remove the bar, leaving the line empty.
+    fn finish(&self) {
+        if !self.visible {
+            return;
+        }
+        print!("\r\x1b[2K");
+        let _ = std::io::stdout().flush();
+    }
+}
+
+impl MangleObserver for ProgressBar {
+    fn on_step_start(&self, index: usize, total: usize, direction: &TranslationDirection) {
+        let status = format!(
+            "hop {}/{} {}->{}",
+            index + 1,
+            total,
+            direction.from_lang,
+            direction.to_lang
+        );
+        self.draw(index, &status);
+    }
+
+    fn on_step_done(&self, index: usize, total: usize, step: &MangleStep) {
+        *self.text.lock().unwrap() = step.output.clone();
+        self.draw(index + 1, &format!("hop {}/{} done", index + 1, total));
+    }
+
+    fn on_retry(&self, index: usize, retry: u32, error: &TranslationError) {
+        let status = format!(
+            "hop {}/{} retry {} after {}",
+            index + 1,
+            self.total,
+            retry,
+            error.kind.as_str()
+        );
+        self.draw(index, &status);
+    }
+}
+
+/// Print every step of `trace` as a table, followed by the totals.
+fn print_trace(trace: &MangleTrace) {
+    println!(
+        "{:>4}  {:<16}  {:<12}  {:>8}  {:>7}  {:>5}  Output",
+        "#", "Direction", "Backend", "Time", "Retries", "Score"
+    );
+    let similarities = trace.step_similarities();
+    for (index, (step, similarity)) in trace.steps.iter().zip(&similarities).enumerate() {
+        let direction = format!("{}->{}", step.direction.from_lang, step.direction.to_lang);
+        println!(
+            "{:>4}  {:<16}  {:<12}  {:>6}ms  {:>7}  {:>5.1}  {}",
+            index + 1,
+            direction,
+            step.backend,
+            step.latency.as_millis(),
+            step.retries,
+            similarity.mangle_score(),
+            step.output.replace('\n', " / ")
+        );
+    }
+    println!(
+        "[i] {} rounds in {:.1}s ({:.1}s translating), {} retries.",
+        trace.steps.len(),
+        trace.elapsed.as_secs_f64(),
+        trace.latency.as_secs_f64(),
+        trace.retries
+    );
+}
+
+/// Get the options of a run reporting to `progress`, which Ctrl+C cancels
+/// through `current_run`.
+fn run_options(
+    progress: &Arc<ProgressBar>,
+    current_run: &Mutex<CancellationToken>,
+    translate_options: &TranslateOptions,
+) -> MangleOptions {
+    let token = CancellationToken::new();
+    *current_run.lock().unwrap() = token.clone();
+    MangleOptions::new()
+        .with_cancellation(token)
+        .with_translate_options(translate_options.clone())
+        .with_shared_observer(progress.clone())
+}
+
+/// Resume the run saved in the checkpoint at `file` with `translators`,
+/// which may be other back-end APIs than those it was started with.
+fn resume_run(
+    file: &str,
+    translators: &[&dyn Translator],
+    current_run: &Mutex<CancellationToken>,
+    translate_options: &TranslateOptions,
+) -> inquire::error::InquireResult<()> {
+    let mut checkpoint = Checkpoint::load(file).map_err(|e| InquireError::Custom(e.into()))?;
+    let Some(input) = checkpoint.inputs.iter().find(|input| !input.is_done()) else {
+        println!("[i] Nothing left to resume in {}.", file);
+        return Ok(());
+    };
+    if let Some(seed) = checkpoint.seed {
+        println!("[i] Seed: {}", seed);
+    }
+    let left = input.path.len() - input.steps.len();
+    println!("[i] Resuming {} of {} rounds.", left, input.path.len());
+
+    let progress = Arc::new(ProgressBar::new(&input.text, left));
+    let options = run_options(&progress, current_run, translate_options);
+    let routing = if translators.len() == 1 {
+        Routing::Single(translators[0])
+    } else {
+        Routing::Mixed(translators)
+    };
+    let resumed = mangle_checkpointed(
+        routing,
+        &mut checkpoint,
+        file,
+        Execution::Sequential,
+        &options,
+    )
+    .into_iter()
+    .collect::<Result<Vec<String>, MangleError>>();
+    progress.finish();
+    match resumed {
+        Ok(_) => {
+            for input in &checkpoint.inputs {
+                println!("[+] {}", input.text);
+                println!(
+                    "[i] Mangle score: {:.1}",
+                    input.trace().similarity().mangle_score()
+                );
+            }
+        }
+        Err(MangleError::Failed(e)) => {
+            println!("[!] {}", e.message);
+            println!("[i] Progress saved to {}.", file);
+        }
+        Err(MangleError::HopFailed(failure)) => {
+            println!(
+                "[!] {} at round {}",
+                failure.error.message,
+                failure.index + 1
+            );
+            println!("[i] Progress saved to {}.", file);
+        }
+        Err(MangleError::Stopped { hops, .. }) => {
+            println!("[!] Interrupted after {} more rounds.", hops);
+            println!("[i] Progress saved to {}.", file);
+        }
+    }
+    Ok(())
+}
+
+/// Offer to retry a run of `total` rounds that `mangled` failed at a round
+/// from that round, for as long as it fails, continuing it with `resume`.
+fn retry_failed<T>(
+    mut mangled: Result<T, MangleError>,
+    total: usize,
+    current_run: &Mutex<CancellationToken>,
+    translate_options: &TranslateOptions,
+    resume: &mut dyn FnMut(&HopFailure, &MangleOptions) -> Result<T, MangleError>,
+) -> inquire::error::InquireResult<Result<T, MangleError>> {
+    while let Err(MangleError::HopFailed(failure)) = &mangled {
+        let round = failure.index + 1;
+        let message = format!("Retry from round {}?", round);
+        let help = format!("Round {} failed: {}", round, failure.error.message);
+        let retry = inquire::CustomType::<bool>::new(&message)
+            .with_help_message(&help)
+            .with_default(true)
+            .prompt()?;
+        if !retry {
+            break;
+        }
+
+        let progress = Arc::new(ProgressBar::new(failure.text(), total - failure.index));
+        let options = run_options(&progress, current_run, translate_options);
+        mangled = resume(failure, &options);
+        progress.finish();
+    }
+    Ok(mangled)
+}
+
+/// Offer to retry each failed candidate of `outcome`, of `total` rounds,
+/// scoring those completed with `objective`.
+fn retry_candidates(
+    outcome: &mut BestOfOutcome,
+    total: usize,
+    objective: &Objective,
+    translators: &[&dyn Translator],
+    current_run: &Mutex<CancellationToken>,
+    translate_options: &TranslateOptions,
+) -> inquire::error::InquireResult<()> {
+    for (index, error) in std::mem::take(&mut outcome.failed) {
+        if matches!(error, MangleError::HopFailed(_)) {
+            println!("[!] Candidate {} failed.", index + 1);
+        }
+        let retried = retry_failed(
+            Err(error),
+            total,
+            current_run,
+            translate_options,
+            &mut |failure, options| resume_mangle_mixed(failure, translators, options),
+        )?;
+        match retried {
+            Ok(trace) => {
+                let position = outcome
+                    .candidates
+                    .partition_point(|candidate| candidate.index < index);
+                let score = objective.score(&trace);
+                outcome.candidates.insert(
+                    position,
+                    Candidate {
+                        index,
+                        trace,
+                        score,
+                    },
+                );
+            }
+            Err(e) => outcome.failed.push((index, e)),
+        }
+    }
+    // The first of the highest scores is the best, as in the search.
+    outcome.best = (0..outcome.candidates.len()).reduce(|best, position| {
+        if outcome.candidates[position].score > outcome.candidates[best].score {
+            position
+        } else {
+            best
+        }
+    });
+    Ok(())
 }
 
-fn prompt_youdao_api() -> inquire::error::InquireResult<Box<dyn Translator>> {
-    let input_app_key = inquire::Text::new("App key for Youdao AI API?").prompt()?;
-    let input_app_secret = inquire::Password::new("App secret for Youdao AI API?")
-        .without_confirmation()
-        .with_display_mode(PasswordDisplayMode::Masked)
+/// Ask what makes the best of many candidates.
+fn prompt_objective() -> inquire::error::InquireResult<Objective> {
+    let objective_choices = vec![
+        "Most mangled",
+        "Least mangled",
+        "Closest to a mangle score",
+        "Closest to a length",
+    ];
+    let input_objective = inquire::Select::new("Keep which candidate?", objective_choices)
+        .with_help_message("Choose which result is kept among the candidates")
         .prompt()?;
+    Ok(match input_objective {
+        "Least mangled" => Objective::LeastMangled,
+        "Closest to a mangle score" => Objective::TargetScore(
+            inquire::CustomType::<f64>::new("Target mangle score?")
+                .with_help_message("Enter the mangle score from 0 to 100 to get closest to")
+                .with_default(50.0)
+                .prompt()?,
+        ),
+        "Closest to a length" => Objective::TargetLength(
+            inquire::CustomType::<usize>::new("Target length?")
+                .with_help_message("Enter the length in characters to get closest to")
+                .prompt()?,
+        ),
+        _ => Objective::MostMangled,
+    })
+}
 
-    Ok(Box::new(TranslatorYoudao::new(
-        &input_app_key,
-        &input_app_secret,
-    )))
+/// Print the score of every candidate of `outcome`, out of `total`.
+fn print_candidates(outcome: &BestOfOutcome, total: usize) {
+    for (position, candidate) in outcome.candidates.iter().enumerate() {
+        let marker = if outcome.best == Some(position) {
+            "*"
+        } else {
+            " "
+        };
+        println!(
+            "[i] {}{:>3}  {:>7.1}  {}",
+            marker,
+            candidate.index + 1,
+            candidate.score,
+            candidate.trace.result.replace('\n', " / ")
+        );
+    }
+    for (index, error) in &outcome.failed {
+        println!("[!] {:>4}  {}", index + 1, error);
+    }
+    if outcome.stopped.is_some() {
+        println!(
+            "[!] Interrupted after {} of {} candidates.",
+            outcome.candidates.len() + outcome.failed.len(),
+            total
+        );
+    }
+}
+
+/// Ask for the seed of a plan, defaulting to a random one.
+fn prompt_seed() -> inquire::error::InquireResult<u64> {
+    inquire::CustomType::<u64>::new("Seed?")
+        .with_help_message("Enter the seed of a previous plan to reproduce it, or keep this one")
+        .with_default(fastrand::u64(..))
+        .prompt()
+}
+
+/// Prompt for a path expression until a valid one is entered, offering the
+/// last one entered as the default.
+fn prompt_path_expr(
+    translators: &[&dyn Translator],
+    lang_bank: &[&str],
+    last_expr: &mut String,
+) -> inquire::error::InquireResult<PathExpr> {
+    loop {
+        let mut prompt = inquire::Text::new("Path expression?").with_help_message(
+            "Enter languages separated by >, with * for a random one, *[ja,ko] for one of a few \
+             and (ja > ko){3} to repeat",
+        );
+        if !last_expr.is_empty() {
+            prompt = prompt.with_default(last_expr);
+        }
+        let input_expr = prompt.prompt()?;
+        // Languages of other APIs are caught when assigning hops to APIs.
+        match PathExpr::parse(&input_expr).and_then(|expr| {
+            translators
+                .iter()
+                .map(|translator| expr.validate(*translator, lang_bank))
+                .reduce(|acc, result| acc.or(result))
+                .unwrap_or(Ok(()))?;
+            Ok(expr)
+        }) {
+            Ok(expr) => {
+                *last_expr = input_expr;
+                return Ok(expr);
+            }
+            Err(e) => println!("[!] {}", e.message),
+        }
+    }
+}
+
+/// Ask for an optional value, which is `None` if left empty.
+fn prompt_optional(message: &str, help: &str) -> inquire::error::InquireResult<Option<String>> {
+    let input = inquire::Text::new(message)
+        .with_help_message(help)
+        .with_default("")
+        .prompt()?;
+    Ok((!input.trim().is_empty()).then(|| input.trim().to_string()))
 }
 
 fn main() -> inquire::error::InquireResult<()> {
+    let args = Args::parse().map_err(|e| InquireError::Custom(e.into()))?;
+    tracing_subscriber::fmt()
+        .with_max_level(args.log_level)
+        .with_writer(std::io::stderr)
+        .init();
+
     println!("[i] Welcome to translator_mangler!");
 
     let api_choices = vec!["Baidu", "Google Cloud", "Youdao AI"];
-    let input_api_choices = inquire::Select::new("Back-end API?", api_choices)
-        .with_help_message("Choose the back-end translation API you want to use")
+    let input_api_choices = inquire::MultiSelect::new("Back-end APIs?", api_choices)
+        .with_help_message(
+            "Choose the back-end translation APIs you want to use, each hop going to one of them",
+        )
+        .with_default(&[0])
         .prompt()?;
-    let translator = match input_api_choices {
-        "Baidu" => prompt_baidu_api()?,
-        "Google Cloud" => prompt_google_cloud_api()?,
-        "Youdao AI" => prompt_youdao_api()?,
-        _ => {
-            return Err(InquireError::InvalidConfiguration(
-                "Back-end API".to_string(),
-            ))
-        }
-    };
+    if input_api_choices.is_empty() {
+        return Err(InquireError::InvalidConfiguration(
+            "Back-end APIs".to_string(),
+        ));
+    }
+    let mut translators = Vec::new();
+    for choice in &input_api_choices {
+        let backend = match *choice {
+            "Baidu" => Backend::Baidu,
+            "Google Cloud" => Backend::GoogleCloud,
+            "Youdao AI" => Backend::Youdao,
+            _ => {
+                return Err(InquireError::InvalidConfiguration(
+                    "Back-end APIs".to_string(),
+                ))
+            }
+        };
+        translators
+            .push(create_translator(backend).map_err(|e| InquireError::Custom(e.message.into()))?);
+    }
 
-    let input_langs = inquire::Text::new("Language bank?")
-        .with_help_message("Choose the languages you want to use in mangling, separated by comma")
-        .with_default(&translator.get_supported_langs().join(","))
-        .prompt()?;
-    let input_langs_vec = input_langs
-        .split(',')
-        .map(|s| s.trim())
-        .collect::<Vec<&str>>();
-
-    let input_rounds = inquire::CustomType::<usize>::new("Rounds to mangle?")
-        .with_help_message("Enter the number of rounds you want to translate")
-        .with_default(20)
+    let input_record = inquire::Text::new("Record session to?")
+        .with_help_message(
+            "Enter a cassette file to record all API calls to, suffixed with the API name if \
+             there are many, or leave empty",
+        )
+        .with_default("")
         .prompt()?;
+    if !input_record.is_empty() {
+        let many = translators.len() > 1;
+        translators = translators
+            .into_iter()
+            .map(|translator| {
+                let path = if many {
+                    format!("{}.{}", input_record, translator.name())
+                } else {
+                    input_record.clone()
+                };
+                let recording = RecordingTranslator::create(translator, &path)
+                    .map_err(|e| InquireError::Custom(e.into()))?;
+                Ok(Box::new(recording) as Box<dyn Translator>)
+            })
+            .collect::<Result<Vec<_>, InquireError>>()?;
+    }
 
     let input_delay = inquire::CustomType::<u64>::new("API cool-down?")
         .with_help_message("Enter the milliseconds between each API call")
         .with_default(1000)
         .prompt()?;
 
+    let input_retries = inquire::CustomType::<u32>::new("Retries on failure?")
+        .with_help_message("Enter how many times an API call failing temporarily is retried")
+        .with_default(2)
+        .prompt()?;
+
+    let input_cache = inquire::CustomType::<bool>::new("Cache translations?")
+        .with_help_message("Enter whether repeated API calls should be answered from a cache")
+        .with_default(true)
+        .prompt()?;
+
+    let supported_options = translators
+        .iter()
+        .flat_map(|translator| translator.supported_options())
+        .collect::<Vec<&TranslateOption>>();
+    let mut translate_options = TranslateOptions::new();
+    if supported_options.contains(&&TranslateOption::Model) {
+        translate_options.model = prompt_optional(
+            "Translation model?",
+            "Enter the model to translate with, such as nmt or base, or leave empty",
+        )?;
+    }
+    if supported_options.contains(&&TranslateOption::Domain) {
+        translate_options.domain = prompt_optional(
+            "Translation domain?",
+            "Enter the domain of your texts, such as medicine or finance, or leave empty",
+        )?;
+    }
+    if supported_options.contains(&&TranslateOption::Glossary) {
+        translate_options.glossary = prompt_optional(
+            "Glossary ID?",
+            "Enter the ID of the custom vocabulary to translate with, or leave empty",
+        )?;
+    }
+
+    // Counts every request actually sent, retries included.
+    let meter = MeterLayer::new();
+    let translators = translators
+        .into_iter()
+        .map(|translator| {
+            TranslatorBuilder::new()
+                .optional_layer(input_cache.then(CacheLayer::new))
+                .layer(RetryLayer::new(input_retries))
+                .layer(RateLimitLayer::new(Duration::from_millis(input_delay)))
+                .layer(meter.clone())
+                .build(translator)
+        })
+        .collect::<Vec<Box<dyn Translator>>>();
+    let translators = translators
+        .iter()
+        .map(|translator| translator.as_ref())
+        .collect::<Vec<&dyn Translator>>();
+    // Ctrl+C while processing cancels the current run only.
+    let current_run = Arc::new(Mutex::new(CancellationToken::new()));
+    {
+        let current_run = current_run.clone();
+        ctrlc::set_handler(move || current_run.lock().unwrap().cancel())
+            .map_err(|e| InquireError::Custom(e.into()))?;
+    }
+
+    if let Some(file) = &args.resume_file {
+        return resume_run(file, &translators, &current_run, &translate_options);
+    }
+
+    // With many APIs, languages are named by their common codes.
+    let mut supported_langs = translators[0].get_supported_langs().to_vec();
+    if translators.len() > 1 {
+        supported_langs = supported_langs.into_iter().map(common_lang).collect();
+        supported_langs.dedup();
+    }
+
+    let plan_choices = vec!["Random path", "Path expression"];
+    let input_plan = inquire::Select::new("Plan by?", plan_choices)
+        .with_help_message(
+            "Choose between random paths and hand-written ones like en > (ja > ko){3} > * > en",
+        )
+        .prompt()?;
+    // A random plan is made of a language bank, a number of rounds and a
+    // strategy; path expressions draw their wildcards from all languages.
+    let random_plan = if input_plan == "Random path" {
+        let input_langs = inquire::Text::new("Language bank?")
+            .with_help_message(
+                "Choose the languages you want to use in mangling, separated by comma",
+            )
+            .with_default(&supported_langs.join(","))
+            .prompt()?;
+        let input_langs_vec = input_langs
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .collect::<Vec<String>>();
+
+        let input_rounds = inquire::CustomType::<usize>::new("Rounds to mangle?")
+            .with_help_message("Enter the number of rounds you want to translate")
+            .with_default(20)
+            .prompt()?;
+
+        let strategy_choices = vec![
+            "Uniform random",
+            "No repeats within a window",
+            "Round trips",
+            "Cycle through bank",
+            "Maximize distance",
+            "Follow supported pairs",
+        ];
+        let input_strategy = inquire::Select::new("Path strategy?", strategy_choices)
+            .with_help_message("Choose how the languages of each mangling plan are picked")
+            .prompt()?;
+        let strategy: Box<dyn PathStrategy> = match input_strategy {
+            "No repeats within a window" => {
+                let input_window = inquire::CustomType::<usize>::new("Window size?")
+                    .with_help_message("Enter how many recent languages must not be visited again")
+                    .with_default(3)
+                    .prompt()?;
+                Box::new(NoRepeatWindow::new(input_window))
+            }
+            "Round trips" => Box::new(RoundTrip),
+            "Cycle through bank" => Box::new(CycleBank),
+            "Maximize distance" => Box::new(MaxDistance),
+            "Follow supported pairs" => Box::new(LanguageGraph::from_translators(&translators)),
+            _ => Box::new(UniformRandom),
+        };
+
+        let input_stop_early = inquire::CustomType::<bool>::new("Stop early?")
+            .with_help_message(
+                "Enter whether runs stop once mangled enough or when the text stops changing, \
+                 taking the rounds as a maximum",
+            )
+            .with_default(false)
+            .prompt()?;
+        let goal = if input_stop_early {
+            let input_score = inquire::CustomType::<f64>::new("Target mangle score?")
+                .with_help_message(
+                    "Enter the mangle score from 0 to 100 to stop at, or 100 to stop only when \
+                     the text stops changing",
+                )
+                .with_default(50.0)
+                .prompt()?;
+            Some(
+                StopRule::new(input_rounds)
+                    .with_similarity_below(1.0 - input_score / 100.0)
+                    .with_unchanged_for(2)
+                    .with_cycle_detection(),
+            )
+        } else {
+            None
+        };
+        Some((input_langs_vec, input_rounds, strategy, goal))
+    } else {
+        None
+    };
+    let mut last_expr = String::new();
+
     let input_preview_plan = inquire::CustomType::<bool>::new("Preview plan?")
         .with_help_message("Enter whether you want to preview mangling plans before running")
         .with_default(true)
         .prompt()?;
 
+    let input_show_trace = inquire::CustomType::<bool>::new("Show trace?")
+        .with_help_message("Enter whether you want to see the text after each round")
+        .with_default(false)
+        .prompt()?;
+
+    let input_candidates = if random_plan.as_ref().is_some_and(|plan| plan.3.is_some()) {
+        1
+    } else {
+        inquire::CustomType::<usize>::new("Candidates per text?")
+            .with_help_message(
+                "Enter how many plans to run for each text, keeping the best result, at least 1",
+            )
+            .with_default(1)
+            .prompt()?
+            .max(1)
+    };
+    let input_objective = if input_candidates > 1 {
+        prompt_objective()?
+    } else {
+        Objective::MostMangled
+    };
+
+    // Only runs of a single plan built up front are checkpointed.
+    let stops_early = random_plan.as_ref().is_some_and(|plan| plan.3.is_some());
+    if args.checkpoint_file.is_some() && (stops_early || input_candidates > 1) {
+        println!(
+            "[!] --checkpoint is ignored: runs stopping early or keeping the best of many \
+             candidates are not checkpointed."
+        );
+    }
+
     println!("[+] Configuration done.");
 
     loop {
         let input_text = inquire::Text::new("Text to mangle?")
             .with_help_message("Enter the text you wish to mangle")
             .prompt()?;
-        let input_orig_lang = inquire::Text::new("Original language?")
-            .with_help_message("Enter the original language of the text you entered")
-            .prompt()?;
+        // A goal builds its path leg by leg while mangling.
+        let (input_seed, paths, lazy_path) = match &random_plan {
+            Some((bank, rounds, strategy, goal)) => {
+                let input_orig_lang = inquire::Text::new("Original language?")
+                    .with_help_message("Enter the original language of the text you entered")
+                    .prompt()?;
+                let input_seed = prompt_seed()?;
+                let bank = bank.iter().map(String::as_str).collect::<Vec<&str>>();
+                let mut rng = fastrand::Rng::with_seed(input_seed);
+                match goal {
+                    Some(rule) => {
+                        let path = LazyPath::new(strategy.as_ref(), &input_orig_lang, &bank, rng);
+                        (input_seed, Vec::new(), Some((path, rule)))
+                    }
+                    None => (
+                        input_seed,
+                        (0..input_candidates)
+                            .map(|_| strategy.path(&input_orig_lang, &bank, *rounds, &mut rng))
+                            .collect(),
+                        None,
+                    ),
+                }
+            }
+            None => {
+                let expr = prompt_path_expr(&translators, &supported_langs, &mut last_expr)?;
+                let input_seed = prompt_seed()?;
+                let mut rng = fastrand::Rng::with_seed(input_seed);
+                match (0..input_candidates)
+                    .map(|_| expr.resolve(&supported_langs, &mut rng))
+                    .collect::<Result<Vec<_>, _>>()
+                {
+                    Ok(paths) => (input_seed, paths, None),
+                    Err(e) => {
+                        println!("[!] {}", e.message);
+                        continue;
+                    }
+                }
+            }
+        };
+
+        // Only runs of a single plan are checkpointed.
+        let mut checkpointed = false;
+        let (total, mangled) = if let Some((mut path, rule)) = lazy_path {
+            println!("[i] Seed: {}", input_seed);
+            let progress = Arc::new(ProgressBar::new(&input_text, rule.max_rounds()));
+            let options = run_options(&progress, &current_run, &translate_options);
+            let mangled = mangle_mixed_until(&translators, &input_text, &mut path, rule, &options);
+            progress.finish();
+            let mangled = retry_failed(
+                mangled,
+                rule.max_rounds(),
+                &current_run,
+                &translate_options,
+                &mut |failure, options| {
+                    resume_mangle_until(failure, &translators, &mut path, rule, options)
+                },
+            )?;
+            (
+                rule.max_rounds(),
+                mangled.map(|outcome| (outcome.trace, Some(outcome.cause))),
+            )
+        } else {
+            let mut rng = fastrand::Rng::with_seed(input_seed);
+            let plans = match paths
+                .iter()
+                .map(|langs| assign_translators(langs, &translators, &mut rng))
+                .collect::<Result<Vec<_>, _>>()
+            {
+                Ok(plans) => plans,
+                Err(e) => {
+                    println!("[!] {}", e.message);
+                    continue;
+                }
+            };
 
-        let langs = get_random_lang_path(&input_orig_lang, &input_langs_vec, input_rounds);
+            if input_preview_plan {
+                for (index, hops) in plans.iter().enumerate() {
+                    let label = if plans.len() > 1 {
+                        format!("Mangling plan {}", index + 1)
+                    } else {
+                        "Mangling plan".to_string()
+                    };
+                    println!(
+                        "[i] {} (seed {}): {}",
+                        label,
+                        input_seed,
+                        hops.iter()
+                            .map(|hop| {
+                                let pair = &hop.direction;
+                                if translators.len() > 1 {
+                                    let backend = translators[hop.translator].name();
+                                    format!("{}->{} ({}) ", pair.from_lang, pair.to_lang, backend)
+                                } else {
+                                    format!("{}->{} ", pair.from_lang, pair.to_lang)
+                                }
+                            })
+                            .reduce(|acc, current| acc + &current)
+                            .unwrap_or_default()
+                    )
+                }
+            } else {
+                println!("[i] Seed: {}", input_seed);
+            }
 
-        if input_preview_plan {
+            let total = plans[0].len();
+            let progress = Arc::new(ProgressBar::new(&input_text, total));
+            let options = run_options(&progress, &current_run, &translate_options);
+            if plans.len() == 1 {
+                let job = MixedJob {
+                    text: input_text.clone(),
+                    path: plans[0].clone(),
+                };
+                let mut checkpoint = Checkpoint::new_mixed(&[job]).with_seed(input_seed);
+                let mut checkpoint = args
+                    .checkpoint_file
+                    .as_deref()
+                    .map(|file| (file, &mut checkpoint));
+                let mangled = match &mut checkpoint {
+                    Some((file, checkpoint)) => {
+                        checkpointed = true;
+                        mangle_checkpointed(
+                            Routing::Mixed(&translators),
+                            checkpoint,
+                            file,
+                            Execution::Sequential,
+                            &options,
+                        )
+                        .remove(0)
+                        .map(|_| checkpoint.inputs[0].trace())
+                    }
+                    None => mangle_mixed_with_trace(&translators, &input_text, &plans[0], &options),
+                };
+                progress.finish();
+                let mangled = retry_failed(
+                    mangled,
+                    total,
+                    &current_run,
+                    &translate_options,
+                    &mut |failure, options| match &mut checkpoint {
+                        Some((file, checkpoint)) => mangle_checkpointed(
+                            Routing::Mixed(&translators),
+                            checkpoint,
+                            file,
+                            Execution::Sequential,
+                            options,
+                        )
+                        .remove(0)
+                        .map(|_| checkpoint.inputs[0].trace()),
+                        None => resume_mangle_mixed(failure, &translators, options),
+                    },
+                )?;
+                (total, mangled.map(|trace| (trace, None)))
+            } else {
+                let mut plans = plans.into_iter();
+                let mangled = mangle_mixed_best_of(
+                    &translators,
+                    &input_text,
+                    &mut || Ok(plans.next().unwrap_or_default()),
+                    &BestOf::new(input_candidates, input_objective),
+                    &options,
+                );
+                progress.finish();
+                let best = match mangled {
+                    Ok(mut outcome) => {
+                        retry_candidates(
+                            &mut outcome,
+                            total,
+                            &input_objective,
+                            &translators,
+                            &current_run,
+                            &translate_options,
+                        )?;
+                        print_candidates(&outcome, input_candidates);
+                        Ok(outcome.best().map(|best| (best.trace.clone(), None)))
+                    }
+                    Err(e) => Err(e),
+                };
+                match best {
+                    Ok(Some(best)) => (total, Ok(best)),
+                    Ok(None) => {
+                        println!("[!] No candidate was completed.");
+                        continue;
+                    }
+                    Err(e) => (total, Err(e)),
+                }
+            }
+        };
+        let interrupted = mangled.is_err();
+        match mangled {
+            Ok((trace, cause)) => {
+                if input_show_trace {
+                    print_trace(&trace);
+                }
+                println!("[+] {}", trace.result);
+                println!("[i] Mangle score: {:.1}", trace.similarity().mangle_score());
+                if let Some(cause) = cause {
+                    println!("[i] Stopped after {} rounds: {}.", trace.steps.len(), cause);
+                }
+            }
+            Err(MangleError::Failed(e)) => println!("[!] {}", e.message),
+            Err(MangleError::HopFailed(failure)) => {
+                println!(
+                    "[!] {} at round {}",
+                    failure.error.message,
+                    failure.index + 1
+                );
+                println!("[+] {}", failure.text());
+                let similarity = Similarity::between(&input_text, failure.text());
+                println!("[i] Mangle score: {:.1}", similarity.mangle_score());
+            }
+            Err(MangleError::Stopped { text, hops, .. }) => {
+                println!("[!] Interrupted after {} of {} rounds.", hops, total);
+                println!("[+] {}", text);
+                let similarity = Similarity::between(&input_text, &text);
+                println!("[i] Mangle score: {:.1}", similarity.mangle_score());
+            }
+        }
+        let usage = meter.take();
+        println!(
+            "[i] Sent {} characters in {} requests.",
+            usage.chars, usage.calls
+        );
+        if let Some(file) = &args.checkpoint_file
+            && checkpointed
+            && interrupted
+        {
             println!(
-                "[i] Mangling plan: {}",
-                langs
-                    .iter()
-                    .map(|pair| format!("{}->{} ", pair.from_lang, pair.to_lang))
-                    .reduce(|acc, current| acc + &current)
-                    .unwrap_or_default()
-            )
+                "[i] Progress saved to {}; pass --resume {} to continue.",
+                file, file
+            );
         }
 
-        println!("[i] Processing...");
-        let mangled = mangle(translator.as_ref(), &input_text, &langs, input_delay);
-        match mangled {
-            Ok(result) => println!("[+] {}", result),
-            Err(e) => println!("[!] {}", e.message),
+        #[cfg(feature = "metrics")]
+        if let Some(path) = &args.metrics_file
+            && let Err(e) = std::fs::write(path, translator_mangler::telemetry::render_metrics())
+        {
+            println!("[!] Failed to write metrics: {}", e);
         }
     }
 }
@@ -0,0 +1,30 @@
+use super::{Backend, CredentialError, CredentialProvider, Credentials, Secret};
+
+/// A provider reading credentials from environment variables.
+///
+/// | Backend      | Identifier       | Secret                 |
+/// |--------------|------------------|------------------------|
+/// | Baidu        | `BAIDU_APP_ID`   | `BAIDU_API_KEY`        |
+/// | Google Cloud |                  | `GOOGLE_CLOUD_API_KEY` |
+/// | Youdao AI    | `YOUDAO_APP_KEY` | `YOUDAO_APP_SECRET`    |
+///
+/// Credentials are only returned if all variables of a backend are set.
+pub struct EnvProvider;
+
+impl CredentialProvider for EnvProvider {
+    fn credentials(&self, backend: Backend) -> Result<Option<Credentials>, CredentialError> {
+        let Ok(secret) = std::env::var(backend.secret_var()) else {
+            return Ok(None);
+        };
+        let secret = Secret::new(secret);
+        let id = match backend.id_var() {
+            Some(var) => match std::env::var(var) {
+                Ok(id) => Some(id),
+                Err(_) => return Ok(None),
+            },
+            None => None,
+        };
+
+        Ok(Some(Credentials { id, secret }))
+    }
+}
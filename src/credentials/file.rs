@@ -0,0 +1,120 @@
+use std::path::{Path, PathBuf};
+
+use zeroize::Zeroize;
+
+use super::{Backend, CredentialError, CredentialProvider, Credentials, Secret};
+
+/// A provider reading credentials from a file.
+///
+/// The file holds one `NAME=value` pair per line, using the same names as
+/// [`EnvProvider`](super::EnvProvider). Blank lines and lines starting with
+/// `#` are ignored.
+///
+/// ```text
+/// # Baidu
+/// BAIDU_APP_ID=20150630000000001
+/// BAIDU_API_KEY=12345678
+/// ```
+///
+/// On Unix, the file is refused if it is readable or writable by anyone but
+/// its owner.
+pub struct FileProvider {
+    path: PathBuf,
+}
+
+impl FileProvider {
+    /// Create a provider reading from `path`.
+    pub fn new(path: impl Into<PathBuf>) -> FileProvider {
+        FileProvider { path: path.into() }
+    }
+
+    /// Create a provider reading from the default location, which is
+    /// `translator_mangler/credentials` under the user's configuration
+    /// directory.
+    ///
+    /// # Returns
+    /// `None` if the configuration directory cannot be determined.
+    pub fn from_default_path() -> Option<FileProvider> {
+        Self::default_path().map(FileProvider::new)
+    }
+
+    /// Get the default location of the credentials file.
+    ///
+    /// This is `$XDG_CONFIG_HOME/translator_mangler/credentials`, falling back
+    /// to `$HOME/.config` on Unix and `%APPDATA%` on Windows.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(config_dir.join("translator_mangler").join("credentials"))
+    }
+
+    /// Get the path this provider reads from.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    #[cfg(unix)]
+    fn check_permissions(&self, metadata: &std::fs::Metadata) -> Result<(), CredentialError> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            return Err(CredentialError {
+                message: format!(
+                    "{} is accessible by other users (mode {:o}); run `chmod 600` on it",
+                    self.path.display(),
+                    mode
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn check_permissions(&self, _metadata: &std::fs::Metadata) -> Result<(), CredentialError> {
+        Ok(())
+    }
+}
+
+impl CredentialProvider for FileProvider {
+    fn credentials(&self, backend: Backend) -> Result<Option<Credentials>, CredentialError> {
+        let metadata = match std::fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(CredentialError {
+                    message: format!("cannot read {}: {}", self.path.display(), e),
+                });
+            }
+        };
+        self.check_permissions(&metadata)?;
+
+        let mut contents = std::fs::read_to_string(&self.path).map_err(|e| CredentialError {
+            message: format!("cannot read {}: {}", self.path.display(), e),
+        })?;
+        let lookup = |name: &str| {
+            contents
+                .lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .filter_map(|line| line.split_once('='))
+                .find(|(key, _)| key.trim() == name)
+                .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        };
+
+        let secret = lookup(backend.secret_var()).map(Secret::new);
+        let id = backend.id_var().map(lookup);
+        contents.zeroize();
+
+        Ok(match (secret, id) {
+            (Some(secret), None) => Some(Credentials { id: None, secret }),
+            (Some(secret), Some(Some(id))) => Some(Credentials {
+                id: Some(id),
+                secret,
+            }),
+            _ => None,
+        })
+    }
+}
@@ -0,0 +1,155 @@
+//! Credentials for the translation backends, and the places to find them.
+
+use zeroize::Zeroize;
+
+pub mod env;
+pub mod file;
+
+pub use env::EnvProvider;
+pub use file::FileProvider;
+
+/// A secret string, such as an API key.
+///
+/// The contents are zeroized when dropped and never shown by `Debug`.
+#[derive(Clone)]
+pub struct Secret(String);
+
+impl Secret {
+    /// Wrap `value` as a secret.
+    ///
+    /// # Example
+    /// ```rust
+    /// use translator_mangler::credentials::Secret;
+    ///
+    /// let secret = Secret::new("hunter2");
+    /// assert_eq!(secret.expose(), "hunter2");
+    /// assert_eq!(format!("{:?}", secret), "Secret([REDACTED])");
+    /// ```
+    pub fn new(value: impl Into<String>) -> Secret {
+        Secret(value.into())
+    }
+
+    /// Get the secret value. Take care not to log or print it.
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Secret([REDACTED])")
+    }
+}
+
+/// The credentials needed to use a backend.
+#[derive(Clone, Debug)]
+pub struct Credentials {
+    /// The public identifier of the account, such as Baidu's App ID.
+    /// `None` for backends that only need a key.
+    pub id: Option<String>,
+    /// The secret key of the account.
+    pub secret: Secret,
+}
+
+/// The backends that credentials can be looked up for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Baidu,
+    GoogleCloud,
+    Youdao,
+}
+
+impl Backend {
+    /// The human-readable name of the backend.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Baidu => "Baidu",
+            Backend::GoogleCloud => "Google Cloud",
+            Backend::Youdao => "Youdao AI",
+        }
+    }
+
+    /// The variable holding the account identifier, if the backend has one.
+    pub fn id_var(&self) -> Option<&'static str> {
+        match self {
+            Backend::Baidu => Some("BAIDU_APP_ID"),
+            Backend::GoogleCloud => None,
+            Backend::Youdao => Some("YOUDAO_APP_KEY"),
+        }
+    }
+
+    /// The variable holding the secret key.
+    pub fn secret_var(&self) -> &'static str {
+        match self {
+            Backend::Baidu => "BAIDU_API_KEY",
+            Backend::GoogleCloud => "GOOGLE_CLOUD_API_KEY",
+            Backend::Youdao => "YOUDAO_APP_SECRET",
+        }
+    }
+}
+
+/// Errors that may occur when looking up credentials.
+///
+/// The message never contains secret values.
+#[derive(Debug)]
+pub struct CredentialError {
+    /// The message string describing the error.
+    pub message: String,
+}
+
+impl std::fmt::Display for CredentialError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "CredentialError: {}", self.message)
+    }
+}
+
+/// A source of credentials.
+pub trait CredentialProvider {
+    /// Look up the credentials for `backend`.
+    ///
+    /// # Returns
+    /// `Ok(None)` if this source has no credentials for `backend`.
+    fn credentials(&self, backend: Backend) -> Result<Option<Credentials>, CredentialError>;
+}
+
+/// A provider trying each of its providers in order, returning the first
+/// credentials found.
+pub struct ChainProvider {
+    providers: Vec<Box<dyn CredentialProvider>>,
+}
+
+impl ChainProvider {
+    /// Create a chain of `providers`, tried from first to last.
+    ///
+    /// # Example
+    /// ```rust
+    /// use translator_mangler::credentials::{ChainProvider, EnvProvider, FileProvider};
+    ///
+    /// let mut providers: Vec<Box<dyn translator_mangler::credentials::CredentialProvider>> =
+    ///     vec![Box::new(EnvProvider)];
+    /// if let Some(file) = FileProvider::from_default_path() {
+    ///     providers.push(Box::new(file));
+    /// }
+    /// let provider = ChainProvider::new(providers);
+    /// ```
+    pub fn new(providers: Vec<Box<dyn CredentialProvider>>) -> ChainProvider {
+        ChainProvider { providers }
+    }
+}
+
+impl CredentialProvider for ChainProvider {
+    fn credentials(&self, backend: Backend) -> Result<Option<Credentials>, CredentialError> {
+        for provider in &self.providers {
+            if let Some(credentials) = provider.credentials(backend)? {
+                return Ok(Some(credentials));
+            }
+        }
+        Ok(None)
+    }
+}
@@ -1,10 +1,31 @@
 #![warn(clippy::all)]
 
+pub mod credentials;
 mod mangler;
+pub mod metrics;
+pub mod telemetry;
+pub mod testing;
 mod translator;
 
-pub use mangler::{get_random_lang_path, mangle};
+pub use mangler::strategy;
+pub use mangler::{
+    assign_translators, get_lang_path_with_rng, get_random_lang_path, get_seeded_lang_path, mangle,
+    mangle_batch, mangle_best_of, mangle_checkpointed, mangle_lockstep, mangle_mixed,
+    mangle_mixed_batch, mangle_mixed_best_of, mangle_mixed_until, mangle_mixed_with_trace,
+    mangle_until, mangle_with, mangle_with_trace, paraphrase, resume_mangle, resume_mangle_mixed,
+    resume_mangle_until, resume_mangle_with, BestOf, BestOfOutcome, CancellationToken, Candidate,
+    Checkpoint, CheckpointInput, Execution, HopFailure, MangleError, MangleJob, MangleObserver,
+    MangleOptions, MangleOutcome, MangleStep, MangleTrace, MixedHop, MixedJob, Objective,
+    Paraphrase, ParaphraseOptions, PathStrategy, Routing, StopCause, StopReason, StopRule,
+};
+pub use translator::cassette;
+pub use translator::langs::common_lang;
+pub use translator::layer;
+pub use translator::options::{
+    CallContext, Formality, TextFormat, TranslateOption, TranslateOptions,
+};
+pub use translator::signing;
 pub use translator::{
-    baidu::TranslatorBaidu, google::TranslatorGoogleCloud, youdao::TranslatorYoudao,
-    TranslationDirection, TranslationError, Translator,
+    baidu::TranslatorBaidu, google::TranslatorGoogleCloud, youdao::TranslatorYoudao, BatchLimits,
+    TranslationDirection, TranslationError, TranslationErrorKind, Translator,
 };
@@ -0,0 +1,116 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use super::{mangle_mixed, mangle_with, MangleError, MangleOptions, MixedHop};
+use crate::translator::{TranslationDirection, Translator};
+
+/// A text to mangle along its own path with [`mangle_batch`].
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MangleJob {
+    /// The text to mangle.
+    pub text: String,
+    /// The 'path' of languages the text should be mangled in.
+    pub path: Vec<TranslationDirection>,
+}
+
+/// A text to mangle along its own path with [`mangle_mixed_batch`].
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MixedJob {
+    /// The text to mangle.
+    pub text: String,
+    /// The hops of the path with the index of their translator.
+    pub path: Vec<MixedHop>,
+}
+
+/// Mangle the text of every job like [`mangle_with`], running up to
+/// `concurrency` jobs at once on as many threads.
+///
+/// All jobs share `translator`, so a
+/// [`RateLimitLayer`](crate::layer::RateLimitLayer) in it limits the
+/// requests of all jobs together, while the delay of `options` applies
+/// within each job. The deadline and cancellation of `options` stop every
+/// job, and its observer is told about the hops of all jobs as they come.
+///
+/// # Returns
+/// The result of every job, in the order of `jobs`. A failed job does not
+/// stop the others.
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+///
+/// use translator_mangler::layer::{RateLimitLayer, TranslatorBuilder};
+/// use translator_mangler::testing::TranslatorReverseWords;
+/// use translator_mangler::{get_random_lang_path, mangle_batch, MangleJob, MangleOptions};
+///
+/// let translator = TranslatorBuilder::new()
+///     .layer(RateLimitLayer::new(Duration::from_millis(5)))
+///     .build(TranslatorReverseWords::new());
+/// let jobs = ["one two", "three four", "five six"]
+///     .into_iter()
+///     .map(|text| MangleJob {
+///         text: text.to_string(),
+///         path: get_random_lang_path("en", &["zh", "ja"], 3),
+///     })
+///     .collect::<Vec<MangleJob>>();
+///
+/// let results = mangle_batch(&translator, &jobs, 2, &MangleOptions::new());
+/// assert_eq!(results[1].as_ref().unwrap(), "four three");
+/// ```
+pub fn mangle_batch(
+    translator: &dyn Translator,
+    jobs: &[MangleJob],
+    concurrency: usize,
+    options: &MangleOptions,
+) -> Vec<Result<String, MangleError>> {
+    run_pool(jobs, concurrency, |job| {
+        mangle_with(translator, &job.text, &job.path, options)
+    })
+}
+
+/// Mangle the text of every job like [`mangle_batch`], sending each hop to
+/// its own translator among `translators` like
+/// [`mangle_mixed`](super::mangle_mixed).
+///
+/// Each translator keeps its own rate limit, if it has one, over all jobs.
+pub fn mangle_mixed_batch(
+    translators: &[&dyn Translator],
+    jobs: &[MixedJob],
+    concurrency: usize,
+    options: &MangleOptions,
+) -> Vec<Result<String, MangleError>> {
+    run_pool(jobs, concurrency, |job| {
+        mangle_mixed(translators, &job.text, &job.path, options)
+    })
+}
+
+/// Run `work` on every job on up to `concurrency` threads, each taking the
+/// next job left once done with its own.
+pub(super) fn run_pool<J: Sync, R: Send>(
+    jobs: &[J],
+    concurrency: usize,
+    work: impl Fn(&J) -> R + Sync,
+) -> Vec<R> {
+    let next_job = AtomicUsize::new(0);
+    let results = Mutex::new((0..jobs.len()).map(|_| None).collect::<Vec<Option<R>>>());
+    let workers = concurrency.clamp(1, jobs.len().max(1));
+    tracing::debug!(jobs = jobs.len(), workers, "batch started");
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next_job.fetch_add(1, Ordering::Relaxed);
+                let Some(job) = jobs.get(index) else {
+                    break;
+                };
+                let result = work(job);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every job is run"))
+        .collect()
+}
@@ -0,0 +1,236 @@
+use super::mixed::mangle_mixed_with_trace;
+use super::{MangleError, MangleOptions, MangleTrace, MixedHop, StopReason};
+use crate::translator::{TranslationDirection, TranslationError, Translator};
+
+/// What makes a candidate of [`mangle_best_of`] the best.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum Objective {
+    /// The highest [`mangle_score`](crate::metrics::Similarity::mangle_score).
+    MostMangled,
+    /// The lowest [`mangle_score`](crate::metrics::Similarity::mangle_score).
+    LeastMangled,
+    /// The mangle score closest to the given one, from 0 to 100.
+    TargetScore(f64),
+    /// The length in characters closest to the given one.
+    TargetLength(usize),
+}
+
+impl Objective {
+    /// Score the result of `trace`, higher being better.
+    ///
+    /// # Example
+    /// ```rust
+    /// use translator_mangler::testing::TranslatorReverseWords;
+    /// use translator_mangler::{get_random_lang_path, mangle_with_trace, MangleOptions, Objective};
+    ///
+    /// let translator = TranslatorReverseWords::new();
+    /// let langs = get_random_lang_path("en", &["zh"], 2);
+    /// let trace = mangle_with_trace(&translator, "one two", &langs, &MangleOptions::new()).unwrap();
+    ///
+    /// assert_eq!(Objective::LeastMangled.score(&trace), 100.0);
+    /// assert_eq!(Objective::TargetLength(5).score(&trace), -2.0);
+    /// ```
+    pub fn score(&self, trace: &MangleTrace) -> f64 {
+        let mangle_score = || trace.similarity().mangle_score();
+        match self {
+            Objective::MostMangled => mangle_score(),
+            Objective::LeastMangled => 100.0 - mangle_score(),
+            Objective::TargetScore(target) => -(mangle_score() - target).abs(),
+            Objective::TargetLength(target) => {
+                -(trace.result.chars().count() as f64 - *target as f64).abs()
+            }
+        }
+    }
+}
+
+/// How to search for the best of many mangling runs with
+/// [`mangle_best_of`].
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::{BestOf, Objective};
+///
+/// // Keep the most mangled of 10 runs, sending at most 150 requests.
+/// let search = BestOf::new(10, Objective::MostMangled).with_max_requests(150);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct BestOf {
+    candidates: usize,
+    objective: Objective,
+    max_requests: Option<usize>,
+}
+
+impl BestOf {
+    /// Search the best of `candidates` runs by `objective`.
+    pub fn new(candidates: usize, objective: Objective) -> BestOf {
+        BestOf {
+            candidates,
+            objective,
+            max_requests: None,
+        }
+    }
+
+    /// Send at most `requests` translation requests over all candidates,
+    /// skipping those whose path would go over.
+    pub fn with_max_requests(mut self, requests: usize) -> BestOf {
+        self.max_requests = Some(requests);
+        self
+    }
+}
+
+/// A completed run of [`mangle_best_of`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    /// The index of the candidate among all those generated.
+    pub index: usize,
+    /// The trace of the run.
+    pub trace: MangleTrace,
+    /// The score of the run by the objective, higher being better.
+    pub score: f64,
+}
+
+/// The result of [`mangle_best_of`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BestOfOutcome {
+    /// The completed runs, in order.
+    pub candidates: Vec<Candidate>,
+    /// The index of the best run in `candidates`, the first one among
+    /// equals, or `None` if no run was completed.
+    pub best: Option<usize>,
+    /// The number of candidates skipped for going over the budget.
+    pub skipped: usize,
+    /// The candidates that failed, with their index among all those
+    /// generated and the error.
+    pub failed: Vec<(usize, MangleError)>,
+    /// Why the search was stopped before trying every candidate, if it was.
+    pub stopped: Option<StopReason>,
+}
+
+impl BestOfOutcome {
+    /// Get the best run, if any was completed.
+    pub fn best(&self) -> Option<&Candidate> {
+        self.best.map(|index| &self.candidates[index])
+    }
+}
+
+/// Mangle the `original_text` along many paths, one after the other, and
+/// pick the best result by the objective of `search`.
+///
+/// Each path is given by `next_path`, which is called once per candidate.
+/// All runs go through the same `translator` with the same `options`, so a
+/// [`RateLimitLayer`](crate::layer::RateLimitLayer) or delay paces them
+/// together and the deadline or cancellation covers the whole search.
+///
+/// # Returns
+/// Every completed run with its score and the best one. A run stopped by
+/// the deadline or cancellation ends the search with the runs tried so far,
+/// or with the error if there is none. A run failing otherwise is
+/// recorded with its error, and the search goes on with the next
+/// candidate.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::strategy::{PathStrategy, UniformRandom};
+/// use translator_mangler::testing::TranslatorShuffle;
+/// use translator_mangler::{mangle_best_of, BestOf, MangleOptions, Objective};
+///
+/// let translator = TranslatorShuffle::new(42);
+/// let mut rng = fastrand::Rng::with_seed(7);
+/// let mut next_path = || UniformRandom.path("en", &["zh", "ja"], 4, &mut rng);
+/// let search = BestOf::new(5, Objective::MostMangled);
+///
+/// let outcome = mangle_best_of(
+///     &translator,
+///     "the quick brown fox",
+///     &mut next_path,
+///     &search,
+///     &MangleOptions::new(),
+/// )
+/// .unwrap();
+/// assert_eq!(outcome.candidates.len(), 5);
+/// let best = outcome.best().unwrap();
+/// assert!(outcome.candidates.iter().all(|other| other.score <= best.score));
+/// ```
+pub fn mangle_best_of(
+    translator: &dyn Translator,
+    original_text: &str,
+    next_path: &mut dyn FnMut() -> Vec<TranslationDirection>,
+    search: &BestOf,
+    options: &MangleOptions,
+) -> Result<BestOfOutcome, MangleError> {
+    mangle_mixed_best_of(
+        &[translator],
+        original_text,
+        &mut || {
+            Ok(next_path()
+                .into_iter()
+                .map(|direction| MixedHop {
+                    direction,
+                    translator: 0,
+                })
+                .collect())
+        },
+        search,
+        options,
+    )
+}
+
+/// Mangle the `original_text` along many paths like [`mangle_best_of`],
+/// sending each hop to its own translator among `translators` like
+/// [`mangle_mixed`](super::mangle_mixed).
+///
+/// An error from `next_path`, such as one of
+/// [`assign_translators`](super::assign_translators), ends the search.
+pub fn mangle_mixed_best_of(
+    translators: &[&dyn Translator],
+    original_text: &str,
+    next_path: &mut dyn FnMut() -> Result<Vec<MixedHop>, TranslationError>,
+    search: &BestOf,
+    options: &MangleOptions,
+) -> Result<BestOfOutcome, MangleError> {
+    let mut outcome = BestOfOutcome {
+        candidates: Vec::new(),
+        best: None,
+        skipped: 0,
+        failed: Vec::new(),
+        stopped: None,
+    };
+    let mut remaining = search.max_requests;
+    for index in 0..search.candidates {
+        let path = next_path()?;
+        if let Some(remaining) = &mut remaining {
+            if path.len() > *remaining {
+                tracing::debug!(index, hops = path.len(), "candidate over budget skipped");
+                outcome.skipped += 1;
+                continue;
+            }
+            *remaining -= path.len();
+        }
+
+        let trace = match mangle_mixed_with_trace(translators, original_text, &path, options) {
+            Ok(trace) => trace,
+            Err(MangleError::Stopped { reason, .. })
+                if !outcome.candidates.is_empty() || !outcome.failed.is_empty() =>
+            {
+                outcome.stopped = Some(reason);
+                break;
+            }
+            Err(e @ MangleError::Stopped { .. }) => return Err(e),
+            Err(e) => {
+                tracing::warn!(index, error = %e, "candidate failed");
+                outcome.failed.push((index, e));
+                continue;
+            }
+        };
+        let score = search.objective.score(&trace);
+        if outcome.best().is_none_or(|best| score > best.score) {
+            outcome.best = Some(outcome.candidates.len());
+        }
+        outcome.candidates.push(Candidate {
+            index,
+            trace,
+            score,
+        });
+    }
+    Ok(outcome)
+}
@@ -0,0 +1,54 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Instant;
+
+/// A token to stop a running [`mangle_with`](crate::mangle_with) from
+/// another thread, for example from a Ctrl+C handler.
+///
+/// Clones share the same state, so cancelling one cancels all of them.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let handle = token.clone();
+/// std::thread::spawn(move || handle.cancel()).join().unwrap();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    state: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl CancellationToken {
+    /// Create a token that is not cancelled yet.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancel the token, waking up everyone waiting on it.
+    pub fn cancel(&self) {
+        let (cancelled, changed) = &*self.state;
+        *cancelled.lock().unwrap() = true;
+        changed.notify_all();
+    }
+
+    /// Check if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        *self.state.0.lock().unwrap()
+    }
+
+    /// Block until `until` or until the token is cancelled, whichever comes
+    /// first. Returns whether the token is cancelled.
+    pub(crate) fn wait_until(&self, until: Instant) -> bool {
+        let (cancelled, changed) = &*self.state;
+        let mut cancelled = cancelled.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            if *cancelled || now >= until {
+                return *cancelled;
+            }
+            cancelled = changed.wait_timeout(cancelled, until - now).unwrap().0;
+        }
+    }
+}
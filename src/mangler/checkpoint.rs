@@ -0,0 +1,560 @@
+//! Saving the progress of long mangling jobs, to resume them later.
+
+use std::fs::OpenOptions;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use super::batch::run_pool;
+use super::lockstep::run_lockstep;
+use super::mixed::{hops_backend_name, translator_of};
+use super::{run_lazy, Hop, MangleError, MangleJob, MangleOptions, MangleStep, MangleTrace};
+use super::{MixedHop, MixedJob};
+use crate::translator::{TranslationError, TranslationErrorKind, Translator};
+
+/// An input of a [`Checkpoint`], with how far it went along its path.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CheckpointInput {
+    /// The text before mangling.
+    pub original: String,
+    /// The planned hops with the index of their translator.
+    pub path: Vec<MixedHop>,
+    /// The completed hops, in order.
+    pub steps: Vec<MangleStep>,
+    /// The text after the last completed hop.
+    pub text: String,
+}
+
+impl CheckpointInput {
+    /// Check if every hop of the path is completed.
+    pub fn is_done(&self) -> bool {
+        self.steps.len() >= self.path.len()
+    }
+
+    /// Get the trace of the completed hops, timed from the first one to the
+    /// last one, whatever happened in between.
+    pub fn trace(&self) -> MangleTrace {
+        let started_at = self
+            .steps
+            .first()
+            .map_or_else(SystemTime::now, |step| step.started_at);
+        let finished_at = self
+            .steps
+            .last()
+            .map_or(started_at, |step| step.finished_at);
+        MangleTrace {
+            original: self.original.clone(),
+            result: self.text.clone(),
+            steps: self.steps.clone(),
+            started_at,
+            finished_at,
+            elapsed: finished_at
+                .duration_since(started_at)
+                .unwrap_or(Duration::ZERO),
+            latency: self.steps.iter().map(|step| step.latency).sum(),
+            retries: self.steps.iter().map(|step| step.retries).sum(),
+        }
+    }
+}
+
+/// The state of a mangling job over many inputs, saved to a file as it goes
+/// by [`mangle_checkpointed`].
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::{get_seeded_lang_path, Checkpoint, MangleJob};
+///
+/// let jobs = ["one two", "three four"]
+///     .into_iter()
+///     .map(|text| MangleJob {
+///         text: text.to_string(),
+///         path: get_seeded_lang_path("en", &["zh", "ja"], 10, 42),
+///     })
+///     .collect::<Vec<MangleJob>>();
+/// let checkpoint = Checkpoint::new(&jobs).with_seed(42);
+/// assert!(!checkpoint.is_done());
+/// ```
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Checkpoint {
+    /// The seed the paths were planned with, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// Every input of the job, in order.
+    pub inputs: Vec<CheckpointInput>,
+}
+
+impl Checkpoint {
+    /// Create the checkpoint of a job mangling the text of every job along
+    /// its path, before any hop.
+    pub fn new(jobs: &[MangleJob]) -> Checkpoint {
+        Checkpoint::new_mixed(
+            &jobs
+                .iter()
+                .map(|job| MixedJob {
+                    text: job.text.clone(),
+                    path: job
+                        .path
+                        .iter()
+                        .map(|direction| MixedHop {
+                            direction: direction.clone(),
+                            translator: 0,
+                        })
+                        .collect(),
+                })
+                .collect::<Vec<MixedJob>>(),
+        )
+    }
+
+    /// Create the checkpoint of a job like [`new`](Checkpoint::new), with
+    /// each hop sent to its own translator.
+    pub fn new_mixed(jobs: &[MixedJob]) -> Checkpoint {
+        Checkpoint {
+            seed: None,
+            inputs: jobs
+                .iter()
+                .map(|job| CheckpointInput {
+                    original: job.text.clone(),
+                    path: job.path.clone(),
+                    steps: Vec::new(),
+                    text: job.text.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Remember the seed the paths were planned with.
+    pub fn with_seed(mut self, seed: u64) -> Checkpoint {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Load a checkpoint saved at `path`.
+    ///
+    /// A hop cut short by the process dying while appending it is left out,
+    /// as if it had not been completed.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Checkpoint> {
+        let file = std::io::BufReader::new(std::fs::File::open(path)?);
+        let mut lines = file.lines().peekable();
+        let header = lines
+            .next()
+            .ok_or_else(|| invalid_data("the checkpoint is empty".to_string()))??;
+        let header: Header = serde_json::from_str(&header)?;
+        let mut checkpoint = Checkpoint {
+            seed: header.seed,
+            inputs: header
+                .inputs
+                .into_iter()
+                .map(|input| CheckpointInput {
+                    text: input.original.clone(),
+                    original: input.original,
+                    path: input.path,
+                    steps: Vec::new(),
+                })
+                .collect(),
+        };
+        while let Some(line) = lines.next() {
+            let record = match serde_json::from_str::<Record<MangleStep>>(&line?) {
+                Ok(record) => record,
+                Err(_) if lines.peek().is_none() => break,
+                Err(e) => return Err(e.into()),
+            };
+            let input = checkpoint
+                .inputs
+                .get_mut(record.input)
+                .filter(|input| !input.is_done())
+                .ok_or_else(|| invalid_data(format!("unexpected hop of input {}", record.input)))?;
+            input.text = record.step.output.clone();
+            input.steps.push(record.step);
+        }
+        Ok(checkpoint)
+    }
+
+    /// Save the checkpoint at `path`.
+    ///
+    /// The checkpoint is written as JSON lines: the planned paths first,
+    /// then one line per completed hop, so that a running job only appends
+    /// to it. It is written to a temporary file next to `path` first, so a
+    /// process dying while saving leaves the previous one intact.
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&temp)?);
+        let header = Header {
+            seed: self.seed,
+            inputs: self
+                .inputs
+                .iter()
+                .map(|input| Planned {
+                    original: input.original.clone(),
+                    path: input.path.clone(),
+                })
+                .collect(),
+        };
+        write_line(&mut file, &header)?;
+        for (index, input) in self.inputs.iter().enumerate() {
+            for step in &input.steps {
+                write_line(&mut file, &Record { input: index, step })?;
+            }
+        }
+        file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        std::fs::rename(&temp, path)
+    }
+
+    /// Check if every input went through its whole path.
+    pub fn is_done(&self) -> bool {
+        self.inputs.iter().all(CheckpointInput::is_done)
+    }
+}
+
+/// The translators the hops of a checkpointed job are sent to.
+#[derive(Clone, Copy)]
+pub enum Routing<'a> {
+    /// Every hop goes to the same translator, whatever the translator it
+    /// was planned for.
+    Single(&'a dyn Translator),
+    /// Each hop goes to the translator it was planned for among these. To
+    /// resume on other backends, give their translators in the same order
+    /// as those the job was started with.
+    Mixed(&'a [&'a dyn Translator]),
+}
+
+impl<'a> Routing<'a> {
+    /// Get the translator of `hop`.
+    fn translator(&self, hop: &MixedHop) -> Result<&'a dyn Translator, TranslationError> {
+        match self {
+            Routing::Single(translator) => Ok(*translator),
+            Routing::Mixed(translators) => translator_of(translators, hop),
+        }
+    }
+
+    /// Get the hops of `path` with their translators.
+    fn hops<'p>(&self, path: &'p [MixedHop]) -> Result<Vec<Hop<'p>>, TranslationError>
+    where
+        'a: 'p,
+    {
+        path.iter()
+            .map(|hop| Ok((self.translator(hop)?, &hop.direction)))
+            .collect()
+    }
+
+    /// Get `path` with the index of the translator each hop goes to.
+    fn routed(&self, path: &[MixedHop]) -> Vec<MixedHop> {
+        path.iter()
+            .map(|hop| MixedHop {
+                direction: hop.direction.clone(),
+                translator: match self {
+                    Routing::Single(_) => 0,
+                    Routing::Mixed(_) => hop.translator,
+                },
+            })
+            .collect()
+    }
+}
+
+/// How the inputs of a checkpointed job are run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Execution {
+    /// One input after the other, on the calling thread.
+    Sequential,
+    /// Up to this many inputs at once on as many threads, like
+    /// [`mangle_batch`](super::mangle_batch).
+    Pool(usize),
+    /// Every input through each hop together, like
+    /// [`mangle_lockstep`](super::mangle_lockstep). Inputs left at the same
+    /// point of the same path go together, so a job whose inputs share a
+    /// path stays in lockstep when resumed.
+    Lockstep,
+}
+
+/// Mangle every input of `checkpoint` along the rest of its path, sending
+/// each hop as given by `routing` and running the inputs as given by
+/// `execution`, and saving every hop to the checkpoint at `file` once
+/// completed.
+///
+/// The checkpoint is saved at `file` anew first, then each completed hop
+/// is appended to it, so saving costs the same at every hop however long
+/// the job is. Completed hops are never sent again, so calling this again
+/// with a checkpoint loaded from `file` resumes the job exactly where it
+/// stopped, with the same translators or others. Observers are told about
+/// the hops left of each input.
+///
+/// # Returns
+/// The result of every input, in order. A failed input does not stop the
+/// others, and its failed hop is counted from the start of the input. If
+/// the checkpoint cannot be saved, the inputs fail with a
+/// [`MangleError::Failed`] of kind [`Other`](TranslationErrorKind::Other),
+/// all of them if it cannot be saved at the start.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::testing::TranslatorReverseWords;
+/// use translator_mangler::{
+///     get_seeded_lang_path, mangle_checkpointed, Checkpoint, Execution, MangleJob,
+///     MangleOptions, Routing,
+/// };
+///
+/// let file = std::env::temp_dir().join("translator_mangler_doc_checkpoint.jsonl");
+/// let jobs = [MangleJob {
+///     text: "one two".to_string(),
+///     path: get_seeded_lang_path("en", &["zh", "ja"], 3, 42),
+/// }];
+/// let mut checkpoint = Checkpoint::new(&jobs);
+///
+/// let translator = TranslatorReverseWords::new();
+/// let results = mangle_checkpointed(
+///     Routing::Single(&translator),
+///     &mut checkpoint,
+///     &file,
+///     Execution::Sequential,
+///     &MangleOptions::new(),
+/// );
+/// assert_eq!(results[0].as_ref().unwrap(), "two one");
+/// assert_eq!(Checkpoint::load(&file).unwrap(), checkpoint);
+/// # std::fs::remove_file(file).unwrap();
+/// ```
+pub fn mangle_checkpointed(
+    routing: Routing,
+    checkpoint: &mut Checkpoint,
+    file: impl AsRef<Path>,
+    execution: Execution,
+    options: &MangleOptions,
+) -> Vec<Result<String, MangleError>> {
+    let log = match Log::create(checkpoint, file.as_ref()) {
+        Ok(log) => log,
+        Err(e) => return failed_all(checkpoint, e),
+    };
+    match execution {
+        Execution::Sequential => {
+            let inputs = checkpoint.inputs.len();
+            let state = Mutex::new(checkpoint);
+            (0..inputs)
+                .map(|index| run_input(&state, &log, index, options, routing))
+                .collect()
+        }
+        Execution::Pool(concurrency) => {
+            let indices = (0..checkpoint.inputs.len()).collect::<Vec<usize>>();
+            let state = Mutex::new(checkpoint);
+            run_pool(&indices, concurrency, |&index| {
+                run_input(&state, &log, index, options, routing)
+            })
+        }
+        Execution::Lockstep => run_groups(checkpoint, &log, options, routing),
+    }
+}
+
+/// Run the inputs of `checkpoint` in lockstep, in groups of inputs left
+/// at the same point of the same path, appending every completed hop to
+/// `log`.
+fn run_groups(
+    checkpoint: &mut Checkpoint,
+    log: &Log,
+    options: &MangleOptions,
+    routing: Routing,
+) -> Vec<Result<String, MangleError>> {
+    let mut results = (0..checkpoint.inputs.len())
+        .map(|_| None)
+        .collect::<Vec<Option<Result<String, MangleError>>>>();
+    let mut groups: Vec<(Vec<MixedHop>, Vec<usize>)> = Vec::new();
+    for (index, input) in checkpoint.inputs.iter().enumerate() {
+        if input.is_done() {
+            results[index] = Some(Ok(input.text.clone()));
+            continue;
+        }
+        let remaining = routing.routed(&input.path[input.steps.len()..]);
+        match groups.iter_mut().find(|(path, _)| *path == remaining) {
+            Some((_, members)) => members.push(index),
+            None => groups.push((remaining, vec![index])),
+        }
+    }
+
+    for (path, members) in groups {
+        let hops = match routing.hops(&path) {
+            Ok(hops) => hops,
+            Err(e) => {
+                for &index in &members {
+                    results[index] = Some(Err(MangleError::Failed(e.clone())));
+                }
+                continue;
+            }
+        };
+        tracing::debug!(
+            inputs = members.len(),
+            hops = hops.len(),
+            "checkpointed group started"
+        );
+        let done = members
+            .iter()
+            .map(|&index| checkpoint.inputs[index].steps.len())
+            .collect::<Vec<usize>>();
+        let texts = members
+            .iter()
+            .map(|&index| checkpoint.inputs[index].text.clone())
+            .collect::<Vec<String>>();
+        let texts = texts.iter().map(String::as_str).collect::<Vec<&str>>();
+        let group_results = run_lockstep(&hops, &texts, options, &mut |text, step| {
+            let input = &mut checkpoint.inputs[members[text]];
+            input.text = step.output.clone();
+            input.steps.push(step.clone());
+            log.append(members[text], step)
+        });
+        for ((&index, done), result) in members.iter().zip(done).zip(group_results) {
+            let result = result.map_err(|e| from_start(e, &checkpoint.inputs[index], done));
+            results[index] = Some(result);
+        }
+    }
+    results
+        .into_iter()
+        .map(|result| result.expect("every input is run"))
+        .collect()
+}
+
+/// Run input `index` of the checkpoint in `state` along the rest of its
+/// path, appending every completed hop to `log`.
+fn run_input(
+    state: &Mutex<&mut Checkpoint>,
+    log: &Log,
+    index: usize,
+    options: &MangleOptions,
+    routing: Routing,
+) -> Result<String, MangleError> {
+    let (done, remaining, text) = {
+        let checkpoint = state.lock().unwrap();
+        let input = &checkpoint.inputs[index];
+        if input.is_done() {
+            return Ok(input.text.clone());
+        }
+        let done = input.steps.len();
+        (done, input.path[done..].to_vec(), input.text.clone())
+    };
+    let hops = routing.hops(&remaining)?;
+    let translators = hops
+        .iter()
+        .map(|(translator, _)| *translator)
+        .collect::<Vec<&dyn Translator>>();
+    tracing::debug!(index, hops = hops.len(), "checkpointed input started");
+
+    run_lazy(
+        hops_backend_name(&hops),
+        &translators,
+        hops.len(),
+        &text,
+        options,
+        &mut |hop, _| {
+            if hop > 0 {
+                let checkpoint = state.lock().unwrap();
+                let step = checkpoint.inputs[index].steps.last();
+                log.append(index, step.expect("a hop was completed"))?;
+            }
+            Ok(hops
+                .get(hop)
+                .map(|(translator, direction)| (*translator, (*direction).clone())))
+        },
+        &mut |step| {
+            let mut checkpoint = state.lock().unwrap();
+            let input = &mut checkpoint.inputs[index];
+            input.text = step.output.clone();
+            input.steps.push(step.clone());
+        },
+    )
+    .map_err(|e| from_start(e, &state.lock().unwrap().inputs[index], done))
+}
+
+/// Count the hops of a failure of `input` from its start, `done` hops
+/// having been completed before the run.
+fn from_start(e: MangleError, input: &CheckpointInput, done: usize) -> MangleError {
+    match e {
+        MangleError::HopFailed(mut failure) => {
+            failure.index += done;
+            failure.trace = input.trace();
+            failure.remaining = input.path[failure.index..].to_vec();
+            MangleError::HopFailed(failure)
+        }
+        e => e,
+    }
+}
+
+/// Fail every input of `checkpoint` with `error`.
+fn failed_all(
+    checkpoint: &Checkpoint,
+    error: TranslationError,
+) -> Vec<Result<String, MangleError>> {
+    checkpoint
+        .inputs
+        .iter()
+        .map(|_| Err(MangleError::Failed(error.clone())))
+        .collect()
+}
+
+/// A checkpoint file completed hops are appended to while a job runs.
+struct Log {
+    file: Mutex<std::fs::File>,
+}
+
+impl Log {
+    /// Save `checkpoint` at `path` anew and open it to append hops.
+    fn create(checkpoint: &Checkpoint, path: &Path) -> Result<Log, TranslationError> {
+        checkpoint.save(path).map_err(checkpoint_error)?;
+        let file = OpenOptions::new()
+            .append(true)
+            .open(path)
+            .map_err(checkpoint_error)?;
+        Ok(Log {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Append the completed `step` of input `input`, in a single write.
+    fn append(&self, input: usize, step: &MangleStep) -> Result<(), TranslationError> {
+        let mut line =
+            serde_json::to_vec(&Record { input, step }).map_err(|e| checkpoint_error(e.into()))?;
+        line.push(b'\n');
+        self.file
+            .lock()
+            .unwrap()
+            .write_all(&line)
+            .map_err(checkpoint_error)
+    }
+}
+
+/// The first line of a checkpoint file.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Header {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    inputs: Vec<Planned>,
+}
+
+/// An input of a checkpoint file, as planned.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Planned {
+    original: String,
+    path: Vec<MixedHop>,
+}
+
+/// A line of a checkpoint file with a completed hop of an input.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Record<S> {
+    input: usize,
+    step: S,
+}
+
+/// Write `value` to `file` as a line of JSON.
+fn write_line(file: &mut impl Write, value: &impl serde::Serialize) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *file, value)?;
+    file.write_all(b"\n")
+}
+
+/// Get an error for a checkpoint file that cannot be read as one.
+fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Get the translation error of a failure to save a checkpoint.
+fn checkpoint_error(e: std::io::Error) -> TranslationError {
+    TranslationError {
+        kind: TranslationErrorKind::Other,
+        message: format!("CHECKPOINT ERR: {}", e),
+    }
+}
@@ -0,0 +1,211 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Instant, SystemTime};
+
+use super::mixed::backend_name;
+use super::{single_translator_path, trace, warn_ignored_options};
+use super::{Hop, HopFailure, MangleError, MangleOptions, MangleStep};
+use crate::telemetry;
+use crate::translator::{TranslationDirection, TranslationError, Translator};
+
+/// Mangle every text of `texts` with `translator` along the same
+/// `lang_path`, moving all texts through each hop together.
+///
+/// Each hop sends the texts still going in batches with
+/// [`translate_many`](Translator::translate_many), as large as the
+/// [`batch_limits`](Translator::batch_limits) of `translator` allow, so that
+/// many texts over a path of `R` hops take about `R` requests instead of
+/// one per text and hop. The delay of `options` is waited before each
+/// request, and the deadline and cancellation are checked meanwhile.
+///
+/// Observers are told once about the start of each hop, and about the step
+/// or error of every text in it.
+///
+/// # Returns
+/// The result of every text, in the order of `texts`. A text whose
+/// translation failed drops out with a [`MangleError::HopFailed`] while the
+/// others go on, and can be continued alone with
+/// [`resume_mangle`](super::resume_mangle). If
+/// the run is stopped, every text still going ends with
+/// [`MangleError::Stopped`] and its text after its last completed hop.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::testing::TranslatorReverseWords;
+/// use translator_mangler::{get_random_lang_path, mangle_lockstep, MangleOptions};
+///
+/// let translator = TranslatorReverseWords::new();
+/// let langs = get_random_lang_path("en", &["zh", "ja"], 3);
+///
+/// let results = mangle_lockstep(&translator, &["one two", "three four"], &langs, &MangleOptions::new());
+/// assert_eq!(results[0].as_ref().unwrap(), "two one");
+/// assert_eq!(results[1].as_ref().unwrap(), "four three");
+/// ```
+pub fn mangle_lockstep(
+    translator: &dyn Translator,
+    texts: &[&str],
+    lang_path: &[TranslationDirection],
+    options: &MangleOptions,
+) -> Vec<Result<String, MangleError>> {
+    let hops = lang_path
+        .iter()
+        .map(|direction| (translator, direction))
+        .collect::<Vec<Hop>>();
+    let path = single_translator_path(lang_path);
+    run_lockstep(&hops, texts, options, &mut |_, _| Ok(()))
+        .into_iter()
+        .map(|result| result.map_err(|e| e.with_path(&path)))
+        .collect()
+}
+
+/// Run the lockstep mangling loop over `lang_path`, passing every completed
+/// hop to `on_step` with the index of its text.
+///
+/// Each hop is sent to its own translator, with its language codes. A text
+/// whose step `on_step` fails on drops out with the error. Failed hops are
+/// left with no hops remaining, for the caller to set.
+pub(super) fn run_lockstep(
+    lang_path: &[Hop],
+    texts: &[&str],
+    options: &MangleOptions,
+    on_step: &mut dyn FnMut(usize, &MangleStep) -> Result<(), TranslationError>,
+) -> Vec<Result<String, MangleError>> {
+    let rounds = lang_path.len();
+    let translators = lang_path
+        .iter()
+        .map(|(translator, _)| *translator)
+        .collect::<Vec<&dyn Translator>>();
+    let span = tracing::info_span!(
+        "mangle_lockstep",
+        backend = backend_name(&translators),
+        rounds,
+        texts = texts.len(),
+    );
+    let _entered = span.enter();
+    warn_ignored_options(&translators, options);
+
+    let mut current = texts
+        .iter()
+        .map(|text| text.to_string())
+        .collect::<Vec<String>>();
+    let run_started_at = SystemTime::now();
+    let mut steps = (0..texts.len())
+        .map(|_| Vec::new())
+        .collect::<Vec<Vec<MangleStep>>>();
+    let mut results = (0..texts.len())
+        .map(|_| None)
+        .collect::<Vec<Option<Result<String, MangleError>>>>();
+
+    'hops: for (index, &(translator, direction)) in lang_path.iter().enumerate() {
+        let active = (0..texts.len())
+            .filter(|&text| results[text].is_none())
+            .collect::<Vec<usize>>();
+        if active.is_empty() {
+            break;
+        }
+        let _hop = tracing::info_span!(
+            "hop",
+            index,
+            from = %direction.from_lang,
+            to = %direction.to_lang,
+            texts = active.len(),
+        )
+        .entered();
+        if let Some(observer) = &options.observer {
+            observer.on_step_start(index, rounds, direction);
+        }
+
+        let native = TranslationDirection {
+            from_lang: translator.native_lang(&direction.from_lang).to_string(),
+            to_lang: translator.native_lang(&direction.to_lang).to_string(),
+        };
+        let inputs = active
+            .iter()
+            .map(|&text| current[text].clone())
+            .collect::<Vec<String>>();
+        let inputs = inputs.iter().map(String::as_str).collect::<Vec<&str>>();
+        for chunk in translator.batch_limits().chunks(&inputs) {
+            if let Err(reason) = options.wait() {
+                tracing::info!(%reason, "mangling stopped");
+                for (text, result) in results.iter_mut().enumerate() {
+                    if result.is_none() {
+                        *result = Some(Err(MangleError::Stopped {
+                            reason,
+                            text: std::mem::take(&mut current[text]),
+                            hops: steps[text].len(),
+                        }));
+                    }
+                }
+                break 'hops;
+            }
+
+            let started_at = SystemTime::now();
+            let start = Instant::now();
+            let retries = Arc::new(AtomicU64::new(0));
+            let request_options = options.request_options(index, &retries);
+            let outputs =
+                translator.translate_many(&inputs[chunk.clone()], &native, &request_options);
+            let finished_at = SystemTime::now();
+            let latency = start.elapsed();
+            let retries = retries.load(Ordering::Relaxed);
+
+            for (&text, output) in active[chunk.clone()].iter().zip(outputs) {
+                match output {
+                    Ok(output) => {
+                        let step = MangleStep {
+                            direction: direction.clone(),
+                            input: std::mem::replace(&mut current[text], output.clone()),
+                            output,
+                            backend: translator.name().to_string(),
+                            started_at,
+                            finished_at,
+                            latency,
+                            retries,
+                        };
+                        if let Some(observer) = &options.observer {
+                            observer.on_step_done(index, rounds, &step);
+                        }
+                        if let Err(e) = on_step(text, &step) {
+                            results[text] = Some(Err(MangleError::Failed(e)));
+                        }
+                        steps[text].push(step);
+                    }
+                    Err(e) => {
+                        if let Some(observer) = &options.observer {
+                            observer.on_error(index, &e);
+                        }
+                        let failure = HopFailure {
+                            error: e,
+                            index,
+                            direction: direction.clone(),
+                            trace: trace::build(
+                                texts[text],
+                                current[text].clone(),
+                                std::mem::take(&mut steps[text]),
+                                run_started_at,
+                            ),
+                            remaining: Vec::new(),
+                            leg_ends: Vec::new(),
+                        };
+                        results[text] = Some(Err(MangleError::HopFailed(Box::new(failure))));
+                    }
+                }
+            }
+        }
+    }
+
+    results
+        .into_iter()
+        .zip(current)
+        .zip(steps)
+        .map(|((result, text), steps)| {
+            let hops = match &result {
+                Some(Err(MangleError::HopFailed(failure))) => failure.index,
+                _ => steps.len(),
+            };
+            let result = result.unwrap_or(Ok(text));
+            telemetry::record_mangle(result.is_ok(), hops);
+            result
+        })
+        .collect()
+}
@@ -0,0 +1,192 @@
+use super::trace::record;
+use super::{run, Hop, MangleError, MangleOptions, MangleTrace};
+use crate::translator::{TranslationDirection, TranslationError, TranslationErrorKind, Translator};
+
+/// A hop of a path translated by one of several translators.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MixedHop {
+    /// The languages of the hop.
+    pub direction: TranslationDirection,
+    /// The index of the translator of the hop.
+    pub translator: usize,
+}
+
+/// Check if `translator` supports `direction`, in its own codes.
+fn supports(translator: &dyn Translator, direction: &TranslationDirection) -> bool {
+    translator.is_pair_supported(
+        translator.native_lang(&direction.from_lang),
+        translator.native_lang(&direction.to_lang),
+    )
+}
+
+/// Choose a translator for each hop of `lang_path` at random among the
+/// `translators` supporting it, as told by [`Translator::is_pair_supported`].
+///
+/// # Arguments
+/// * `lang_path` - The path to translate.
+/// * `translators` - The translators to choose from.
+/// * `rng` - The random number generator to use.
+///
+/// # Returns
+/// The hops with the index of their translator in `translators`, or an
+/// error if no translator supports some hop.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::{
+///     assign_translators, get_random_lang_path, Translator, TranslatorBaidu, TranslatorYoudao,
+/// };
+///
+/// let baidu = TranslatorBaidu::new("[YOUR_APP_ID]", "[YOUR_API_KEY]");
+/// let youdao = TranslatorYoudao::new("[YOUR_APP_KEY]", "[YOUR_APP_SECRET]");
+/// let translators: [&dyn Translator; 2] = [&baidu, &youdao];
+///
+/// let langs = get_random_lang_path("en", &["zh", "ja", "wyw"], 10);
+/// let hops = assign_translators(&langs, &translators, &mut fastrand::Rng::new()).unwrap();
+/// for hop in hops.iter().filter(|hop| hop.direction.to_lang == "wyw") {
+///     // Only Baidu translates to Classical Chinese.
+///     assert_eq!(hop.translator, 0);
+/// }
+/// ```
+pub fn assign_translators(
+    lang_path: &[TranslationDirection],
+    translators: &[&dyn Translator],
+    rng: &mut fastrand::Rng,
+) -> Result<Vec<MixedHop>, TranslationError> {
+    lang_path
+        .iter()
+        .map(|direction| {
+            let candidates = (0..translators.len())
+                .filter(|index| supports(translators[*index], direction))
+                .collect::<Vec<usize>>();
+            if candidates.is_empty() {
+                return Err(TranslationError {
+                    kind: TranslationErrorKind::UnsupportedLanguage,
+                    message: format!(
+                        "No translator supports {} -> {}",
+                        direction.from_lang, direction.to_lang
+                    ),
+                });
+            }
+            Ok(MixedHop {
+                direction: direction.clone(),
+                translator: candidates[rng.usize(..candidates.len())],
+            })
+        })
+        .collect()
+}
+
+/// Pair every hop with its translator.
+fn route<'a>(
+    translators: &[&'a dyn Translator],
+    path: &'a [MixedHop],
+) -> Result<Vec<Hop<'a>>, TranslationError> {
+    path.iter()
+        .map(|hop| Ok((translator_of(translators, hop)?, &hop.direction)))
+        .collect()
+}
+
+/// Get the translator of `hop` among `translators`.
+pub(super) fn translator_of<'a>(
+    translators: &[&'a dyn Translator],
+    hop: &MixedHop,
+) -> Result<&'a dyn Translator, TranslationError> {
+    translators
+        .get(hop.translator)
+        .copied()
+        .ok_or_else(|| TranslationError {
+            kind: TranslationErrorKind::Other,
+            message: format!(
+                "No translator {} among {} translators",
+                hop.translator,
+                translators.len()
+            ),
+        })
+}
+
+/// Get the name of `translators` for logs.
+pub(super) fn backend_name<'a>(translators: &[&'a dyn Translator]) -> &'a str {
+    match translators.first() {
+        Some(&first) if translators.iter().all(|other| other.name() == first.name()) => {
+            first.name()
+        }
+        _ => "mixed",
+    }
+}
+
+/// Get the name of the translators of `hops` for logs.
+pub(super) fn hops_backend_name<'a>(hops: &[Hop<'a>]) -> &'a str {
+    backend_name(
+        &hops
+            .iter()
+            .map(|(translator, _)| *translator)
+            .collect::<Vec<&dyn Translator>>(),
+    )
+}
+
+/// Mangle the `original_text` like [`mangle_with`](super::mangle_with),
+/// sending each hop of `path` to its own translator among `translators`.
+///
+/// Each translator gets the codes it uses for the languages of its hops,
+/// as given by [`Translator::native_lang`], so paths may be written with
+/// the codes of any backend.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::testing::{TranslatorPigLatin, TranslatorReverseWords};
+/// use translator_mangler::{
+///     mangle_mixed, MangleOptions, MixedHop, TranslationDirection, Translator,
+/// };
+///
+/// let reverse = TranslatorReverseWords::new();
+/// let pig_latin = TranslatorPigLatin::new();
+/// let translators: [&dyn Translator; 2] = [&reverse, &pig_latin];
+/// let hop = |from: &str, to: &str, translator| MixedHop {
+///     direction: TranslationDirection {
+///         from_lang: from.to_string(),
+///         to_lang: to.to_string(),
+///     },
+///     translator,
+/// };
+///
+/// let path = [hop("en", "zh", 0), hop("zh", "en", 1)];
+/// let result = mangle_mixed(&translators, "one two", &path, &MangleOptions::new()).unwrap();
+/// assert_eq!(result, "otway oneway");
+/// ```
+pub fn mangle_mixed(
+    translators: &[&dyn Translator],
+    original_text: &str,
+    path: &[MixedHop],
+    options: &MangleOptions,
+) -> Result<String, MangleError> {
+    let hops = route(translators, path)?;
+    run(
+        hops_backend_name(&hops),
+        &hops,
+        original_text,
+        options,
+        &mut |_| {},
+    )
+    .map_err(|e| e.with_path(path))
+}
+
+/// Mangle the `original_text` like [`mangle_mixed`], recording every hop
+/// like [`mangle_with_trace`](super::mangle_with_trace).
+pub fn mangle_mixed_with_trace(
+    translators: &[&dyn Translator],
+    original_text: &str,
+    path: &[MixedHop],
+    options: &MangleOptions,
+) -> Result<MangleTrace, MangleError> {
+    let hops = route(translators, path)?;
+    record(original_text, |on_step| {
+        run(
+            hops_backend_name(&hops),
+            &hops,
+            original_text,
+            options,
+            on_step,
+        )
+    })
+    .map_err(|e| e.with_path(path))
+}
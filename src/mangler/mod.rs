@@ -1,9 +1,47 @@
+mod batch;
+mod best;
+mod cancel;
+mod checkpoint;
+mod lockstep;
+mod mixed;
+mod observer;
+mod paraphrase;
+mod resume;
+pub mod strategy;
+mod trace;
+mod until;
+
+pub use batch::{mangle_batch, mangle_mixed_batch, MangleJob, MixedJob};
+pub use best::{mangle_best_of, mangle_mixed_best_of, BestOf, BestOfOutcome, Candidate, Objective};
+pub use cancel::CancellationToken;
+pub use checkpoint::{mangle_checkpointed, Checkpoint, CheckpointInput, Execution, Routing};
+pub use lockstep::mangle_lockstep;
+pub use mixed::{assign_translators, mangle_mixed, mangle_mixed_with_trace, MixedHop};
+pub use observer::MangleObserver;
+pub use paraphrase::{paraphrase, Paraphrase, ParaphraseOptions};
+pub use resume::{resume_mangle, resume_mangle_mixed, resume_mangle_with};
+pub use strategy::PathStrategy;
+pub use trace::{mangle_with_trace, MangleStep, MangleTrace};
+pub use until::{
+    mangle_mixed_until, mangle_until, resume_mangle_until, MangleOutcome, StopCause, StopRule,
+};
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::telemetry;
+use crate::translator::options::{CallContext, TranslateOptions};
 use crate::translator::{TranslationDirection, TranslationError, Translator};
+use strategy::UniformRandom;
 
 /// Get a random 'path' of languages, starting from and ending with
 /// `original_language`. The nodes in the way is randomly selected from
 /// `lang_bank`, with a total of `rounds` nodes.
 ///
+/// Nothing is checked; use a [`PathPlan`](strategy::PathPlan) to get an
+/// error for impossible requests and to add constraints.
+///
 /// # Arguments
 /// * `original_lang` - The original language to translate to.
 /// * `lang_bank` - The list of languages to choose from.
@@ -11,6 +49,8 @@ use crate::translator::{TranslationDirection, TranslationError, Translator};
 ///
 /// # Example
 /// ```rust
+/// use translator_mangler::get_random_lang_path;
+///
 /// let lang_bank = vec![
 ///    "en", "zh", "fr"
 /// ];
@@ -32,39 +72,317 @@ pub fn get_random_lang_path(
     lang_bank: &[&str],
     rounds: usize,
 ) -> Vec<TranslationDirection> {
-    // Fast-fail
-    if lang_bank.is_empty() {
-        return vec![TranslationDirection {
-            from_lang: original_lang.to_string(),
-            to_lang: original_lang.to_string(),
-        }];
-    }
-
-    let mut prev_lang = original_lang;
-    let mut langs: Vec<TranslationDirection> = Vec::with_capacity(rounds);
-    for i in 0..rounds {
-        // Choose a random language from the lang_bank,
-        // or original_lang if we have reached the last round.
-        let next_lang = if i == (rounds - 1) {
-            original_lang
-        } else {
-            let mut l = lang_bank[fastrand::usize(..lang_bank.len())];
-            // Make sure we don't get the same language twice
-            // to avoid request waste.
-            while l == prev_lang {
-                l = lang_bank[fastrand::usize(..lang_bank.len())];
+    get_lang_path_with_rng(original_lang, lang_bank, rounds, &mut fastrand::Rng::new())
+}
+
+/// Get a random 'path' of languages like [`get_random_lang_path`], always
+/// the same for the same `seed`.
+///
+/// # Arguments
+/// * `original_lang` - The original language to translate to.
+/// * `lang_bank` - The list of languages to choose from.
+/// * `rounds` - The number of pairs to get.
+/// * `seed` - The seed of the random choices.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::get_seeded_lang_path;
+///
+/// let pairs = get_seeded_lang_path("en", &["en", "zh", "fr"], 5, 42);
+/// assert_eq!(pairs, get_seeded_lang_path("en", &["en", "zh", "fr"], 5, 42));
+/// ```
+pub fn get_seeded_lang_path(
+    original_lang: &str,
+    lang_bank: &[&str],
+    rounds: usize,
+    seed: u64,
+) -> Vec<TranslationDirection> {
+    get_lang_path_with_rng(
+        original_lang,
+        lang_bank,
+        rounds,
+        &mut fastrand::Rng::with_seed(seed),
+    )
+}
+
+/// Get a random 'path' of languages like [`get_random_lang_path`], making
+/// the random choices with `rng`.
+///
+/// # Arguments
+/// * `original_lang` - The original language to translate to.
+/// * `lang_bank` - The list of languages to choose from.
+/// * `rounds` - The number of pairs to get.
+/// * `rng` - The random number generator to use.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::get_lang_path_with_rng;
+///
+/// let mut rng = fastrand::Rng::with_seed(42);
+/// let first = get_lang_path_with_rng("en", &["en", "zh", "fr"], 5, &mut rng);
+/// let second = get_lang_path_with_rng("en", &["en", "zh", "fr"], 5, &mut rng);
+/// ```
+pub fn get_lang_path_with_rng(
+    original_lang: &str,
+    lang_bank: &[&str],
+    rounds: usize,
+    rng: &mut fastrand::Rng,
+) -> Vec<TranslationDirection> {
+    UniformRandom.path(original_lang, lang_bank, rounds, rng)
+}
+
+/// Options controlling a run of [`mangle_with`].
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use translator_mangler::{CancellationToken, MangleOptions};
+///
+/// let token = CancellationToken::new();
+/// let options = MangleOptions::new()
+///     .with_delay(Duration::from_millis(500))
+///     .with_timeout(Duration::from_secs(60))
+///     .with_cancellation(token.clone());
+/// ```
+#[derive(Clone, Default)]
+pub struct MangleOptions {
+    delay: Duration,
+    deadline: Option<Instant>,
+    cancellation: Option<CancellationToken>,
+    translate_options: TranslateOptions,
+    observer: Option<Arc<dyn MangleObserver>>,
+}
+
+impl std::fmt::Debug for MangleOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("MangleOptions")
+            .field("delay", &self.delay)
+            .field("deadline", &self.deadline)
+            .field("cancellation", &self.cancellation)
+            .field("translate_options", &self.translate_options)
+            .field("observer", &self.observer.is_some())
+            .finish()
+    }
+}
+
+impl MangleOptions {
+    /// Create options with no delay, no deadline, no cancellation and
+    /// default translation options.
+    pub fn new() -> MangleOptions {
+        MangleOptions::default()
+    }
+
+    /// Wait for `delay` before each translation request.
+    pub fn with_delay(mut self, delay: Duration) -> MangleOptions {
+        self.delay = delay;
+        self
+    }
+
+    /// Stop the run once `deadline` has passed.
+    pub fn with_deadline(mut self, deadline: Instant) -> MangleOptions {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Stop the run once `timeout` has passed from now.
+    pub fn with_timeout(self, timeout: Duration) -> MangleOptions {
+        self.with_deadline(Instant::now() + timeout)
+    }
+
+    /// Stop the run once `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> MangleOptions {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Send every translation request with `options`.
+    pub fn with_translate_options(mut self, options: TranslateOptions) -> MangleOptions {
+        self.translate_options = options;
+        self
+    }
+
+    /// Tell `observer` how the run is going.
+    pub fn with_observer(self, observer: impl MangleObserver + 'static) -> MangleOptions {
+        self.with_shared_observer(Arc::new(observer))
+    }
+
+    /// Tell `observer` how the run is going, keeping it shared with the
+    /// caller.
+    pub fn with_shared_observer(mut self, observer: Arc<dyn MangleObserver>) -> MangleOptions {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Wait for the delay before the next request, returning early with the
+    /// reason to stop if the run should not go on.
+    fn wait(&self) -> Result<(), StopReason> {
+        let until = Instant::now() + self.delay;
+        let until = self.deadline.map_or(until, |deadline| until.min(deadline));
+        let cancelled = match &self.cancellation {
+            Some(token) => token.wait_until(until),
+            None => {
+                std::thread::sleep(until.saturating_duration_since(Instant::now()));
+                false
             }
-            l
         };
-        langs.push(TranslationDirection {
-            from_lang: prev_lang.to_string(),
-            to_lang: next_lang.to_string(),
+        if cancelled {
+            Err(StopReason::Cancelled)
+        } else if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            Err(StopReason::DeadlineExceeded)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get the translate options of the request of hop `index`, counting its
+    /// retries in `retries` and telling the observer about them, on top of
+    /// telling the context of the options.
+    fn request_options(&self, index: usize, retries: &Arc<AtomicU64>) -> TranslateOptions {
+        let outer = self.translate_options.context.clone();
+        let observer = self.observer.clone();
+        let retries = retries.clone();
+        let context = CallContext::new().with_retry_listener(move |retry, error| {
+            retries.fetch_add(1, Ordering::Relaxed);
+            if let Some(observer) = &observer {
+                observer.on_retry(index, retry, error);
+            }
+            outer.notify_retry(retry, error);
         });
-        // Move on.
-        prev_lang = next_lang;
+        self.translate_options.clone().with_context(context)
+    }
+}
+
+/// The reasons a mangling run may stop before the end of its path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// The cancellation token was cancelled.
+    Cancelled,
+    /// The deadline passed.
+    DeadlineExceeded,
+}
+
+impl std::fmt::Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StopReason::Cancelled => write!(f, "cancelled"),
+            StopReason::DeadlineExceeded => write!(f, "deadline exceeded"),
+        }
+    }
+}
+
+/// A hop of a mangling run that failed, with everything done before it.
+///
+/// The run can be continued from the failed hop with
+/// [`resume_mangle`], or with [`resume_mangle_until`] for a run of
+/// [`mangle_until`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct HopFailure {
+    /// The error the hop failed with.
+    pub error: TranslationError,
+    /// The index of the failed hop in the run, from 0.
+    pub index: usize,
+    /// The direction of the failed hop.
+    pub direction: TranslationDirection,
+    /// The trace of the completed hops, whose `result` is the text after
+    /// the last of them.
+    pub trace: MangleTrace,
+    /// The hops left, from the failed one on, with the index of their
+    /// translator among those of the run.
+    ///
+    /// A run of [`mangle_until`] plans its path one leg at a time, so only
+    /// the rest of the failed leg is known. Resuming it with
+    /// [`resume_mangle`] would stop at the end of that leg without checking
+    /// the [`StopRule`].
+    pub remaining: Vec<MixedHop>,
+    /// For a run of [`mangle_until`], the number of hops done at the end of
+    /// each leg completed before the failure, where the [`StopRule`] was
+    /// checked. Empty for other runs.
+    pub leg_ends: Vec<usize>,
+}
+
+impl HopFailure {
+    /// Get the text after the last completed hop, or the original text if
+    /// no hop was completed.
+    pub fn text(&self) -> &str {
+        &self.trace.result
+    }
+}
+
+/// Errors that may occur when mangling with [`mangle_with`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum MangleError {
+    /// A translation request failed outside of a hop, or the run could not
+    /// be started.
+    Failed(TranslationError),
+    /// A hop failed after the hops before it were completed.
+    HopFailed(Box<HopFailure>),
+    /// The run was stopped before the end of its path.
+    Stopped {
+        /// Why the run was stopped.
+        reason: StopReason,
+        /// The text after the last completed hop, or the original text if
+        /// no hop was completed.
+        text: String,
+        /// The number of completed hops.
+        hops: usize,
+    },
+}
+
+impl std::fmt::Display for MangleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MangleError::Failed(e) => e.fmt(f),
+            MangleError::HopFailed(failure) => {
+                write!(f, "{} at hop {}", failure.error, failure.index + 1)
+            }
+            MangleError::Stopped { reason, hops, .. } => {
+                write!(f, "Mangling {} after {} hops", reason, hops)
+            }
+        }
+    }
+}
+
+impl MangleError {
+    /// Get the translation error the run failed with, if it failed rather
+    /// than being stopped.
+    pub fn translation_error(&self) -> Option<&TranslationError> {
+        match self {
+            MangleError::Failed(e) => Some(e),
+            MangleError::HopFailed(failure) => Some(&failure.error),
+            MangleError::Stopped { .. } => None,
+        }
     }
 
-    langs
+    /// Set the hops left after a failed hop of a run along `path`.
+    fn with_path(self, path: &[MixedHop]) -> MangleError {
+        match self {
+            MangleError::HopFailed(mut failure) => {
+                failure.remaining = path[failure.index.min(path.len())..].to_vec();
+                MangleError::HopFailed(failure)
+            }
+            e => e,
+        }
+    }
+}
+
+/// Get `lang_path` as the hops of a path with a single translator.
+fn single_translator_path(lang_path: &[TranslationDirection]) -> Vec<MixedHop> {
+    lang_path
+        .iter()
+        .map(|direction| MixedHop {
+            direction: direction.clone(),
+            translator: 0,
+        })
+        .collect()
+}
+
+impl From<TranslationError> for MangleError {
+    fn from(e: TranslationError) -> MangleError {
+        MangleError::Failed(e)
+    }
 }
 
 /// Mangle the `original_text` by translating it for many times with `translator`.
@@ -76,11 +394,16 @@ pub fn get_random_lang_path(
 /// * `delay` - The delay in milliseconds between each translation request.
 ///
 /// # Returns
-/// The mangled text.
+/// The mangled text, or the error of the first request that failed. The
+/// hops completed before it are lost: use [`mangle_with`] to get them in a
+/// [`HopFailure`] and continue the run with [`resume_mangle`].
 ///
 /// # Example
-/// ```rust
-/// let langs = get_random_lang_pairs("en", &["en", "zh"], 20);
+/// ```rust,no_run
+/// use translator_mangler::{get_random_lang_path, mangle, TranslatorBaidu};
+///
+/// let translator = TranslatorBaidu::new("[YOUR_APP_ID]", "[YOUR_API_KEY]");
+/// let langs = get_random_lang_path("en", &["en", "zh"], 20);
 /// let result = mangle(&translator, "Ignorance is strength.", &langs, 1000);
 /// ```
 pub fn mangle(
@@ -89,11 +412,226 @@ pub fn mangle(
     lang_path: &[TranslationDirection],
     delay: u64,
 ) -> Result<String, TranslationError> {
-    //Reduce the langs list, apply translation to each pair.
-    lang_path
+    let options = MangleOptions::new().with_delay(Duration::from_millis(delay));
+    match mangle_with(translator, original_text, lang_path, &options) {
+        Ok(text) => Ok(text),
+        Err(MangleError::Failed(e)) => Err(e),
+        Err(MangleError::HopFailed(failure)) => Err(failure.error),
+        Err(MangleError::Stopped { .. }) => unreachable!("no deadline or cancellation was set"),
+    }
+}
+
+/// Mangle the `original_text` like [`mangle`], with a deadline,
+/// cancellation and translation options given in `options`.
+///
+/// Translation options not honored by `translator` are ignored, with a
+/// warning logged.
+///
+/// The run is checked for cancellation and the deadline before each hop and
+/// while waiting for the delay. A request already sent is allowed to finish.
+///
+/// # Returns
+/// The mangled text, or [`MangleError::Stopped`] with the text after the last
+/// completed hop if the run was stopped early. A failed hop gives
+/// [`MangleError::HopFailed`] with the hops completed so far, to continue
+/// with [`resume_mangle`].
+///
+/// # Example
+/// ```rust
+/// use std::time::Duration;
+/// use translator_mangler::testing::TranslatorReverseWords;
+/// use translator_mangler::{get_random_lang_path, mangle_with, MangleError, MangleOptions};
+///
+/// let translator = TranslatorReverseWords::new();
+/// let langs = get_random_lang_path("en", &["zh", "ja"], 20);
+/// let options = MangleOptions::new()
+///     .with_delay(Duration::from_millis(100))
+///     .with_timeout(Duration::from_millis(250));
+///
+/// match mangle_with(&translator, "one two three", &langs, &options) {
+///     Err(MangleError::Stopped { text, hops, .. }) => println!("{} after {} hops", text, hops),
+///     other => panic!("unexpected result: {:?}", other),
+/// }
+/// ```
+pub fn mangle_with(
+    translator: &dyn Translator,
+    original_text: &str,
+    lang_path: &[TranslationDirection],
+    options: &MangleOptions,
+) -> Result<String, MangleError> {
+    let hops = lang_path
         .iter()
-        .try_fold(original_text.to_string(), |acc, current| {
-            std::thread::sleep(std::time::Duration::from_millis(delay));
-            translator.translate(&acc, current)
-        })
+        .map(|direction| (translator, direction))
+        .collect::<Vec<Hop>>();
+    run(
+        translator.name(),
+        &hops,
+        original_text,
+        options,
+        &mut |_| {},
+    )
+    .map_err(|e| e.with_path(&single_translator_path(lang_path)))
+}
+
+/// A hop of a path with the translator sending it.
+type Hop<'a> = (&'a dyn Translator, &'a TranslationDirection);
+
+/// The next hop of a path built while running, if any, with the translator
+/// sending it.
+type NextHop<'a> = Result<Option<(&'a dyn Translator, TranslationDirection)>, TranslationError>;
+
+/// Run the mangling loop over `lang_path`, passing every completed hop to
+/// `on_step`.
+///
+/// Each hop is sent with the language codes of its translator, and
+/// `backend` names the translators in logs.
+fn run(
+    backend: &str,
+    lang_path: &[Hop],
+    original_text: &str,
+    options: &MangleOptions,
+    on_step: &mut dyn FnMut(&MangleStep),
+) -> Result<String, MangleError> {
+    let translators = lang_path
+        .iter()
+        .map(|(translator, _)| *translator)
+        .collect::<Vec<&dyn Translator>>();
+    run_lazy(
+        backend,
+        &translators,
+        lang_path.len(),
+        original_text,
+        options,
+        &mut |index, _| {
+            Ok(lang_path
+                .get(index)
+                .map(|(translator, direction)| (*translator, (*direction).clone())))
+        },
+        on_step,
+    )
+}
+
+/// Log a warning for every translation option of `options` that one of
+/// `translators` ignores, once per backend.
+fn warn_ignored_options(translators: &[&dyn Translator], options: &MangleOptions) {
+    let mut warned: Vec<&str> = Vec::new();
+    for translator in translators {
+        if warned.contains(&translator.name()) {
+            continue;
+        }
+        warned.push(translator.name());
+        for option in options.translate_options.set_options() {
+            if !translator.supported_options().contains(&option) {
+                tracing::warn!(
+                    ?option,
+                    backend = translator.name(),
+                    "translation option ignored by backend"
+                );
+            }
+        }
+    }
+}
+
+/// Run the mangling loop, asking `next_hop` for each hop given its index
+/// and the current text, until it returns `None`.
+///
+/// `translators` are all those `next_hop` may choose, and `rounds` is the
+/// number of hops told to observers, at most.
+fn run_lazy<'a>(
+    backend: &str,
+    translators: &[&'a dyn Translator],
+    rounds: usize,
+    original_text: &str,
+    options: &MangleOptions,
+    next_hop: &mut dyn FnMut(usize, &str) -> NextHop<'a>,
+    on_step: &mut dyn FnMut(&MangleStep),
+) -> Result<String, MangleError> {
+    let span = tracing::info_span!(
+        "mangle",
+        backend,
+        rounds,
+        text_len = original_text.chars().count(),
+    );
+    let _entered = span.enter();
+
+    warn_ignored_options(translators, options);
+
+    let run_started_at = SystemTime::now();
+    let mut steps: Vec<MangleStep> = Vec::new();
+    let mut text = original_text.to_string();
+    let mut hops = 0;
+    let mut run_hops = || -> Result<(), MangleError> {
+        while let Some((translator, current)) = next_hop(hops, &text)? {
+            let index = hops;
+            let _hop = tracing::info_span!(
+                "hop",
+                index,
+                from = %current.from_lang,
+                to = %current.to_lang,
+            )
+            .entered();
+            if let Some(observer) = &options.observer {
+                observer.on_step_start(index, rounds, &current);
+            }
+            if let Err(reason) = options.wait() {
+                tracing::info!(%reason, "mangling stopped");
+                return Err(MangleError::Stopped {
+                    reason,
+                    text: std::mem::take(&mut text),
+                    hops,
+                });
+            }
+
+            let started_at = SystemTime::now();
+            let start = Instant::now();
+            let retries = Arc::new(AtomicU64::new(0));
+            let request_options = options.request_options(index, &retries);
+            let native = TranslationDirection {
+                from_lang: translator.native_lang(&current.from_lang).to_string(),
+                to_lang: translator.native_lang(&current.to_lang).to_string(),
+            };
+            let result = translator.translate_with(&text, &native, &request_options);
+            if let (Some(observer), Err(e)) = (&options.observer, &result) {
+                observer.on_error(index, e);
+            }
+            let output = match result {
+                Ok(output) => output,
+                Err(error) => {
+                    return Err(MangleError::HopFailed(Box::new(HopFailure {
+                        error,
+                        index,
+                        direction: current,
+                        trace: trace::build(
+                            original_text,
+                            std::mem::take(&mut text),
+                            std::mem::take(&mut steps),
+                            run_started_at,
+                        ),
+                        remaining: Vec::new(),
+                        leg_ends: Vec::new(),
+                    })));
+                }
+            };
+            let step = MangleStep {
+                direction: current,
+                input: std::mem::replace(&mut text, output.clone()),
+                output,
+                backend: translator.name().to_string(),
+                started_at,
+                finished_at: SystemTime::now(),
+                latency: start.elapsed(),
+                retries: retries.load(Ordering::Relaxed),
+            };
+            hops += 1;
+            if let Some(observer) = &options.observer {
+                observer.on_step_done(index, rounds, &step);
+            }
+            on_step(&step);
+            steps.push(step);
+        }
+        Ok(())
+    };
+    let result = run_hops();
+    telemetry::record_mangle(result.is_ok(), hops);
+    result.map(|()| text)
 }
@@ -0,0 +1,56 @@
+use super::MangleStep;
+use crate::translator::{TranslationDirection, TranslationError};
+
+/// Callbacks telling how a mangling run is going, for example to draw a
+/// progress bar.
+///
+/// All callbacks do nothing by default. They are called on the thread
+/// running the mangling, except for
+/// [`on_retry`](MangleObserver::on_retry), which is called on the thread
+/// making the retry. Hops are numbered from 0 out of `total`.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::testing::TranslatorReverseWords;
+/// use translator_mangler::{
+///     get_random_lang_path, mangle_with, MangleObserver, MangleOptions, TranslationDirection,
+/// };
+///
+/// struct PrintProgress;
+///
+/// impl MangleObserver for PrintProgress {
+///     fn on_step_start(&self, index: usize, total: usize, direction: &TranslationDirection) {
+///         println!("hop {}/{}: {} -> {}", index + 1, total, direction.from_lang, direction.to_lang);
+///     }
+/// }
+///
+/// let langs = get_random_lang_path("en", &["zh", "ja"], 3);
+/// let options = MangleOptions::new().with_observer(PrintProgress);
+/// mangle_with(&TranslatorReverseWords::new(), "one two", &langs, &options).unwrap();
+/// ```
+pub trait MangleObserver: Send + Sync {
+    /// Called before waiting for the delay and sending a hop.
+    fn on_step_start(&self, index: usize, total: usize, direction: &TranslationDirection) {
+        let _ = (index, total, direction);
+    }
+
+    /// Called when a hop is completed.
+    fn on_step_done(&self, index: usize, total: usize, step: &MangleStep) {
+        let _ = (index, total, step);
+    }
+
+    /// Called when a request of a hop failed with `error` and is retried by
+    /// a [`RetryLayer`](crate::layer::RetryLayer) or other middleware telling
+    /// the [`CallContext`](crate::CallContext) of the request, `retry`
+    /// being the retry number, from 1. The retried request is sent as
+    /// attempt `retry + 1`, as told by
+    /// [`CallContext::attempt`](crate::CallContext::attempt).
+    fn on_retry(&self, index: usize, retry: u32, error: &TranslationError) {
+        let _ = (index, retry, error);
+    }
+
+    /// Called when a hop failed, ending the run.
+    fn on_error(&self, index: usize, error: &TranslationError) {
+        let _ = (index, error);
+    }
+}
@@ -0,0 +1,208 @@
+use super::{mangle_with, MangleError, MangleOptions};
+use crate::metrics::Similarity;
+use crate::strategy::lang_script;
+use crate::translator::langs::common_lang;
+use crate::translator::{TranslationDirection, TranslationErrorKind, Translator};
+
+/// What paraphrases to look for with [`paraphrase`].
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::ParaphraseOptions;
+///
+/// // Up to 3 paraphrases at least 60% similar to the text, and at most 85%
+/// // similar to each other.
+/// let options = ParaphraseOptions::new("en", &["fr", "de", "ja", "zh"])
+///     .with_count(3)
+///     .with_similarity_floor(0.6)
+///     .with_duplicate_threshold(0.85);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParaphraseOptions {
+    source_lang: String,
+    pivots: Vec<String>,
+    count: usize,
+    similarity_floor: f64,
+    duplicate_threshold: f64,
+}
+
+impl ParaphraseOptions {
+    /// Look for 5 paraphrases of a text in `source_lang`, each through one
+    /// of `pivots`, at least 50% similar to the text and at most 90% similar
+    /// to each other.
+    pub fn new(source_lang: &str, pivots: &[&str]) -> ParaphraseOptions {
+        ParaphraseOptions {
+            source_lang: source_lang.to_string(),
+            pivots: pivots.iter().map(|pivot| pivot.to_string()).collect(),
+            count: 5,
+            similarity_floor: 0.5,
+            duplicate_threshold: 0.9,
+        }
+    }
+
+    /// Look for `count` paraphrases at most.
+    pub fn with_count(mut self, count: usize) -> ParaphraseOptions {
+        self.count = count;
+        self
+    }
+
+    /// Drop paraphrases whose [`overall`](Similarity::overall) similarity to
+    /// the text is below `similarity`, having lost its meaning.
+    pub fn with_similarity_floor(mut self, similarity: f64) -> ParaphraseOptions {
+        self.similarity_floor = similarity;
+        self
+    }
+
+    /// Drop paraphrases whose [`overall`](Similarity::overall) similarity to
+    /// one already found is `similarity` or more, being near-duplicates.
+    pub fn with_duplicate_threshold(mut self, similarity: f64) -> ParaphraseOptions {
+        self.duplicate_threshold = similarity;
+        self
+    }
+
+    /// Get the pivots in the order they are tried: the first pivot of each
+    /// script in turn, then the second, and so on, leaving out the source
+    /// language.
+    fn ordered_pivots(&self) -> Vec<&str> {
+        let mut by_script: Vec<(&str, Vec<&str>)> = Vec::new();
+        for pivot in &self.pivots {
+            if common_lang(pivot) == common_lang(&self.source_lang) {
+                continue;
+            }
+            let script = lang_script(pivot);
+            match by_script.iter_mut().find(|(other, _)| *other == script) {
+                Some((_, pivots)) => pivots.push(pivot),
+                None => by_script.push((script, vec![pivot])),
+            }
+        }
+        let rounds = by_script.iter().map(|(_, pivots)| pivots.len()).max();
+        (0..rounds.unwrap_or(0))
+            .flat_map(|round| {
+                by_script
+                    .iter()
+                    .filter_map(move |(_, pivots)| pivots.get(round).copied())
+            })
+            .collect()
+    }
+}
+
+/// A paraphrase found by [`paraphrase`].
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Paraphrase {
+    /// The text of the paraphrase.
+    pub text: String,
+    /// The language the text was translated to and back from.
+    pub pivot: String,
+    /// The [`overall`](Similarity::overall) similarity of the paraphrase to
+    /// the text.
+    pub similarity: f64,
+}
+
+/// Get paraphrases of `text` keeping its meaning, by translating it to each
+/// pivot of `paraphrase_options` and back with [`mangle_with`].
+///
+/// Pivots of different scripts are tried first, to get diverse wordings,
+/// and pivots `translator` does not support both ways or fails on are
+/// skipped. Each round trip is kept unless it gives the text back, falls below the
+/// similarity floor or nearly duplicates a paraphrase already found.
+///
+/// # Returns
+/// Up to the wanted number of paraphrases, in the order they were found.
+/// If none was found, the first error other than an unsupported language
+/// is returned instead. A run stopped by the deadline or cancellation ends
+/// the search with the paraphrases found so far.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::testing::TranslatorDictionary;
+/// use translator_mangler::{paraphrase, MangleOptions, ParaphraseOptions};
+///
+/// let translator = TranslatorDictionary::new(&[
+///     ("big", "grand"),
+///     ("grand", "large"),
+///     ("house", "maison"),
+///     ("maison", "home"),
+/// ]);
+/// let options = ParaphraseOptions::new("en", &["fr"]).with_similarity_floor(0.0);
+///
+/// let paraphrases = paraphrase(&translator, "big house", &options, &MangleOptions::new()).unwrap();
+/// assert_eq!(paraphrases[0].text, "large home");
+/// assert_eq!(paraphrases[0].pivot, "fr");
+/// ```
+pub fn paraphrase(
+    translator: &dyn Translator,
+    text: &str,
+    paraphrase_options: &ParaphraseOptions,
+    options: &MangleOptions,
+) -> Result<Vec<Paraphrase>, MangleError> {
+    let source = &paraphrase_options.source_lang;
+    let mut paraphrases: Vec<Paraphrase> = Vec::new();
+    let mut first_error: Option<MangleError> = None;
+    for pivot in paraphrase_options.ordered_pivots() {
+        if paraphrases.len() >= paraphrase_options.count {
+            break;
+        }
+        let native_source = translator.native_lang(source);
+        let native_pivot = translator.native_lang(pivot);
+        if !translator.is_pair_supported(native_source, native_pivot)
+            || !translator.is_pair_supported(native_pivot, native_source)
+        {
+            tracing::debug!(pivot, "pivot not supported both ways skipped");
+            continue;
+        }
+
+        let round_trip = [
+            TranslationDirection {
+                from_lang: source.clone(),
+                to_lang: pivot.to_string(),
+            },
+            TranslationDirection {
+                from_lang: pivot.to_string(),
+                to_lang: source.clone(),
+            },
+        ];
+        let output = match mangle_with(translator, text, &round_trip, options) {
+            Ok(output) => output,
+            Err(e)
+                if e.translation_error()
+                    .is_some_and(|e| e.kind == TranslationErrorKind::UnsupportedLanguage) =>
+            {
+                tracing::debug!(pivot, "pivot refused by backend skipped");
+                continue;
+            }
+            Err(e @ MangleError::Stopped { .. }) if paraphrases.is_empty() => return Err(e),
+            Err(MangleError::Stopped { reason, .. }) => {
+                tracing::info!(%reason, "paraphrasing stopped");
+                break;
+            }
+            Err(e) => {
+                tracing::warn!(pivot, error = %e, "failing pivot skipped");
+                first_error.get_or_insert(e);
+                continue;
+            }
+        };
+
+        if output.trim() == text.trim() {
+            continue;
+        }
+        let similarity = Similarity::between(text, &output).overall();
+        if similarity < paraphrase_options.similarity_floor {
+            continue;
+        }
+        if paraphrases.iter().any(|other| {
+            Similarity::between(&other.text, &output).overall()
+                >= paraphrase_options.duplicate_threshold
+        }) {
+            continue;
+        }
+        paraphrases.push(Paraphrase {
+            text: output,
+            pivot: pivot.to_string(),
+            similarity,
+        });
+    }
+    match first_error {
+        Some(e) if paraphrases.is_empty() => Err(e),
+        _ => Ok(paraphrases),
+    }
+}
@@ -0,0 +1,109 @@
+use super::{mangle_mixed_with_trace, mangle_with_trace};
+use super::{HopFailure, MangleError, MangleOptions, MangleTrace};
+use crate::translator::{TranslationDirection, Translator};
+
+/// Continue a run that failed at a hop, sending the failed hop and every
+/// hop after it to `translator`.
+///
+/// # Returns
+/// The trace of the whole run, with the hops completed before the failure,
+/// or an error like [`resume_mangle_with`].
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::testing::{TranslatorReverseWords, TranslatorScripted};
+/// use translator_mangler::{get_random_lang_path, mangle_with, resume_mangle, MangleError, MangleOptions};
+///
+/// let flaky = TranslatorScripted::new().then_ok("two one").then_err("quota exhausted");
+/// let langs = get_random_lang_path("en", &["zh", "ja"], 3);
+///
+/// let Err(MangleError::HopFailed(failure)) = mangle_with(&flaky, "one two", &langs, &MangleOptions::new())
+/// else {
+///     panic!("the second hop should fail");
+/// };
+/// assert_eq!(failure.index, 1);
+/// assert_eq!(failure.text(), "two one");
+///
+/// let trace = resume_mangle(&failure, &TranslatorReverseWords::new()).unwrap();
+/// assert_eq!(trace.steps.len(), 3);
+/// assert_eq!(trace.result, "two one");
+/// ```
+pub fn resume_mangle(
+    failure: &HopFailure,
+    translator: &dyn Translator,
+) -> Result<MangleTrace, MangleError> {
+    resume_mangle_with(failure, translator, &MangleOptions::new())
+}
+
+/// Continue a run that failed at a hop like [`resume_mangle`], with a
+/// deadline, cancellation and translation options given in `options`.
+///
+/// # Returns
+/// The trace of the whole run, or an error like
+/// [`mangle_with`](super::mangle_with) with its hops counted from the
+/// start of the run. Another failure can be resumed in turn.
+pub fn resume_mangle_with(
+    failure: &HopFailure,
+    translator: &dyn Translator,
+    options: &MangleOptions,
+) -> Result<MangleTrace, MangleError> {
+    let lang_path = failure
+        .remaining
+        .iter()
+        .map(|hop| hop.direction.clone())
+        .collect::<Vec<TranslationDirection>>();
+    continued(
+        failure,
+        mangle_with_trace(translator, failure.text(), &lang_path, options),
+    )
+}
+
+/// Continue a run that failed at a hop like [`resume_mangle_with`],
+/// sending each hop left to its own translator among `translators` like
+/// [`mangle_mixed`](super::mangle_mixed).
+pub fn resume_mangle_mixed(
+    failure: &HopFailure,
+    translators: &[&dyn Translator],
+    options: &MangleOptions,
+) -> Result<MangleTrace, MangleError> {
+    continued(
+        failure,
+        mangle_mixed_with_trace(translators, failure.text(), &failure.remaining, options),
+    )
+}
+
+/// Put the hops completed before `failure` in front of the `resumed` run.
+pub(super) fn continued(
+    failure: &HopFailure,
+    resumed: Result<MangleTrace, MangleError>,
+) -> Result<MangleTrace, MangleError> {
+    match resumed {
+        Ok(trace) => Ok(join(&failure.trace, trace)),
+        Err(MangleError::HopFailed(mut later)) => {
+            later.index += failure.index;
+            later.trace = join(&failure.trace, later.trace);
+            Err(MangleError::HopFailed(later))
+        }
+        Err(MangleError::Stopped { reason, text, hops }) => Err(MangleError::Stopped {
+            reason,
+            text,
+            hops: failure.index + hops,
+        }),
+        Err(e) => Err(e),
+    }
+}
+
+/// Join the traces of two parts of a run, leaving out the time between
+/// them.
+fn join(before: &MangleTrace, after: MangleTrace) -> MangleTrace {
+    MangleTrace {
+        original: before.original.clone(),
+        result: after.result,
+        steps: before.steps.iter().cloned().chain(after.steps).collect(),
+        started_at: before.started_at,
+        finished_at: after.finished_at,
+        elapsed: before.elapsed + after.elapsed,
+        latency: before.latency + after.latency,
+        retries: before.retries + after.retries,
+    }
+}
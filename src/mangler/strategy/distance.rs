@@ -0,0 +1,101 @@
+/// Language codes used by the backends, with their family and branch.
+const LANGUAGES: &[(&[&str], &str, &str)] = &[
+    (
+        &[
+            "zh", "zh-CHS", "zh-CHT", "zh-CN", "zh-TW", "cht", "yue", "wyw",
+        ],
+        "sino-tibetan",
+        "sinitic",
+    ),
+    (&["ja", "jp"], "japonic", "japonic"),
+    (&["ko", "kor"], "koreanic", "koreanic"),
+    (
+        &[
+            "fr", "fra", "es", "spa", "it", "pt", "ro", "rom", "ca", "gl",
+        ],
+        "indo-european",
+        "romance",
+    ),
+    (
+        &[
+            "en", "de", "nl", "sv", "swe", "da", "dan", "no", "is", "af", "yi",
+        ],
+        "indo-european",
+        "germanic",
+    ),
+    (
+        &[
+            "ru", "pl", "cs", "bg", "bul", "uk", "sk", "slo", "sl", "hr", "sr", "be", "mk",
+        ],
+        "indo-european",
+        "slavic",
+    ),
+    (&["el"], "indo-european", "hellenic"),
+    (&["lt", "lv"], "indo-european", "baltic"),
+    (&["ga", "cy", "gd"], "indo-european", "celtic"),
+    (
+        &["hi", "bn", "ur", "fa", "pa", "mr", "gu", "ne"],
+        "indo-european",
+        "indo-iranian",
+    ),
+    (&["fi", "fin", "et", "est"], "uralic", "finnic"),
+    (&["hu"], "uralic", "ugric"),
+    (&["ar", "ara", "he", "iw", "mt"], "afro-asiatic", "semitic"),
+    (
+        &["tr", "az", "kk", "uz", "ky", "tk", "tt"],
+        "turkic",
+        "turkic",
+    ),
+    (&["th", "lo"], "kra-dai", "tai"),
+    (&["vi", "vie", "km"], "austroasiatic", "austroasiatic"),
+    (
+        &["id", "ms", "tl", "jv", "su"],
+        "austronesian",
+        "malayo-polynesian",
+    ),
+];
+
+/// The largest distance returned by [`lang_distance`].
+pub const MAX_LANG_DISTANCE: u32 = 3;
+
+/// Find the family and branch of `lang`.
+fn classify(lang: &str) -> Option<(&'static str, &'static str)> {
+    LANGUAGES
+        .iter()
+        .find(|(codes, _, _)| codes.contains(&lang))
+        .map(|(_, family, branch)| (*family, *branch))
+}
+
+/// Get a rough linguistic distance between two languages.
+///
+/// The distance is 0 for the same language, 1 within a branch such as the
+/// Romance languages, 2 within a family such as the Indo-European languages
+/// and [`MAX_LANG_DISTANCE`] otherwise. Codes of all supported backends are
+/// understood. Unknown languages are treated as unrelated to any other.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::strategy::lang_distance;
+///
+/// assert_eq!(lang_distance("fr", "fr"), 0);
+/// assert_eq!(lang_distance("fra", "spa"), 1);
+/// assert_eq!(lang_distance("en", "ru"), 2);
+/// assert_eq!(lang_distance("en", "ja"), 3);
+/// ```
+pub fn lang_distance(a: &str, b: &str) -> u32 {
+    if a == b {
+        return 0;
+    }
+    match (classify(a), classify(b)) {
+        (Some((family_a, branch_a)), Some((family_b, branch_b))) => {
+            if family_a != family_b {
+                MAX_LANG_DISTANCE
+            } else if branch_a != branch_b {
+                2
+            } else {
+                1
+            }
+        }
+        _ => MAX_LANG_DISTANCE,
+    }
+}
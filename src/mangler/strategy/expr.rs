@@ -0,0 +1,404 @@
+use std::str::FromStr;
+
+use crate::translator::{TranslationDirection, Translator};
+
+/// The most languages a path expression may visit once expanded.
+const MAX_STOPS: usize = 10_000;
+
+/// Errors that may occur when parsing or resolving a path expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathExprError {
+    /// The message string describing the error.
+    pub message: String,
+}
+
+impl std::fmt::Display for PathExprError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "PathExprError: {}", self.message)
+    }
+}
+
+impl PathExprError {
+    fn at(column: usize, message: impl std::fmt::Display) -> PathExprError {
+        PathExprError {
+            message: format!("{} at column {}", message, column),
+        }
+    }
+}
+
+/// A part of a path expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Node {
+    /// A fixed language, with the column it was written at.
+    Lang(String, usize),
+    /// A random language, from the given languages if any or from the bank
+    /// otherwise, with the column it was written at.
+    Wildcard(Option<Vec<String>>, usize),
+    /// Nodes visited in order some number of times.
+    Repeat(Vec<Node>, usize),
+}
+
+/// A language visited by a path, before the wildcards are resolved.
+enum Stop<'a> {
+    Lang(&'a str, usize),
+    Wildcard(Option<&'a [String]>, usize),
+}
+
+/// A hand-written mangling path, possibly with random parts.
+///
+/// Languages are separated by `>` (or `→`) and visited in order:
+/// * `ja` visits Japanese.
+/// * `*` visits a random language from the bank.
+/// * `*[ja,ko,fr]` visits a random language from the listed ones.
+/// * `(ja > ko){3}` visits Japanese then Korean three times in a row.
+///   Any single part can be repeated too, so `*{4}` visits four random
+///   languages.
+///
+/// Random languages are never the language visited just before, nor the
+/// fixed one just after, unless nothing else is left.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::strategy::PathExpr;
+///
+/// let expr: PathExpr = "en > (ja > ko){2} > *[fr,de] > en".parse().unwrap();
+/// let path = expr.resolve(&[], &mut fastrand::Rng::with_seed(1)).unwrap();
+/// assert_eq!(path.len(), 6);
+/// assert_eq!(path[0].to_lang, "ja");
+/// assert!(["fr", "de"].contains(&path[5].from_lang.as_str()));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathExpr {
+    nodes: Vec<Node>,
+}
+
+impl PathExpr {
+    /// Parse a path expression.
+    ///
+    /// The expression must visit at least two languages, and never the same
+    /// fixed language twice in a row.
+    pub fn parse(source: &str) -> Result<PathExpr, PathExprError> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            pos: 0,
+        };
+        let nodes = parser.sequence()?;
+        parser.skip_whitespace();
+        if let Some(c) = parser.peek() {
+            return Err(PathExprError::at(
+                parser.column(),
+                format!("unexpected '{}'", c),
+            ));
+        }
+
+        let stops = count_stops(&nodes)
+            .filter(|count| *count <= MAX_STOPS)
+            .ok_or_else(|| PathExprError {
+                message: format!("the path visits more than {} languages", MAX_STOPS),
+            })?;
+        if stops < 2 {
+            return Err(PathExprError {
+                message: "the path must visit at least two languages".to_string(),
+            });
+        }
+
+        let mut expanded = Vec::with_capacity(stops);
+        expand(&nodes, &mut expanded);
+        let repeated = expanded.windows(2).find_map(|pair| match pair {
+            [Stop::Lang(prev, _), Stop::Lang(lang, column)] if prev == lang => {
+                Some((*lang, *column))
+            }
+            _ => None,
+        });
+        if let Some((lang, column)) = repeated {
+            return Err(PathExprError::at(
+                column,
+                format!("'{}' is visited twice in a row", lang),
+            ));
+        }
+        Ok(PathExpr { nodes })
+    }
+
+    /// Check whether the expression has a wildcard choosing from the bank.
+    pub fn uses_bank(&self) -> bool {
+        fn uses_bank(nodes: &[Node]) -> bool {
+            nodes.iter().any(|node| match node {
+                Node::Lang(..) => false,
+                Node::Wildcard(langs, _) => langs.is_none(),
+                Node::Repeat(nodes, _) => uses_bank(nodes),
+            })
+        }
+        uses_bank(&self.nodes)
+    }
+
+    /// Check that every language the expression may visit is supported by
+    /// `translator`, in its own codes or those of another backend.
+    ///
+    /// # Arguments
+    /// * `translator` - The translator the path is for.
+    /// * `lang_bank` - The languages `*` chooses from.
+    pub fn validate(
+        &self,
+        translator: &dyn Translator,
+        lang_bank: &[&str],
+    ) -> Result<(), PathExprError> {
+        let mut langs: Vec<&str> = Vec::new();
+        collect_langs(&self.nodes, &mut langs);
+        if self.uses_bank() {
+            langs.extend(lang_bank);
+        }
+        match langs
+            .iter()
+            .find(|lang| !translator.is_lang_supported(translator.native_lang(lang)))
+        {
+            Some(lang) => Err(PathExprError {
+                message: format!(
+                    "language '{}' is not supported by {}",
+                    lang,
+                    translator.name()
+                ),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the path described by the expression, choosing the random
+    /// languages with `rng`.
+    ///
+    /// # Arguments
+    /// * `lang_bank` - The languages `*` chooses from.
+    /// * `rng` - The random number generator to use.
+    pub fn resolve(
+        &self,
+        lang_bank: &[&str],
+        rng: &mut fastrand::Rng,
+    ) -> Result<Vec<TranslationDirection>, PathExprError> {
+        let mut stops = Vec::new();
+        expand(&self.nodes, &mut stops);
+
+        let mut langs: Vec<&str> = Vec::with_capacity(stops.len());
+        for (i, stop) in stops.iter().enumerate() {
+            let lang = match stop {
+                Stop::Lang(lang, _) => *lang,
+                Stop::Wildcard(choices, column) => {
+                    let choices = match choices {
+                        Some(choices) => choices.iter().map(String::as_str).collect(),
+                        None => lang_bank.to_vec(),
+                    };
+                    if choices.is_empty() {
+                        return Err(PathExprError::at(
+                            *column,
+                            "no language to choose from for the wildcard",
+                        ));
+                    }
+                    let prev = langs.last().copied();
+                    let next = match stops.get(i + 1) {
+                        Some(Stop::Lang(lang, _)) => Some(*lang),
+                        _ => None,
+                    };
+                    let fresh = |avoid_next: bool| {
+                        choices
+                            .iter()
+                            .copied()
+                            .filter(|lang| Some(*lang) != prev)
+                            .filter(|lang| !avoid_next || Some(*lang) != next)
+                            .collect::<Vec<&str>>()
+                    };
+                    let mut candidates = fresh(true);
+                    if candidates.is_empty() {
+                        candidates = fresh(false);
+                    }
+                    super::pick(rng, &candidates, &choices)
+                }
+            };
+            langs.push(lang);
+        }
+
+        Ok(langs
+            .windows(2)
+            .map(|pair| TranslationDirection {
+                from_lang: pair[0].to_string(),
+                to_lang: pair[1].to_string(),
+            })
+            .collect())
+    }
+}
+
+impl FromStr for PathExpr {
+    type Err = PathExprError;
+
+    fn from_str(s: &str) -> Result<PathExpr, PathExprError> {
+        PathExpr::parse(s)
+    }
+}
+
+/// Count the languages visited by `nodes`, or `None` on overflow.
+fn count_stops(nodes: &[Node]) -> Option<usize> {
+    nodes.iter().try_fold(0usize, |acc, node| {
+        let count = match node {
+            Node::Lang(..) | Node::Wildcard(..) => 1,
+            Node::Repeat(nodes, times) => count_stops(nodes)?.checked_mul(*times)?,
+        };
+        acc.checked_add(count)
+    })
+}
+
+/// Collect the languages written in `nodes`.
+fn collect_langs<'a>(nodes: &'a [Node], langs: &mut Vec<&'a str>) {
+    for node in nodes {
+        match node {
+            Node::Lang(lang, _) => langs.push(lang),
+            Node::Wildcard(choices, _) => {
+                langs.extend(choices.iter().flatten().map(String::as_str))
+            }
+            Node::Repeat(nodes, _) => collect_langs(nodes, langs),
+        }
+    }
+}
+
+/// Flatten `nodes` into the languages they visit.
+fn expand<'a>(nodes: &'a [Node], stops: &mut Vec<Stop<'a>>) {
+    for node in nodes {
+        match node {
+            Node::Lang(lang, column) => stops.push(Stop::Lang(lang, *column)),
+            Node::Wildcard(choices, column) => {
+                stops.push(Stop::Wildcard(choices.as_deref(), *column))
+            }
+            Node::Repeat(nodes, times) => {
+                for _ in 0..*times {
+                    expand(nodes, stops);
+                }
+            }
+        }
+    }
+}
+
+/// A recursive descent parser of path expressions.
+///
+/// ```text
+/// sequence := item (('>' | '→') item)*
+/// item     := atom ('{' number '}')?
+/// atom     := lang | '*' ('[' lang (',' lang)* ']')? | '(' sequence ')'
+/// ```
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn column(&self) -> usize {
+        self.pos + 1
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skip whitespace and consume `c` if it comes next.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), PathExprError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", c)))
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> PathExprError {
+        match self.peek() {
+            Some(c) => PathExprError::at(
+                self.column(),
+                format!("expected {} but found '{}'", expected, c),
+            ),
+            None => PathExprError::at(
+                self.column(),
+                format!("expected {} but the expression ended", expected),
+            ),
+        }
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, PathExprError> {
+        let mut nodes = vec![self.item()?];
+        while self.eat('>') || self.eat('→') {
+            nodes.push(self.item()?);
+        }
+        Ok(nodes)
+    }
+
+    fn item(&mut self) -> Result<Node, PathExprError> {
+        let atom = self.atom()?;
+        if !self.eat('{') {
+            return Ok(atom);
+        }
+        self.skip_whitespace();
+        let column = self.column();
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        let times = digits
+            .parse::<usize>()
+            .map_err(|_| self.unexpected("a number of repetitions"))?;
+        if times == 0 {
+            return Err(PathExprError::at(column, "repeating 0 times"));
+        }
+        self.expect('}')?;
+        let nodes = match atom {
+            Node::Repeat(nodes, 1) => nodes,
+            atom => vec![atom],
+        };
+        Ok(Node::Repeat(nodes, times))
+    }
+
+    fn atom(&mut self) -> Result<Node, PathExprError> {
+        self.skip_whitespace();
+        let column = self.column();
+        if self.eat('*') {
+            if !self.eat('[') {
+                return Ok(Node::Wildcard(None, column));
+            }
+            let mut langs = vec![self.lang()?];
+            while self.eat(',') {
+                langs.push(self.lang()?);
+            }
+            self.expect(']')?;
+            Ok(Node::Wildcard(Some(langs), column))
+        } else if self.eat('(') {
+            let nodes = self.sequence()?;
+            self.expect(')')?;
+            Ok(Node::Repeat(nodes, 1))
+        } else {
+            Ok(Node::Lang(self.lang()?, column))
+        }
+    }
+
+    fn lang(&mut self) -> Result<String, PathExprError> {
+        self.skip_whitespace();
+        let lang = self.take_while(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if lang.is_empty() {
+            Err(self.unexpected("a language"))
+        } else {
+            Ok(lang)
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&f) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+}
@@ -0,0 +1,443 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use super::{lang_script, PathStrategy, UniformRandom};
+use crate::translator::langs::common_lang;
+use crate::translator::{TranslationDirection, Translator};
+
+/// How many walks [`LanguageGraph::distinct_scripts_walk`] tries before
+/// giving up.
+const ATTEMPTS: usize = 100;
+
+/// The weights of an edge of a [`LanguageGraph`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct EdgeWeights {
+    /// The price of a request, in any unit.
+    pub cost: f64,
+    /// How long a request usually takes.
+    pub latency: Duration,
+    /// How much the meaning is expected to drift, 1 for a direct
+    /// translation and 2 for one through a pivot language.
+    pub drift: f64,
+}
+
+impl Default for EdgeWeights {
+    fn default() -> Self {
+        EdgeWeights {
+            cost: 1.0,
+            latency: Duration::ZERO,
+            drift: 1.0,
+        }
+    }
+}
+
+impl EdgeWeights {
+    /// Get the weight measured by `metric`.
+    pub fn get(&self, metric: EdgeMetric) -> f64 {
+        match metric {
+            EdgeMetric::Hops => 1.0,
+            EdgeMetric::Cost => self.cost,
+            EdgeMetric::Latency => self.latency.as_secs_f64(),
+            EdgeMetric::Drift => self.drift,
+        }
+    }
+
+    /// Keep the best of both weights, for a pair supported twice.
+    fn best(&self, other: &EdgeWeights) -> EdgeWeights {
+        EdgeWeights {
+            cost: self.cost.min(other.cost),
+            latency: self.latency.min(other.latency),
+            drift: self.drift.min(other.drift),
+        }
+    }
+}
+
+/// The weights a path through a [`LanguageGraph`] can be measured by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeMetric {
+    /// The number of hops.
+    Hops,
+    /// The sum of [`EdgeWeights::cost`].
+    Cost,
+    /// The sum of [`EdgeWeights::latency`], in seconds.
+    Latency,
+    /// The sum of [`EdgeWeights::drift`].
+    Drift,
+}
+
+/// Languages and the pairs that can be translated directly between them.
+///
+/// Languages are named by their common codes as given by
+/// [`common_lang`](crate::common_lang), so a graph may be built from
+/// several backends and its paths sent to any of them.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::strategy::{EdgeMetric, LanguageGraph};
+/// use translator_mangler::TranslatorYoudao;
+///
+/// let translator = TranslatorYoudao::new("[YOUR_APP_KEY]", "[YOUR_APP_SECRET]");
+/// let graph = LanguageGraph::from_translator(&translator);
+///
+/// // Youdao has no direct pair between Japanese and Korean.
+/// assert!(graph.edge("ja", "ko").is_none());
+/// let path = graph.shortest_path("ja", "ko", EdgeMetric::Hops).unwrap();
+/// assert_eq!(path.len(), 2);
+///
+/// let walk = graph.random_walk("ja", "ja", 6, &mut fastrand::Rng::with_seed(1)).unwrap();
+/// assert!(walk.iter().all(|hop| graph.edge(&hop.from_lang, &hop.to_lang).is_some()));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LanguageGraph {
+    edges: BTreeMap<String, BTreeMap<String, EdgeWeights>>,
+}
+
+impl LanguageGraph {
+    /// Create a graph with no languages.
+    pub fn new() -> LanguageGraph {
+        LanguageGraph::default()
+    }
+
+    /// Build the graph of the pairs supported by `translator`.
+    pub fn from_translator(translator: &dyn Translator) -> LanguageGraph {
+        LanguageGraph::from_translators(&[translator])
+    }
+
+    /// Build the graph of the pairs supported by any of `translators`,
+    /// keeping the best weights of pairs supported more than once.
+    ///
+    /// Pairs translated through the pivot language of a translator, as
+    /// given by [`Translator::pivot_lang`], drift twice as much.
+    pub fn from_translators(translators: &[&dyn Translator]) -> LanguageGraph {
+        let mut graph = LanguageGraph::new();
+        for translator in translators {
+            let langs = translator.get_supported_langs();
+            let pivot = translator.pivot_lang();
+            for from in langs {
+                for to in langs {
+                    if !translator.is_pair_supported(from, to) {
+                        continue;
+                    }
+                    let pivoted = pivot.is_some_and(|pivot| pivot != *from && pivot != *to);
+                    let weights = EdgeWeights {
+                        drift: if pivoted { 2.0 } else { 1.0 },
+                        ..EdgeWeights::default()
+                    };
+                    graph.merge_edge(from, to, weights);
+                }
+            }
+        }
+        graph
+    }
+
+    /// Add an edge from `from` to `to`, or keep the best weights of both
+    /// if there is one already.
+    fn merge_edge(&mut self, from: &str, to: &str, weights: EdgeWeights) {
+        let (from, to) = (common_lang(from), common_lang(to));
+        if from == to {
+            return;
+        }
+        self.edges.entry(to.to_string()).or_default();
+        let edges = self.edges.entry(from.to_string()).or_default();
+        match edges.get_mut(to) {
+            Some(edge) => *edge = edge.best(&weights),
+            None => {
+                edges.insert(to.to_string(), weights);
+            }
+        }
+    }
+
+    /// Add an edge from `from` to `to` with `weights`, replacing any edge
+    /// between them.
+    pub fn with_edge(mut self, from: &str, to: &str, weights: EdgeWeights) -> LanguageGraph {
+        let (from, to) = (common_lang(from), common_lang(to));
+        if from != to {
+            self.edges.entry(to.to_string()).or_default();
+            self.edges
+                .entry(from.to_string())
+                .or_default()
+                .insert(to.to_string(), weights);
+        }
+        self
+    }
+
+    /// Get all languages of the graph.
+    pub fn langs(&self) -> Vec<&str> {
+        self.edges.keys().map(String::as_str).collect()
+    }
+
+    /// Get the weights of the edge from `from` to `to`, if any.
+    pub fn edge(&self, from: &str, to: &str) -> Option<&EdgeWeights> {
+        self.edges.get(common_lang(from))?.get(common_lang(to))
+    }
+
+    /// Get the languages `lang` can be translated to directly.
+    pub fn neighbors(&self, lang: &str) -> Vec<&str> {
+        self.edges
+            .get(common_lang(lang))
+            .map(|edges| edges.keys().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the part of the graph between `langs` only.
+    pub fn restrict(&self, langs: &[&str]) -> LanguageGraph {
+        let langs = langs
+            .iter()
+            .map(|lang| common_lang(lang))
+            .collect::<BTreeSet<&str>>();
+        let edges = self
+            .edges
+            .iter()
+            .filter(|(from, _)| langs.contains(from.as_str()))
+            .map(|(from, edges)| {
+                let edges = edges
+                    .iter()
+                    .filter(|(to, _)| langs.contains(to.as_str()))
+                    .map(|(to, weights)| (to.clone(), weights.clone()))
+                    .collect();
+                (from.clone(), edges)
+            })
+            .collect();
+        LanguageGraph { edges }
+    }
+
+    /// Get, for each number of hops up to `rounds`, the languages with a
+    /// walk of exactly that many hops to `end`.
+    fn walks_to(&self, end: &str, rounds: usize) -> Vec<BTreeSet<&str>> {
+        let mut reach = Vec::with_capacity(rounds + 1);
+        reach.push(
+            self.edges
+                .get_key_value(end)
+                .map(|(end, _)| end.as_str())
+                .into_iter()
+                .collect::<BTreeSet<&str>>(),
+        );
+        for k in 1..=rounds {
+            let next = self
+                .edges
+                .iter()
+                .filter(|(_, edges)| edges.keys().any(|to| reach[k - 1].contains(to.as_str())))
+                .map(|(from, _)| from.as_str())
+                .collect();
+            reach.push(next);
+        }
+        reach
+    }
+
+    /// Walk at random from `start` along `reach`, preferring languages in
+    /// new scripts until `scripts` scripts have been seen.
+    fn walk<'a>(
+        &'a self,
+        start: &'a str,
+        reach: &[BTreeSet<&'a str>],
+        scripts: usize,
+        rng: &mut fastrand::Rng,
+    ) -> Vec<&'a str> {
+        let rounds = reach.len() - 1;
+        let mut stops = vec![start];
+        let mut seen = BTreeSet::from([lang_script(start)]);
+        for k in (0..rounds).rev() {
+            let candidates = self
+                .neighbors(stops[stops.len() - 1])
+                .into_iter()
+                .filter(|lang| reach[k].contains(lang))
+                .collect::<Vec<&str>>();
+            let fresh = candidates
+                .iter()
+                .copied()
+                .filter(|lang| !seen.contains(lang_script(lang)))
+                .collect::<Vec<&str>>();
+            let candidates = if seen.len() < scripts && !fresh.is_empty() {
+                fresh
+            } else {
+                candidates
+            };
+            let lang = candidates[rng.usize(..candidates.len())];
+            seen.insert(lang_script(lang));
+            stops.push(lang);
+        }
+        stops
+    }
+
+    /// Get a random walk of exactly `rounds` hops from `start` to `end`
+    /// along the edges of the graph, or `None` if there is none.
+    ///
+    /// # Arguments
+    /// * `start` - The language to start from.
+    /// * `end` - The language to end with.
+    /// * `rounds` - The number of hops.
+    /// * `rng` - The random number generator to use.
+    pub fn random_walk(
+        &self,
+        start: &str,
+        end: &str,
+        rounds: usize,
+        rng: &mut fastrand::Rng,
+    ) -> Option<Vec<TranslationDirection>> {
+        self.distinct_scripts_walk(start, end, rounds, 0, rng)
+    }
+
+    /// Get a random walk like [`random_walk`](LanguageGraph::random_walk)
+    /// visiting languages in at least `scripts` different scripts, as given
+    /// by [`lang_script`], or `None` if none is found.
+    pub fn distinct_scripts_walk(
+        &self,
+        start: &str,
+        end: &str,
+        rounds: usize,
+        scripts: usize,
+        rng: &mut fastrand::Rng,
+    ) -> Option<Vec<TranslationDirection>> {
+        let (start, end) = (common_lang(start), common_lang(end));
+        let reach = self.walks_to(end, rounds);
+        let (start, _) = self.edges.get_key_value(start)?;
+        if !reach[rounds].contains(start.as_str()) {
+            return None;
+        }
+        (0..ATTEMPTS)
+            .map(|_| self.walk(start, &reach, scripts, rng))
+            .find(|stops| {
+                stops
+                    .iter()
+                    .map(|lang| lang_script(lang))
+                    .collect::<BTreeSet<&str>>()
+                    .len()
+                    >= scripts
+            })
+            .map(|stops| directions(&stops))
+    }
+
+    /// Get the path from `from` to `to` with the least total weight by
+    /// `metric`, or `None` if `to` cannot be reached.
+    pub fn shortest_path(
+        &self,
+        from: &str,
+        to: &str,
+        metric: EdgeMetric,
+    ) -> Option<Vec<TranslationDirection>> {
+        let (from, to) = (common_lang(from), common_lang(to));
+        let mut dist: BTreeMap<&str, (f64, Option<&str>)> = BTreeMap::new();
+        let mut done: BTreeSet<&str> = BTreeSet::new();
+        dist.insert(self.edges.get_key_value(from)?.0, (0.0, None));
+        while let Some((lang, (cost, _))) = dist
+            .iter()
+            .filter(|(lang, _)| !done.contains(*lang))
+            .min_by(|a, b| a.1 .0.total_cmp(&b.1 .0))
+            .map(|(lang, entry)| (*lang, *entry))
+        {
+            if lang == to {
+                break;
+            }
+            done.insert(lang);
+            for (next, weights) in &self.edges[lang] {
+                let next_cost = cost + weights.get(metric);
+                if dist
+                    .get(next.as_str())
+                    .is_none_or(|(known, _)| next_cost < *known)
+                {
+                    dist.insert(next, (next_cost, Some(lang)));
+                }
+            }
+        }
+
+        let mut stops = vec![to];
+        while let Some((_, Some(prev))) = dist.get(stops[stops.len() - 1]) {
+            stops.push(prev);
+        }
+        if stops[stops.len() - 1] != from {
+            return None;
+        }
+        stops.reverse();
+        Some(directions(&stops))
+    }
+
+    /// Get the walk of exactly `rounds` hops from `from` to `to` with the
+    /// most total weight by `metric`, or `None` if there is none.
+    pub fn longest_path(
+        &self,
+        from: &str,
+        to: &str,
+        rounds: usize,
+        metric: EdgeMetric,
+    ) -> Option<Vec<TranslationDirection>> {
+        let (from, to) = (common_lang(from), common_lang(to));
+        // best[k][lang] is the heaviest walk of k hops from lang to `to`,
+        // with the language after lang.
+        let mut best: Vec<BTreeMap<&str, (f64, &str)>> = Vec::with_capacity(rounds + 1);
+        best.push(
+            self.edges
+                .get_key_value(to)
+                .map(|(to, _)| (to.as_str(), (0.0, to.as_str())))
+                .into_iter()
+                .collect(),
+        );
+        for k in 1..=rounds {
+            let mut next = BTreeMap::new();
+            for (lang, edges) in &self.edges {
+                for (to, weights) in edges {
+                    let Some((rest, _)) = best[k - 1].get(to.as_str()) else {
+                        continue;
+                    };
+                    let total = rest + weights.get(metric);
+                    if next
+                        .get(lang.as_str())
+                        .is_none_or(|(known, _): &(f64, &str)| total > *known)
+                    {
+                        next.insert(lang.as_str(), (total, to.as_str()));
+                    }
+                }
+            }
+            best.push(next);
+        }
+
+        let mut stops = vec![self.edges.get_key_value(from)?.0.as_str()];
+        for k in (1..=rounds).rev() {
+            let (_, next) = best[k].get(stops[stops.len() - 1])?;
+            stops.push(next);
+        }
+        (stops[stops.len() - 1] == to).then(|| directions(&stops))
+    }
+}
+
+/// Follow the pairs supported by the backends the graph was built from,
+/// within the bank.
+///
+/// When the bank allows no such path, a warning is logged and the languages
+/// are chosen like [`UniformRandom`] instead.
+impl PathStrategy for LanguageGraph {
+    fn path(
+        &self,
+        original_lang: &str,
+        lang_bank: &[&str],
+        rounds: usize,
+        rng: &mut fastrand::Rng,
+    ) -> Vec<TranslationDirection> {
+        let mut langs = lang_bank.to_vec();
+        langs.push(original_lang);
+        match self
+            .restrict(&langs)
+            .random_walk(original_lang, original_lang, rounds, rng)
+        {
+            Some(path) => path,
+            None => {
+                tracing::warn!(
+                    original_lang,
+                    rounds,
+                    "no path through supported pairs, choosing languages at random"
+                );
+                UniformRandom.path(original_lang, lang_bank, rounds, rng)
+            }
+        }
+    }
+}
+
+/// Get the hops between consecutive `stops`.
+fn directions(stops: &[&str]) -> Vec<TranslationDirection> {
+    stops
+        .windows(2)
+        .map(|pair| TranslationDirection {
+            from_lang: pair[0].to_string(),
+            to_lang: pair[1].to_string(),
+        })
+        .collect()
+}
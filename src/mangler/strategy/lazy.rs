@@ -0,0 +1,90 @@
+use super::PathStrategy;
+use crate::translator::TranslationDirection;
+
+/// The number of hops of each leg of a [`LazyPath`] by default.
+const DEFAULT_LEG_ROUNDS: usize = 2;
+
+/// A path of unknown length, built leg by leg with a [`PathStrategy`] while
+/// mangling, as done by [`mangle_until`](crate::mangle_until).
+///
+/// Each leg starts from and ends with the original language, so the text
+/// can be compared with the original one between legs.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::strategy::{LazyPath, RoundTrip};
+///
+/// let mut path = LazyPath::new(&RoundTrip, "en", &["zh", "ja"], fastrand::Rng::with_seed(7));
+/// let leg = path.next_leg(10);
+/// assert_eq!(leg.len(), 2);
+/// assert_eq!(leg[0].from_lang, "en");
+/// assert_eq!(leg[1].to_lang, "en");
+/// ```
+pub struct LazyPath<'a> {
+    strategy: &'a dyn PathStrategy,
+    original_lang: String,
+    lang_bank: Vec<String>,
+    leg_rounds: usize,
+    rng: fastrand::Rng,
+}
+
+impl std::fmt::Debug for LazyPath<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("LazyPath")
+            .field("original_lang", &self.original_lang)
+            .field("lang_bank", &self.lang_bank)
+            .field("leg_rounds", &self.leg_rounds)
+            .finish()
+    }
+}
+
+impl<'a> LazyPath<'a> {
+    /// Create a path of legs of 2 hops chosen by `strategy` from
+    /// `lang_bank`, making the random choices with `rng`.
+    pub fn new(
+        strategy: &'a dyn PathStrategy,
+        original_lang: &str,
+        lang_bank: &[&str],
+        rng: fastrand::Rng,
+    ) -> LazyPath<'a> {
+        LazyPath {
+            strategy,
+            original_lang: original_lang.to_string(),
+            lang_bank: lang_bank.iter().map(|lang| lang.to_string()).collect(),
+            leg_rounds: DEFAULT_LEG_ROUNDS,
+            rng,
+        }
+    }
+
+    /// Build legs of `rounds` hops, at least 2.
+    pub fn with_leg_rounds(mut self, rounds: usize) -> LazyPath<'a> {
+        self.leg_rounds = rounds.max(2);
+        self
+    }
+
+    /// Get the language every leg starts from and ends with.
+    pub fn original_lang(&self) -> &str {
+        &self.original_lang
+    }
+
+    /// Get the next leg, of at most `max_rounds` hops.
+    pub fn next_leg(&mut self, max_rounds: usize) -> Vec<TranslationDirection> {
+        let lang_bank = self
+            .lang_bank
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+        self.strategy.path(
+            &self.original_lang,
+            &lang_bank,
+            self.leg_rounds.min(max_rounds),
+            &mut self.rng,
+        )
+    }
+
+    /// Get the random number generator of the path, to make other choices
+    /// reproducible with the same seed.
+    pub(crate) fn rng(&mut self) -> &mut fastrand::Rng {
+        &mut self.rng
+    }
+}
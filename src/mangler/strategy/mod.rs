@@ -0,0 +1,341 @@
+//! Strategies choosing the languages of a mangling path.
+//!
+//! Every strategy builds a path of `rounds` hops starting from and ending
+//! with the original language, choosing the languages in between from a
+//! bank. All random choices are made with the given `fastrand::Rng`, so a
+//! seeded generator always gives the same path.
+//!
+//! Paths can also be written by hand as a [`PathExpr`], such as
+//! `en > ja > * > en`, and checked against constraints with a
+//! [`PathPlan`]. A [`LanguageGraph`] follows only the pairs the backends
+//! translate directly. A [`LazyPath`] builds a path leg by leg while
+//! mangling, for runs of unknown length.
+//!
+//! # Example
+//! ```rust
+//! use translator_mangler::strategy::{PathStrategy, RoundTrip};
+//!
+//! let mut rng = fastrand::Rng::with_seed(7);
+//! let path = RoundTrip.path("en", &["zh", "ja", "fr"], 4, &mut rng);
+//! assert_eq!(path[1].to_lang, "en");
+//! assert_eq!(path[3].to_lang, "en");
+//! ```
+
+mod distance;
+mod expr;
+mod graph;
+mod lazy;
+mod plan;
+mod script;
+
+pub use distance::{lang_distance, MAX_LANG_DISTANCE};
+pub use expr::{PathExpr, PathExprError};
+pub use graph::{EdgeMetric, EdgeWeights, LanguageGraph};
+pub use lazy::LazyPath;
+pub use plan::{PathPlan, PathPlanError};
+pub use script::lang_script;
+
+use crate::translator::TranslationDirection;
+
+/// A way of choosing the languages of a mangling path.
+pub trait PathStrategy {
+    /// Get a path of `rounds` hops, starting from and ending with
+    /// `original_lang`, through languages from `lang_bank`.
+    ///
+    /// # Arguments
+    /// * `original_lang` - The original language to translate to.
+    /// * `lang_bank` - The list of languages to choose from.
+    /// * `rounds` - The number of pairs to get.
+    /// * `rng` - The random number generator to use.
+    fn path(
+        &self,
+        original_lang: &str,
+        lang_bank: &[&str],
+        rounds: usize,
+        rng: &mut fastrand::Rng,
+    ) -> Vec<TranslationDirection>;
+}
+
+/// Build a path visiting `langs` in order, starting from and ending with
+/// `original_lang`.
+fn path_through(original_lang: &str, langs: &[&str]) -> Vec<TranslationDirection> {
+    std::iter::once(original_lang)
+        .chain(langs.iter().copied())
+        .zip(langs.iter().copied().chain(std::iter::once(original_lang)))
+        .map(|(from, to)| TranslationDirection {
+            from_lang: from.to_string(),
+            to_lang: to.to_string(),
+        })
+        .collect()
+}
+
+/// Build a path of `rounds` hops, choosing each intermediate language with
+/// `next` from the languages visited so far.
+///
+/// `next` is also given the language to avoid if it can, which is
+/// `original_lang` for the last intermediate language, so that the path does
+/// not end with a hop to the language the text is already in.
+fn build_path(
+    original_lang: &str,
+    lang_bank: &[&str],
+    rounds: usize,
+    mut next: impl FnMut(&[&str], Option<&str>) -> String,
+) -> Vec<TranslationDirection> {
+    // Fast-fail
+    if lang_bank.is_empty() {
+        return path_through(original_lang, &[]);
+    }
+    if rounds == 0 {
+        return Vec::new();
+    }
+
+    let mut visited = vec![original_lang.to_string()];
+    for index in 1..rounds {
+        let avoid = (index == rounds - 1).then_some(original_lang);
+        let lang = next(
+            &visited.iter().map(String::as_str).collect::<Vec<&str>>(),
+            avoid,
+        );
+        visited.push(lang);
+    }
+    path_through(
+        original_lang,
+        &visited[1..]
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>(),
+    )
+}
+
+/// Get the languages of `lang_bank` other than `avoid`, or the whole bank if
+/// there is none.
+fn avoiding<'a>(lang_bank: &[&'a str], avoid: Option<&str>) -> Vec<&'a str> {
+    let others = lang_bank
+        .iter()
+        .copied()
+        .filter(|lang| Some(*lang) != avoid)
+        .collect::<Vec<&str>>();
+    if others.is_empty() {
+        lang_bank.to_vec()
+    } else {
+        others
+    }
+}
+
+/// Pick a random language from `candidates`, or from `fallback` if there
+/// is none.
+fn pick<'a>(rng: &mut fastrand::Rng, candidates: &[&'a str], fallback: &[&'a str]) -> &'a str {
+    if candidates.is_empty() {
+        fallback[rng.usize(..fallback.len())]
+    } else {
+        candidates[rng.usize(..candidates.len())]
+    }
+}
+
+/// Choose each language uniformly at random, never translating to the
+/// language the text is already in.
+///
+/// This is the strategy of [`get_random_lang_path`](crate::get_random_lang_path).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UniformRandom;
+
+impl PathStrategy for UniformRandom {
+    fn path(
+        &self,
+        original_lang: &str,
+        lang_bank: &[&str],
+        rounds: usize,
+        rng: &mut fastrand::Rng,
+    ) -> Vec<TranslationDirection> {
+        // Fast-fail
+        if lang_bank.is_empty() {
+            return path_through(original_lang, &[]);
+        }
+
+        let mut prev_lang = original_lang;
+        let mut langs: Vec<TranslationDirection> = Vec::with_capacity(rounds);
+        for i in 0..rounds {
+            // Choose a random language from the lang_bank,
+            // or original_lang if we have reached the last round.
+            let next_lang = if i == (rounds - 1) || lang_bank.iter().all(|l| *l == prev_lang) {
+                original_lang
+            } else {
+                let mut l = lang_bank[rng.usize(..lang_bank.len())];
+                // Make sure we don't get the same language twice
+                // to avoid request waste.
+                while l == prev_lang {
+                    l = lang_bank[rng.usize(..lang_bank.len())];
+                }
+                l
+            };
+            langs.push(TranslationDirection {
+                from_lang: prev_lang.to_string(),
+                to_lang: next_lang.to_string(),
+            });
+            // Move on.
+            prev_lang = next_lang;
+        }
+
+        langs
+    }
+}
+
+/// Choose each language at random among those not visited in the last
+/// `window` hops, which is at least the current language.
+///
+/// When the bank is too small for the window, the least recently visited
+/// languages are allowed again.
+#[derive(Clone, Copy, Debug)]
+pub struct NoRepeatWindow {
+    /// The number of recently visited languages to avoid.
+    pub window: usize,
+}
+
+impl NoRepeatWindow {
+    /// Create a strategy avoiding the last `window` visited languages.
+    pub fn new(window: usize) -> NoRepeatWindow {
+        NoRepeatWindow { window }
+    }
+}
+
+impl PathStrategy for NoRepeatWindow {
+    fn path(
+        &self,
+        original_lang: &str,
+        lang_bank: &[&str],
+        rounds: usize,
+        rng: &mut fastrand::Rng,
+    ) -> Vec<TranslationDirection> {
+        build_path(original_lang, lang_bank, rounds, |visited, avoid| {
+            let bank = avoiding(lang_bank, avoid);
+            // Shrink the window until some language is left.
+            let mut window = self.window.max(1).min(visited.len());
+            loop {
+                let recent = &visited[visited.len() - window..];
+                let candidates = bank
+                    .iter()
+                    .copied()
+                    .filter(|lang| !recent.contains(lang))
+                    .collect::<Vec<&str>>();
+                if !candidates.is_empty() || window == 1 {
+                    return pick(rng, &candidates, &bank).to_string();
+                }
+                window -= 1;
+            }
+        })
+    }
+}
+
+/// Go back to the original language after every hop, like
+/// `en→X→en→Y→en`.
+///
+/// With an odd number of rounds, the last trip visits two languages, like
+/// `en→X→Y→en`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RoundTrip;
+
+impl PathStrategy for RoundTrip {
+    fn path(
+        &self,
+        original_lang: &str,
+        lang_bank: &[&str],
+        rounds: usize,
+        rng: &mut fastrand::Rng,
+    ) -> Vec<TranslationDirection> {
+        let others = lang_bank
+            .iter()
+            .copied()
+            .filter(|lang| *lang != original_lang)
+            .collect::<Vec<&str>>();
+        build_path(original_lang, lang_bank, rounds, |visited, _| {
+            let prev = visited[visited.len() - 1];
+            let index = visited.len();
+            let last_trip_is_long = rounds % 2 == 1 && index == rounds - 1;
+            if prev == original_lang {
+                pick(rng, &others, lang_bank).to_string()
+            } else if last_trip_is_long {
+                let candidates = others
+                    .iter()
+                    .copied()
+                    .filter(|lang| *lang != prev)
+                    .collect::<Vec<&str>>();
+                pick(rng, &candidates, lang_bank).to_string()
+            } else {
+                original_lang.to_string()
+            }
+        })
+    }
+}
+
+/// Go through the whole bank in a random order before visiting any
+/// language again.
+///
+/// A bank of a single language is alternated with the original language.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CycleBank;
+
+impl PathStrategy for CycleBank {
+    fn path(
+        &self,
+        original_lang: &str,
+        lang_bank: &[&str],
+        rounds: usize,
+        rng: &mut fastrand::Rng,
+    ) -> Vec<TranslationDirection> {
+        let mut deck: Vec<&str> = Vec::new();
+        build_path(original_lang, lang_bank, rounds, |visited, avoid| {
+            let prev = visited[visited.len() - 1];
+            if lang_bank.iter().all(|lang| *lang == prev) {
+                return original_lang.to_string();
+            }
+            if deck.is_empty() {
+                deck = lang_bank.to_vec();
+                rng.shuffle(&mut deck);
+            }
+            let fits = |lang: &str| lang != prev && Some(lang) != avoid;
+            // Draw the last card that fits, leaving the others in the deck.
+            if let Some(index) = deck.iter().rposition(|lang| fits(lang)) {
+                return deck.remove(index).to_string();
+            }
+            // Only the last language can be left for the next cycle.
+            if avoid.is_some()
+                && let Some(lang) = lang_bank.iter().find(|lang| fits(lang))
+            {
+                return lang.to_string();
+            }
+            let index = deck.iter().rposition(|lang| *lang != prev).unwrap_or(0);
+            deck.remove(index).to_string()
+        })
+    }
+}
+
+/// Choose each language among the most distant ones from the language the
+/// text is in, as measured by [`lang_distance`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MaxDistance;
+
+impl PathStrategy for MaxDistance {
+    fn path(
+        &self,
+        original_lang: &str,
+        lang_bank: &[&str],
+        rounds: usize,
+        rng: &mut fastrand::Rng,
+    ) -> Vec<TranslationDirection> {
+        build_path(original_lang, lang_bank, rounds, |visited, avoid| {
+            let bank = avoiding(lang_bank, avoid);
+            let prev = visited[visited.len() - 1];
+            let farthest = bank
+                .iter()
+                .map(|lang| lang_distance(prev, lang))
+                .max()
+                .unwrap_or(0);
+            let candidates = bank
+                .iter()
+                .copied()
+                .filter(|lang| lang_distance(prev, lang) == farthest)
+                .collect::<Vec<&str>>();
+            pick(rng, &candidates, &bank).to_string()
+        })
+    }
+}
@@ -0,0 +1,342 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::translator::{TranslationDirection, Translator};
+
+/// How many times [`PathPlan::build`] starts over before giving up.
+const ATTEMPTS: usize = 100;
+
+/// Errors that may occur when building a [`PathPlan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPlanError {
+    /// The message string describing the error.
+    pub message: String,
+}
+
+impl std::fmt::Display for PathPlanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "PathPlanError: {}", self.message)
+    }
+}
+
+impl PathPlanError {
+    fn new(message: impl Into<String>) -> PathPlanError {
+        PathPlanError {
+            message: message.into(),
+        }
+    }
+}
+
+/// A builder of random paths meeting some constraints, failing with an
+/// error when they cannot be met instead of giving a wrong path.
+///
+/// A language is visited each time the path translates to it, so the
+/// original language is not visited by the first hop but is by the last
+/// one if the path ends there.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::strategy::PathPlan;
+///
+/// let path = PathPlan::new("en", 6)
+///     .with_bank(&["zh", "ja", "ko", "fr", "de"])
+///     .with_required(&["ja"])
+///     .with_forbidden(&["ko"])
+///     .with_min_distinct(4)
+///     .with_max_visits(2)
+///     .with_final_lang("fr")
+///     .build(&mut fastrand::Rng::with_seed(3))
+///     .unwrap();
+/// assert_eq!(path.len(), 6);
+/// assert_eq!(path[5].to_lang, "fr");
+/// assert!(path.iter().any(|hop| hop.to_lang == "ja"));
+/// assert!(path.iter().all(|hop| hop.to_lang != "ko"));
+///
+/// assert!(PathPlan::new("en", 0).build(&mut fastrand::Rng::new()).is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct PathPlan {
+    original_lang: String,
+    rounds: usize,
+    lang_bank: Vec<String>,
+    final_lang: Option<String>,
+    required: Vec<String>,
+    forbidden: Vec<String>,
+    min_distinct: usize,
+    max_visits: Option<usize>,
+}
+
+impl PathPlan {
+    /// Plan a path of `rounds` hops starting from `original_lang`, ending
+    /// there too unless [`with_final_lang`](PathPlan::with_final_lang) says
+    /// otherwise, with an empty bank and no constraints.
+    pub fn new(original_lang: &str, rounds: usize) -> PathPlan {
+        PathPlan {
+            original_lang: original_lang.to_string(),
+            rounds,
+            lang_bank: Vec::new(),
+            final_lang: None,
+            required: Vec::new(),
+            forbidden: Vec::new(),
+            min_distinct: 0,
+            max_visits: None,
+        }
+    }
+
+    /// Choose the languages in between from `lang_bank`.
+    pub fn with_bank(mut self, lang_bank: &[&str]) -> PathPlan {
+        self.lang_bank = to_owned(lang_bank);
+        self
+    }
+
+    /// End the path in `final_lang` instead of the original language.
+    pub fn with_final_lang(mut self, final_lang: &str) -> PathPlan {
+        self.final_lang = Some(final_lang.to_string());
+        self
+    }
+
+    /// Visit each of `langs` at least once, even if not in the bank.
+    pub fn with_required(mut self, langs: &[&str]) -> PathPlan {
+        self.required = to_owned(langs);
+        self
+    }
+
+    /// Never visit any of `langs`, even if in the bank.
+    pub fn with_forbidden(mut self, langs: &[&str]) -> PathPlan {
+        self.forbidden = to_owned(langs);
+        self
+    }
+
+    /// Visit at least `count` different languages, counting the original
+    /// one.
+    pub fn with_min_distinct(mut self, count: usize) -> PathPlan {
+        self.min_distinct = count;
+        self
+    }
+
+    /// Visit each language at most `count` times.
+    pub fn with_max_visits(mut self, count: usize) -> PathPlan {
+        self.max_visits = Some(count);
+        self
+    }
+
+    fn final_lang(&self) -> &str {
+        self.final_lang.as_deref().unwrap_or(&self.original_lang)
+    }
+
+    /// Check that every language the path may visit is supported by
+    /// `translator`, in its own codes or those of another backend.
+    pub fn check_supported(&self, translator: &dyn Translator) -> Result<(), PathPlanError> {
+        let langs = std::iter::once(&self.original_lang)
+            .chain(&self.final_lang)
+            .chain(&self.required)
+            .chain(&self.lang_bank);
+        for lang in langs {
+            if !self.forbidden.contains(lang)
+                && !translator.is_lang_supported(translator.native_lang(lang))
+            {
+                return Err(PathPlanError::new(format!(
+                    "language '{}' is not supported by {}",
+                    lang,
+                    translator.name()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the constraints for contradictions found without searching
+    /// for a path.
+    fn check(&self) -> Result<(), PathPlanError> {
+        let original = self.original_lang.as_str();
+        let final_lang = self.final_lang();
+        if self.rounds == 0 {
+            return Err(PathPlanError::new("the path needs at least one round"));
+        }
+        if self.rounds == 1 && original == final_lang {
+            return Err(PathPlanError::new(format!(
+                "a single round cannot go from '{}' back to itself",
+                original
+            )));
+        }
+        if self.final_lang.as_deref() == Some(original) {
+            return Err(PathPlanError::new(
+                "the final language must differ from the original one",
+            ));
+        }
+        for lang in [original, final_lang]
+            .iter()
+            .copied()
+            .chain(self.required())
+        {
+            if self.forbidden.iter().any(|forbidden| forbidden == lang) {
+                return Err(PathPlanError::new(format!(
+                    "language '{}' is forbidden but must be visited",
+                    lang
+                )));
+            }
+        }
+        if self.max_visits == Some(0) {
+            return Err(PathPlanError::new(
+                "each language must be allowed at least one visit",
+            ));
+        }
+
+        let slots = self.rounds - 1;
+        let endpoints = HashSet::from([original, final_lang]);
+        let required = self
+            .required()
+            .filter(|lang| !endpoints.contains(lang))
+            .collect::<HashSet<&str>>();
+        if required.len() > slots {
+            return Err(PathPlanError::new(format!(
+                "{} required languages do not fit in {} rounds",
+                required.len(),
+                self.rounds
+            )));
+        }
+
+        let mut reachable = endpoints.clone();
+        reachable.extend(self.pool());
+        reachable.extend(&required);
+        let most_distinct = reachable.len().min(endpoints.len() + slots);
+        if self.min_distinct > most_distinct {
+            return Err(PathPlanError::new(format!(
+                "at most {} different languages can be visited",
+                most_distinct
+            )));
+        }
+
+        if let Some(max_visits) = self.max_visits {
+            // The original language is only visited when coming back to it.
+            let mut visitable = self.pool().into_iter().collect::<HashSet<&str>>();
+            visitable.extend(&required);
+            visitable.insert(final_lang);
+            if visitable.len().saturating_mul(max_visits) < self.rounds {
+                return Err(PathPlanError::new(format!(
+                    "{} rounds need more than {} visits to each of {} languages",
+                    self.rounds,
+                    max_visits,
+                    visitable.len()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the required languages without duplicates.
+    fn required(&self) -> impl Iterator<Item = &str> {
+        let mut seen = HashSet::new();
+        self.required
+            .iter()
+            .map(String::as_str)
+            .filter(move |lang| seen.insert(*lang))
+    }
+
+    /// Get the bank without forbidden languages and duplicates.
+    fn pool(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.lang_bank
+            .iter()
+            .map(String::as_str)
+            .filter(|lang| !self.forbidden.iter().any(|forbidden| forbidden == lang))
+            .filter(|lang| seen.insert(*lang))
+            .collect()
+    }
+
+    /// Get a path meeting all constraints, choosing at random with `rng`.
+    pub fn build(
+        &self,
+        rng: &mut fastrand::Rng,
+    ) -> Result<Vec<TranslationDirection>, PathPlanError> {
+        self.check()?;
+        for _ in 0..ATTEMPTS {
+            if let Some(path) = self.attempt(rng) {
+                return Ok(path);
+            }
+        }
+        Err(PathPlanError::new(
+            "no path meeting all constraints was found",
+        ))
+    }
+
+    /// Check that `translator` supports every language, then get a path
+    /// like [`build`](PathPlan::build).
+    pub fn build_for(
+        &self,
+        translator: &dyn Translator,
+        rng: &mut fastrand::Rng,
+    ) -> Result<Vec<TranslationDirection>, PathPlanError> {
+        self.check_supported(translator)?;
+        self.build(rng)
+    }
+
+    /// Try to choose the languages in between at random, keeping room for
+    /// the languages that must still be visited.
+    fn attempt(&self, rng: &mut fastrand::Rng) -> Option<Vec<TranslationDirection>> {
+        let original = self.original_lang.as_str();
+        let final_lang = self.final_lang();
+        let pool = self.pool();
+        let slots = self.rounds - 1;
+        let max_visits = self.max_visits.unwrap_or(usize::MAX);
+
+        // Languages to visit on the way: the required ones, then enough
+        // unvisited ones to reach the minimum of distinct languages.
+        let mut distinct = HashSet::from([original, final_lang]);
+        let mut pending = self
+            .required()
+            .filter(|lang| distinct.insert(lang))
+            .collect::<Vec<&str>>();
+        let mut fresh = pool
+            .iter()
+            .copied()
+            .filter(|lang| !distinct.contains(lang))
+            .collect::<Vec<&str>>();
+        rng.shuffle(&mut fresh);
+        let missing = self.min_distinct.saturating_sub(distinct.len());
+        pending.extend(fresh.into_iter().take(missing));
+
+        let mut visits = HashMap::from([(final_lang, 1)]);
+        let mut stops = vec![original];
+        for i in 0..slots {
+            let prev = stops[stops.len() - 1];
+            let is_last = i == slots - 1;
+            let allowed = |lang: &&str| {
+                *lang != prev
+                    && !(is_last && *lang == final_lang)
+                    && visits.get(lang).copied().unwrap_or(0) < max_visits
+            };
+            let must_visit = pending.len() == slots - i;
+            let candidates = pending
+                .iter()
+                .copied()
+                .chain(pool.iter().copied().filter(|_| !must_visit))
+                .filter(allowed)
+                .collect::<Vec<&str>>();
+            if candidates.is_empty() {
+                return None;
+            }
+            let lang = candidates[rng.usize(..candidates.len())];
+            pending.retain(|pending| *pending != lang);
+            *visits.entry(lang).or_insert(0) += 1;
+            stops.push(lang);
+        }
+        if !pending.is_empty() {
+            return None;
+        }
+        stops.push(final_lang);
+
+        Some(
+            stops
+                .windows(2)
+                .map(|pair| TranslationDirection {
+                    from_lang: pair[0].to_string(),
+                    to_lang: pair[1].to_string(),
+                })
+                .collect(),
+        )
+    }
+}
+
+fn to_owned(langs: &[&str]) -> Vec<String> {
+    langs.iter().map(|lang| lang.to_string()).collect()
+}
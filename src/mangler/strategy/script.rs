@@ -0,0 +1,58 @@
+/// Language codes used by the backends, with the ISO 15924 code of the
+/// script they are written in.
+const SCRIPTS: &[(&[&str], &str)] = &[
+    (
+        &[
+            "zh", "zh-CHS", "zh-CHT", "zh-CN", "zh-TW", "cht", "yue", "wyw",
+        ],
+        "Hani",
+    ),
+    (&["ja", "jp"], "Jpan"),
+    (&["ko", "kor"], "Hang"),
+    (
+        &[
+            "ru", "bg", "bul", "uk", "sr", "be", "mk", "kk", "ky", "mn", "tg", "tt",
+        ],
+        "Cyrl",
+    ),
+    (&["ar", "ara", "fa", "ur", "ps", "sd", "ug", "ku"], "Arab"),
+    (&["he", "iw", "yi"], "Hebr"),
+    (&["el"], "Grek"),
+    (&["hi", "mr", "ne", "sa"], "Deva"),
+    (&["bn"], "Beng"),
+    (&["pa"], "Guru"),
+    (&["gu"], "Gujr"),
+    (&["ta"], "Taml"),
+    (&["te"], "Telu"),
+    (&["kn"], "Knda"),
+    (&["ml"], "Mlym"),
+    (&["si"], "Sinh"),
+    (&["th"], "Thai"),
+    (&["lo"], "Laoo"),
+    (&["km"], "Khmr"),
+    (&["my"], "Mymr"),
+    (&["ka"], "Geor"),
+    (&["hy"], "Armn"),
+    (&["am"], "Ethi"),
+];
+
+/// Get the ISO 15924 code of the script `lang` is usually written in, like
+/// `Cyrl` for Russian.
+///
+/// Codes of all supported backends are understood. Other languages are
+/// taken to be written in the Latin script, `Latn`.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::strategy::lang_script;
+///
+/// assert_eq!(lang_script("ru"), "Cyrl");
+/// assert_eq!(lang_script("kor"), "Hang");
+/// assert_eq!(lang_script("fr"), "Latn");
+/// ```
+pub fn lang_script(lang: &str) -> &'static str {
+    SCRIPTS
+        .iter()
+        .find(|(codes, _)| codes.contains(&lang))
+        .map_or("Latn", |(_, script)| script)
+}
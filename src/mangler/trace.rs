@@ -0,0 +1,133 @@
+use std::time::{Duration, SystemTime};
+
+use super::{run, single_translator_path, Hop, MangleError, MangleOptions};
+use crate::metrics::Similarity;
+use crate::translator::{TranslationDirection, Translator};
+
+/// A single completed hop of a mangling run.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MangleStep {
+    /// The direction of the translation.
+    pub direction: TranslationDirection,
+    /// The text before the hop.
+    pub input: String,
+    /// The text after the hop.
+    pub output: String,
+    /// The name of the translator used, as given by
+    /// [`Translator::name`].
+    pub backend: String,
+    /// When the request was sent, after any delay.
+    pub started_at: SystemTime,
+    /// When the translation was received.
+    pub finished_at: SystemTime,
+    /// How long the translation took, including retries.
+    pub latency: Duration,
+    /// How many times a request was retried, as told to the
+    /// [`CallContext`](crate::CallContext) of the request by a
+    /// [`RetryLayer`](crate::layer::RetryLayer) or other middleware.
+    pub retries: u64,
+}
+
+/// The record of a whole mangling run, hop by hop.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MangleTrace {
+    /// The text before mangling.
+    pub original: String,
+    /// The mangled text.
+    pub result: String,
+    /// Every hop, in order.
+    pub steps: Vec<MangleStep>,
+    /// When the run started.
+    pub started_at: SystemTime,
+    /// When the run finished.
+    pub finished_at: SystemTime,
+    /// How long the whole run took, including delays.
+    pub elapsed: Duration,
+    /// The time spent in translation over all hops.
+    pub latency: Duration,
+    /// The retries over all hops.
+    pub retries: u64,
+}
+
+impl MangleTrace {
+    /// Measure how the mangled text differs from the original text.
+    pub fn similarity(&self) -> Similarity {
+        Similarity::between(&self.original, &self.result)
+    }
+
+    /// Measure how the text after each hop differs from the original text.
+    pub fn step_similarities(&self) -> Vec<Similarity> {
+        self.steps
+            .iter()
+            .map(|step| Similarity::between(&self.original, &step.output))
+            .collect()
+    }
+}
+
+/// Mangle the `original_text` like [`mangle_with`](super::mangle_with),
+/// recording every hop.
+///
+/// # Returns
+/// The trace of the run, whose `result` is the mangled text.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::testing::TranslatorReverseWords;
+/// use translator_mangler::{get_random_lang_path, mangle_with_trace, MangleOptions};
+///
+/// let translator = TranslatorReverseWords::new();
+/// let langs = get_random_lang_path("en", &["zh", "ja"], 3);
+/// let trace = mangle_with_trace(&translator, "one two", &langs, &MangleOptions::new()).unwrap();
+///
+/// assert_eq!(trace.steps.len(), 3);
+/// assert_eq!(trace.steps[0].output, "two one");
+/// assert_eq!(trace.result, "two one");
+/// ```
+pub fn mangle_with_trace(
+    translator: &dyn Translator,
+    original_text: &str,
+    lang_path: &[TranslationDirection],
+    options: &MangleOptions,
+) -> Result<MangleTrace, MangleError> {
+    let hops = lang_path
+        .iter()
+        .map(|direction| (translator, direction))
+        .collect::<Vec<Hop>>();
+    record(original_text, |on_step| {
+        run(translator.name(), &hops, original_text, options, on_step)
+    })
+    .map_err(|e| e.with_path(&single_translator_path(lang_path)))
+}
+
+/// Record every hop of the mangling `run`.
+pub(super) fn record(
+    original_text: &str,
+    run: impl FnOnce(&mut dyn FnMut(&MangleStep)) -> Result<String, MangleError>,
+) -> Result<MangleTrace, MangleError> {
+    let started_at = SystemTime::now();
+    let mut steps = Vec::new();
+    let result = run(&mut |step| steps.push(step.clone()))?;
+    Ok(build(original_text, result, steps, started_at))
+}
+
+/// Build the trace of a run started at `started_at`, finishing now.
+pub(super) fn build(
+    original_text: &str,
+    result: String,
+    steps: Vec<MangleStep>,
+    started_at: SystemTime,
+) -> MangleTrace {
+    let finished_at = SystemTime::now();
+    MangleTrace {
+        original: original_text.to_string(),
+        result,
+        started_at,
+        finished_at,
+        elapsed: finished_at
+            .duration_since(started_at)
+            .unwrap_or(Duration::ZERO),
+        latency: steps.iter().map(|step| step.latency).sum(),
+        retries: steps.iter().map(|step| step.retries).sum(),
+        steps,
+    }
+}
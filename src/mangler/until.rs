@@ -0,0 +1,416 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::mixed::{assign_translators, backend_name};
+use super::resume::continued;
+use super::strategy::LazyPath;
+use super::trace::record;
+use super::{run_lazy, HopFailure, MangleError, MangleOptions, MangleTrace, MixedHop};
+use crate::metrics::Similarity;
+use crate::translator::{TranslationDirection, TranslationError, Translator};
+
+/// When to stop a run of [`mangle_until`].
+///
+/// The rules are checked whenever the text is back in the original
+/// language, that is after each leg of the [`LazyPath`]. The run stops at
+/// the first rule met, and before going over `max_rounds` hops in any case.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::StopRule;
+///
+/// // Stop once the text is less than half similar to the original, or has
+/// // not changed for 4 hops, or comes back to an earlier text, or after 40
+/// // hops.
+/// let rule = StopRule::new(40)
+///     .with_similarity_below(0.5)
+///     .with_unchanged_for(4)
+///     .with_cycle_detection();
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct StopRule {
+    max_rounds: usize,
+    similarity_below: Option<f64>,
+    unchanged_for: Option<usize>,
+    stop_on_cycle: bool,
+}
+
+impl StopRule {
+    /// Create a rule stopping after `max_rounds` hops.
+    pub fn new(max_rounds: usize) -> StopRule {
+        StopRule {
+            max_rounds,
+            similarity_below: None,
+            unchanged_for: None,
+            stop_on_cycle: false,
+        }
+    }
+
+    /// Stop once the [`overall`](Similarity::overall) similarity of the text
+    /// to the original is below `similarity`, between 0 and 1.
+    pub fn with_similarity_below(mut self, similarity: f64) -> StopRule {
+        self.similarity_below = Some(similarity);
+        self
+    }
+
+    /// Stop once no hop has changed the text for `hops` hops, the
+    /// translations having reached a fixed point.
+    pub fn with_unchanged_for(mut self, hops: usize) -> StopRule {
+        self.unchanged_for = Some(hops);
+        self
+    }
+
+    /// Stop once the text comes back to the original text or to the text
+    /// at the end of an earlier leg, including the one just before, having
+    /// changed in between.
+    pub fn with_cycle_detection(mut self) -> StopRule {
+        self.stop_on_cycle = true;
+        self
+    }
+
+    /// Get the number of hops the run never goes over.
+    pub fn max_rounds(&self) -> usize {
+        self.max_rounds
+    }
+}
+
+/// Why a run of [`mangle_until`] stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum StopCause {
+    /// The text became less similar to the original than wanted.
+    SimilarityBelow,
+    /// No hop changed the text for a while.
+    Unchanged,
+    /// The text came back to an earlier state.
+    Cycle,
+    /// The maximum number of hops was reached, or another leg would have
+    /// gone over it.
+    MaxRounds,
+}
+
+impl std::fmt::Display for StopCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StopCause::SimilarityBelow => write!(f, "similarity below target"),
+            StopCause::Unchanged => write!(f, "text unchanged"),
+            StopCause::Cycle => write!(f, "text cycled back"),
+            StopCause::MaxRounds => write!(f, "maximum rounds reached"),
+        }
+    }
+}
+
+/// The result of a run of [`mangle_until`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MangleOutcome {
+    /// The trace of the run, whose `result` is the mangled text.
+    pub trace: MangleTrace,
+    /// Why the run stopped.
+    pub cause: StopCause,
+}
+
+/// The texts seen in the original language during a run, and when a hop
+/// last changed the text.
+struct Checkpoints<'a> {
+    rule: &'a StopRule,
+    original: String,
+    seen: HashSet<String>,
+    last: String,
+    changed_at: usize,
+}
+
+impl<'a> Checkpoints<'a> {
+    fn new(rule: &'a StopRule, original_text: &str) -> Checkpoints<'a> {
+        Checkpoints {
+            rule,
+            original: original_text.to_string(),
+            seen: HashSet::from([original_text.to_string()]),
+            last: original_text.to_string(),
+            changed_at: 0,
+        }
+    }
+
+    /// Note `text`, the text after `hops` hops, in any language.
+    fn observe(&mut self, hops: usize, text: &str) {
+        if text != self.last {
+            self.changed_at = hops;
+            self.last = text.to_string();
+        }
+    }
+
+    /// Check the rules against `text`, back in the original language after
+    /// `hops` hops.
+    fn check(&mut self, hops: usize, text: &str) -> Option<StopCause> {
+        if hops == 0 {
+            return None;
+        }
+        if let Some(threshold) = self.rule.similarity_below
+            && Similarity::between(&self.original, text).overall() < threshold
+        {
+            return Some(StopCause::SimilarityBelow);
+        }
+        if self
+            .rule
+            .unchanged_for
+            .is_some_and(|unchanged_for| hops - self.changed_at >= unchanged_for)
+        {
+            return Some(StopCause::Unchanged);
+        }
+        if !self.seen.insert(text.to_string()) && self.rule.stop_on_cycle {
+            return Some(StopCause::Cycle);
+        }
+        None
+    }
+
+    /// Note the texts of `failure` and check the rules again at the end of
+    /// every leg it completed, as the run did before failing.
+    fn replay(&mut self, failure: &HopFailure) {
+        let mut leg_ends = failure.leg_ends.iter().peekable();
+        for (index, step) in failure.trace.steps.iter().enumerate() {
+            self.observe(index + 1, &step.output);
+            if leg_ends.next_if_eq(&&(index + 1)).is_some() {
+                self.check(index + 1, &step.output);
+            }
+        }
+    }
+}
+
+/// Mangle the `original_text` with `translator` along `path` until `rule`
+/// tells to stop, building the path one leg at a time.
+///
+/// Unlike [`mangle_with`](super::mangle_with), no hop is planned past the
+/// point where the text stops changing or reaches the wanted similarity,
+/// saving the requests a fixed number of rounds would make. Observers are
+/// told the maximum number of rounds as the total.
+///
+/// # Returns
+/// The trace of the run with the reason it stopped, or an error like
+/// [`mangle_with`](super::mangle_with).
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::strategy::{LazyPath, UniformRandom};
+/// use translator_mangler::testing::TranslatorReverseWords;
+/// use translator_mangler::{mangle_until, MangleOptions, StopCause, StopRule};
+///
+/// let translator = TranslatorReverseWords::new();
+/// let mut path = LazyPath::new(&UniformRandom, "en", &["zh", "ja"], fastrand::Rng::new());
+/// let rule = StopRule::new(20).with_cycle_detection();
+///
+/// // Reversing the words twice gives the original text back.
+/// let outcome = mangle_until(&translator, "one two", &mut path, &rule, &MangleOptions::new())
+///     .unwrap();
+/// assert_eq!(outcome.cause, StopCause::Cycle);
+/// assert_eq!(outcome.trace.steps.len(), 2);
+/// ```
+pub fn mangle_until(
+    translator: &dyn Translator,
+    original_text: &str,
+    path: &mut LazyPath,
+    rule: &StopRule,
+    options: &MangleOptions,
+) -> Result<MangleOutcome, MangleError> {
+    run_until(
+        &[translator],
+        original_text,
+        path,
+        rule,
+        options,
+        None,
+        &mut |leg, _| Ok(vec![0; leg.len()]),
+    )
+}
+
+/// Mangle the `original_text` like [`mangle_until`], sending each hop to one
+/// of `translators` chosen at random like
+/// [`assign_translators`](super::assign_translators).
+///
+/// The choices are made with the random number generator of `path`, so
+/// the same seed gives the same translators.
+pub fn mangle_mixed_until(
+    translators: &[&dyn Translator],
+    original_text: &str,
+    path: &mut LazyPath,
+    rule: &StopRule,
+    options: &MangleOptions,
+) -> Result<MangleOutcome, MangleError> {
+    run_until(
+        translators,
+        original_text,
+        path,
+        rule,
+        options,
+        None,
+        &mut |leg, rng| {
+            Ok(assign_translators(leg, translators, rng)?
+                .into_iter()
+                .map(|hop| hop.translator)
+                .collect())
+        },
+    )
+}
+
+/// Continue a run of [`mangle_until`] or [`mangle_mixed_until`] that failed
+/// at a hop, with the `path` and `rule` of the run.
+///
+/// The rest of the failed leg is sent to the translators it was assigned
+/// to among `translators`, then the run goes on leg by leg as if it had not
+/// failed: the rules are checked against the whole run, from the ends of
+/// the legs recorded in [`leg_ends`](HopFailure::leg_ends), and the maximum
+/// number of rounds counts the hops completed before the failure. `path`
+/// must be the one the run was left with, so that the same seed gives the
+/// same legs.
+///
+/// # Returns
+/// The outcome of the whole run, or an error like
+/// [`resume_mangle_with`](super::resume_mangle_with). Another failure can
+/// be resumed in turn.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::strategy::{LazyPath, RoundTrip};
+/// use translator_mangler::testing::TranslatorScripted;
+/// use translator_mangler::{mangle_until, resume_mangle_until, MangleError, MangleOptions, StopCause, StopRule};
+///
+/// let translator = TranslatorScripted::new()
+///     .then_ok("一二")
+///     .then_err("quota exhausted")
+///     .then_ok("two one")
+///     .then_ok("一二")
+///     .then_ok("two one");
+/// let mut path = LazyPath::new(&RoundTrip, "en", &["zh"], fastrand::Rng::with_seed(7));
+/// let rule = StopRule::new(20).with_cycle_detection();
+///
+/// let Err(MangleError::HopFailed(failure)) =
+///     mangle_until(&translator, "one two", &mut path, &rule, &MangleOptions::new())
+/// else {
+///     panic!("the second hop should fail");
+/// };
+/// let outcome =
+///     resume_mangle_until(&failure, &[&translator], &mut path, &rule, &MangleOptions::new())
+///         .unwrap();
+/// assert_eq!(outcome.cause, StopCause::Cycle);
+/// assert_eq!(outcome.trace.steps.len(), 4);
+/// ```
+pub fn resume_mangle_until(
+    failure: &HopFailure,
+    translators: &[&dyn Translator],
+    path: &mut LazyPath,
+    rule: &StopRule,
+    options: &MangleOptions,
+) -> Result<MangleOutcome, MangleError> {
+    let mut cause = StopCause::MaxRounds;
+    let trace = continued(
+        failure,
+        run_until(
+            translators,
+            &failure.trace.original,
+            path,
+            rule,
+            options,
+            Some(failure),
+            &mut |leg, rng| {
+                Ok(assign_translators(leg, translators, rng)?
+                    .into_iter()
+                    .map(|hop| hop.translator)
+                    .collect())
+            },
+        )
+        .map(|outcome| {
+            cause = outcome.cause;
+            outcome.trace
+        }),
+    )?;
+    Ok(MangleOutcome { trace, cause })
+}
+
+/// Choose the index of the translator of each hop of a leg.
+type Assign<'a> = dyn FnMut(&[TranslationDirection], &mut fastrand::Rng) -> Result<Vec<usize>, TranslationError>
+    + 'a;
+
+/// Run the mangling loop leg by leg, choosing the translator of each hop of
+/// a leg with `assign`.
+///
+/// A `resumed` run starts from the failed hop, and its trace and errors
+/// only cover the hops from there on.
+fn run_until(
+    translators: &[&dyn Translator],
+    original_text: &str,
+    path: &mut LazyPath,
+    rule: &StopRule,
+    options: &MangleOptions,
+    resumed: Option<&HopFailure>,
+    assign: &mut Assign,
+) -> Result<MangleOutcome, MangleError> {
+    let mut checkpoints = Checkpoints::new(rule, original_text);
+    let mut leg: VecDeque<(usize, TranslationDirection)> = VecDeque::new();
+    let mut leg_ends = resumed.map_or_else(Vec::new, |failure| failure.leg_ends.clone());
+    let (done, start_text) = match resumed {
+        Some(failure) => {
+            checkpoints.replay(failure);
+            leg.extend(
+                failure
+                    .remaining
+                    .iter()
+                    .map(|hop| (hop.translator, hop.direction.clone())),
+            );
+            (failure.index, failure.text())
+        }
+        None => (0, original_text),
+    };
+    let mut cause = StopCause::MaxRounds;
+    let mut current: Option<MixedHop> = None;
+    let trace = record(start_text, |on_step| {
+        run_lazy(
+            backend_name(translators),
+            translators,
+            rule.max_rounds.saturating_sub(done),
+            start_text,
+            options,
+            &mut |index, text| {
+                let index = done + index;
+                checkpoints.observe(index, text);
+                if leg.is_empty() {
+                    if let Some(reached) = checkpoints.check(index, text) {
+                        cause = reached;
+                        return Ok(None);
+                    }
+                    if index > 0 {
+                        leg_ends.push(index);
+                    }
+                    // A single hop left could not come back.
+                    let remaining = rule.max_rounds.saturating_sub(index);
+                    if remaining < 2 {
+                        return Ok(None);
+                    }
+                    let directions = path.next_leg(remaining);
+                    let assigned = assign(&directions, path.rng())?;
+                    leg = assigned.into_iter().zip(directions).collect();
+                }
+                Ok(leg.pop_front().map(|(translator, direction)| {
+                    current = Some(MixedHop {
+                        direction: direction.clone(),
+                        translator,
+                    });
+                    (translators[translator], direction)
+                }))
+            },
+            on_step,
+        )
+    })
+    .map_err(|e| match e {
+        // Only the rest of the current leg is planned, the legs after it
+        // are drawn from `path` on resuming.
+        MangleError::HopFailed(mut failure) => {
+            failure.remaining = current
+                .into_iter()
+                .chain(leg.into_iter().map(|(translator, direction)| MixedHop {
+                    direction,
+                    translator,
+                }))
+                .collect();
+            failure.leg_ends = leg_ends;
+            MangleError::HopFailed(failure)
+        }
+        e => e,
+    })?;
+    Ok(MangleOutcome { trace, cause })
+}
@@ -0,0 +1,288 @@
+//! Measures of how far a mangled text has drifted from the original.
+//!
+//! Texts are split into words at whitespace, with punctuation as separate
+//! tokens. Characters of scripts written without spaces, such as Chinese,
+//! Japanese or Thai, are tokens of their own, so all measures work at the
+//! character level for them.
+//!
+//! Scores are between 0 and 1, 1 meaning identical texts. Not being meant
+//! for machine translation evaluation, the measures are computed on single
+//! texts and are not comparable with corpus-level scores.
+//!
+//! # Example
+//! ```rust
+//! use translator_mangler::metrics::{bleu, char_edit_distance, token_jaccard, Similarity};
+//!
+//! assert_eq!(char_edit_distance("kitten", "sitting"), 3);
+//! assert_eq!(token_jaccard("我爱你", "我想你"), 0.5);
+//! assert_eq!(bleu("the cat sat", "the cat sat"), 1.0);
+//!
+//! let similarity = Similarity::between("The cat sat on the mat.", "A cat was on a mat.");
+//! assert!(similarity.mangle_score() > 0.0 && similarity.mangle_score() < 100.0);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+/// The longest character n-grams counted by [`chrf`].
+const CHRF_ORDER: usize = 6;
+/// The weight of recall over precision in [`chrf`].
+const CHRF_BETA: f64 = 2.0;
+/// The longest word n-grams counted by [`bleu`].
+const BLEU_ORDER: usize = 4;
+
+/// Check if `c` belongs to a script written without spaces between words.
+fn is_unspaced(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0E00}'..='\u{0EFF}' // Thai, Lao
+            | '\u{1780}'..='\u{17FF}' // Khmer
+            | '\u{3040}'..='\u{30FF}' // Hiragana, Katakana
+            | '\u{3400}'..='\u{4DBF}' // CJK Extension A
+            | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+            | '\u{AC00}'..='\u{D7AF}' // Hangul Syllables
+            | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+            | '\u{20000}'..='\u{2FA1F}' // CJK Extensions B and later
+    )
+}
+
+/// Check if `c` is punctuation, including full-width CJK punctuation.
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || matches!(
+            c,
+            '\u{2000}'..='\u{206F}' // General Punctuation
+                | '\u{3000}'..='\u{303F}' // CJK Symbols and Punctuation
+                | '\u{FF00}'..='\u{FF0F}'
+                | '\u{FF1A}'..='\u{FF20}'
+                | '\u{FF3B}'..='\u{FF40}'
+                | '\u{FF5B}'..='\u{FF65}' // Full-width punctuation
+        )
+}
+
+/// Split `text` into words, punctuation marks and single characters of
+/// scripts written without spaces.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::metrics::tokenize;
+///
+/// assert_eq!(tokenize("Hello, 世界!"), ["Hello", ",", "世", "界", "!"]);
+/// ```
+pub fn tokenize(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut word_start = None;
+    for (i, c) in text.char_indices() {
+        let alone = !c.is_whitespace() && (is_unspaced(c) || is_punctuation(c));
+        if c.is_whitespace() || alone {
+            if let Some(start) = word_start.take() {
+                tokens.push(&text[start..i]);
+            }
+            if alone {
+                tokens.push(&text[i..i + c.len_utf8()]);
+            }
+        } else if word_start.is_none() {
+            word_start = Some(i);
+        }
+    }
+    if let Some(start) = word_start {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+/// Get the Levenshtein distance between two sequences.
+fn levenshtein<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let mut prev = (0..=b.len()).collect::<Vec<usize>>();
+    let mut current = vec![0; b.len() + 1];
+    for (i, x) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(x != y);
+            current[j + 1] = substitution.min(prev[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut current);
+    }
+    prev[b.len()]
+}
+
+/// Get the number of characters to insert, delete or replace to turn `a`
+/// into `b`.
+pub fn char_edit_distance(a: &str, b: &str) -> usize {
+    levenshtein(
+        &a.chars().collect::<Vec<char>>(),
+        &b.chars().collect::<Vec<char>>(),
+    )
+}
+
+/// Get the number of tokens to insert, delete or replace to turn `a` into
+/// `b`, as split by [`tokenize`].
+pub fn word_edit_distance(a: &str, b: &str) -> usize {
+    levenshtein(&tokenize(a), &tokenize(b))
+}
+
+/// Count the n-grams of `items`.
+fn ngrams<T: Eq + std::hash::Hash>(items: &[T], n: usize) -> HashMap<&[T], usize> {
+    let mut counts = HashMap::new();
+    for gram in items.windows(n) {
+        *counts.entry(gram).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Count the n-grams of `hypothesis` also in `reference`, each as many
+/// times as it is in both.
+fn clipped_matches<T: Eq + std::hash::Hash>(
+    hypothesis: &HashMap<&[T], usize>,
+    reference: &HashMap<&[T], usize>,
+) -> usize {
+    hypothesis
+        .iter()
+        .map(|(gram, count)| (*count).min(reference.get(gram).copied().unwrap_or(0)))
+        .sum()
+}
+
+/// Get the chrF score of `hypothesis` against `reference`, the F-score of
+/// their character n-grams up to 6 characters, recall weighing twice as
+/// much as precision. Whitespace is ignored.
+pub fn chrf(hypothesis: &str, reference: &str) -> f64 {
+    let hypothesis = hypothesis
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<char>>();
+    let reference = reference
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<Vec<char>>();
+    if hypothesis.is_empty() && reference.is_empty() {
+        return 1.0;
+    }
+
+    let (mut precision, mut recall, mut orders) = (0.0, 0.0, 0);
+    for n in 1..=CHRF_ORDER {
+        let hypothesis_grams = ngrams(&hypothesis, n);
+        let reference_grams = ngrams(&reference, n);
+        let hypothesis_count = hypothesis.len().saturating_sub(n - 1);
+        let reference_count = reference.len().saturating_sub(n - 1);
+        if hypothesis_count == 0 && reference_count == 0 {
+            break;
+        }
+        let matches = clipped_matches(&hypothesis_grams, &reference_grams) as f64;
+        if hypothesis_count > 0 {
+            precision += matches / hypothesis_count as f64;
+        }
+        if reference_count > 0 {
+            recall += matches / reference_count as f64;
+        }
+        orders += 1;
+    }
+    let (precision, recall) = (precision / orders as f64, recall / orders as f64);
+    if precision + recall == 0.0 {
+        return 0.0;
+    }
+    let beta2 = CHRF_BETA * CHRF_BETA;
+    (1.0 + beta2) * precision * recall / (beta2 * precision + recall)
+}
+
+/// Get the BLEU score of `hypothesis` against `reference`, over tokens as
+/// split by [`tokenize`] and n-grams up to 4 tokens.
+///
+/// Missing n-grams longer than one token are smoothed by adding one to
+/// their counts, so that short texts do not always score 0.
+pub fn bleu(hypothesis: &str, reference: &str) -> f64 {
+    let hypothesis = tokenize(hypothesis);
+    let reference = tokenize(reference);
+    if hypothesis.is_empty() || reference.is_empty() {
+        return if hypothesis.len() == reference.len() {
+            1.0
+        } else {
+            0.0
+        };
+    }
+
+    let mut log_precision = 0.0;
+    for n in 1..=BLEU_ORDER {
+        let matches = clipped_matches(&ngrams(&hypothesis, n), &ngrams(&reference, n)) as f64;
+        let total = hypothesis.len().saturating_sub(n - 1) as f64;
+        let precision = if n == 1 {
+            matches / total
+        } else {
+            (matches + 1.0) / (total + 1.0)
+        };
+        if precision == 0.0 {
+            return 0.0;
+        }
+        log_precision += precision.ln() / BLEU_ORDER as f64;
+    }
+    let brevity_penalty = if hypothesis.len() >= reference.len() {
+        1.0
+    } else {
+        (1.0 - reference.len() as f64 / hypothesis.len() as f64).exp()
+    };
+    brevity_penalty * log_precision.exp()
+}
+
+/// Get the Jaccard index of the sets of tokens of `a` and `b`, as split by
+/// [`tokenize`]: the share of distinct tokens found in both.
+pub fn token_jaccard(a: &str, b: &str) -> f64 {
+    let a = tokenize(a).into_iter().collect::<HashSet<&str>>();
+    let b = tokenize(b).into_iter().collect::<HashSet<&str>>();
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// All measures between an original text and a mangled one.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Similarity {
+    /// The [`char_edit_distance`] between the texts.
+    pub char_distance: usize,
+    /// The [`word_edit_distance`] between the texts.
+    pub word_distance: usize,
+    /// The [`chrf`] score of the mangled text.
+    pub chrf: f64,
+    /// The [`bleu`] score of the mangled text.
+    pub bleu: f64,
+    /// The [`token_jaccard`] index of the texts.
+    pub jaccard: f64,
+    /// The length of the longer text in characters.
+    pub max_chars: usize,
+}
+
+impl Similarity {
+    /// Measure how `mangled` differs from `original`.
+    pub fn between(original: &str, mangled: &str) -> Similarity {
+        Similarity {
+            char_distance: char_edit_distance(original, mangled),
+            word_distance: word_edit_distance(original, mangled),
+            chrf: chrf(mangled, original),
+            bleu: bleu(mangled, original),
+            jaccard: token_jaccard(original, mangled),
+            max_chars: original.chars().count().max(mangled.chars().count()),
+        }
+    }
+
+    /// Get how close the texts are overall, from 0 for texts sharing
+    /// nothing to 1 for identical texts.
+    ///
+    /// This is the average of the chrF score, the BLEU score, the token
+    /// Jaccard index and the share of characters left unchanged.
+    pub fn overall(&self) -> f64 {
+        let unchanged = if self.max_chars == 0 {
+            1.0
+        } else {
+            1.0 - self.char_distance as f64 / self.max_chars as f64
+        };
+        (self.chrf + self.bleu + self.jaccard + unchanged) / 4.0
+    }
+
+    /// Get how mangled the text is, from 0 for an unchanged text to 100 for
+    /// a text sharing nothing with the original.
+    ///
+    /// This is 100 times one minus the [`overall`](Similarity::overall)
+    /// similarity.
+    pub fn mangle_score(&self) -> f64 {
+        100.0 * (1.0 - self.overall())
+    }
+}
@@ -0,0 +1,87 @@
+//! Instrumentation of translation requests and mangling runs.
+//!
+//! Every backend request is wrapped in a `translate` span and every run of
+//! [`mangle`](crate::mangle) in a `mangle` span, with one `hop` span per
+//! translation. Spans carry the backend name, the direction, the length of
+//! the text in characters, the attempt number, from 1 and raised by
+//! [`RetryLayer`](crate::layer::RetryLayer) on each retry, the latency and
+//! the error kind. They never carry the text itself or any credentials.
+//!
+//! With the `metrics` feature, the same events are counted and can be
+//! exported in Prometheus text format with [`render_metrics`].
+
+#[cfg(feature = "metrics")]
+mod prometheus;
+
+#[cfg(feature = "metrics")]
+pub use prometheus::render_metrics;
+
+use std::time::Instant;
+
+use crate::translator::{TranslationDirection, TranslationError};
+
+/// Send a single backend request with `send`, instrumented with a
+/// `translate` span.
+///
+/// For a batched request, `text` is all the text sent in it. `attempt` is
+/// the attempt number from the [`CallContext`](crate::CallContext) of the
+/// call.
+pub(crate) fn instrument_request<T, F>(
+    backend: &str,
+    text: &str,
+    lang: &TranslationDirection,
+    attempt: u32,
+    send: F,
+) -> Result<T, TranslationError>
+where
+    F: FnOnce() -> Result<T, TranslationError>,
+{
+    let span = tracing::info_span!(
+        "translate",
+        backend,
+        from = %lang.from_lang,
+        to = %lang.to_lang,
+        text_len = text.chars().count(),
+        attempt,
+        latency_ms = tracing::field::Empty,
+        error_kind = tracing::field::Empty,
+    );
+    let _entered = span.enter();
+
+    let start = Instant::now();
+    let result = send();
+    let latency = start.elapsed();
+
+    span.record("latency_ms", latency.as_millis() as u64);
+    match &result {
+        Ok(_) => tracing::debug!("request done"),
+        Err(e) => {
+            span.record("error_kind", e.kind.as_str());
+            tracing::warn!(error = %e.message, "request failed");
+        }
+    }
+    #[cfg(feature = "metrics")]
+    prometheus::record_request(backend, result.as_ref().err().map(|e| e.kind), latency);
+
+    result
+}
+
+/// Note that a request to `backend` failed with `error` and is retried.
+pub(crate) fn record_retry(backend: &str, attempt: u32, error: &TranslationError) {
+    tracing::info!(
+        backend,
+        attempt,
+        error_kind = error.kind.as_str(),
+        "retrying request"
+    );
+    #[cfg(feature = "metrics")]
+    prometheus::record_retry(backend);
+}
+
+/// Note that a mangling run finished after `hops` successful translations.
+pub(crate) fn record_mangle(ok: bool, hops: usize) {
+    #[cfg(feature = "metrics")]
+    prometheus::record_mangle(ok, hops);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (ok, hops);
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::translator::TranslationErrorKind;
+
+/// Upper bounds of the latency histogram buckets, in seconds.
+const LATENCY_BUCKETS: [f64; 9] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+struct Registry {
+    /// Requests by backend and outcome.
+    requests: BTreeMap<(String, &'static str), u64>,
+    /// Request latencies by backend.
+    latencies: BTreeMap<String, Histogram>,
+    /// Retries by backend.
+    retries: BTreeMap<String, u64>,
+    /// Mangling runs by outcome.
+    mangles: BTreeMap<&'static str, u64>,
+    /// Successful hops over all mangling runs.
+    hops: u64,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    requests: BTreeMap::new(),
+    latencies: BTreeMap::new(),
+    retries: BTreeMap::new(),
+    mangles: BTreeMap::new(),
+    hops: 0,
+});
+
+pub(crate) fn record_request(
+    backend: &str,
+    error: Option<TranslationErrorKind>,
+    latency: Duration,
+) {
+    let mut registry = REGISTRY.lock().unwrap();
+    let outcome = error.map_or("ok", |kind| kind.as_str());
+    *registry
+        .requests
+        .entry((backend.to_string(), outcome))
+        .or_default() += 1;
+
+    let seconds = latency.as_secs_f64();
+    let histogram = registry
+        .latencies
+        .entry(backend.to_string())
+        .or_insert(Histogram {
+            buckets: [0; LATENCY_BUCKETS.len()],
+            sum: 0.0,
+            count: 0,
+        });
+    if let Some(i) = LATENCY_BUCKETS.iter().position(|le| seconds <= *le) {
+        histogram.buckets[i] += 1;
+    }
+    histogram.sum += seconds;
+    histogram.count += 1;
+}
+
+pub(crate) fn record_retry(backend: &str) {
+    *REGISTRY
+        .lock()
+        .unwrap()
+        .retries
+        .entry(backend.to_string())
+        .or_default() += 1;
+}
+
+pub(crate) fn record_mangle(ok: bool, hops: usize) {
+    let mut registry = REGISTRY.lock().unwrap();
+    *registry
+        .mangles
+        .entry(if ok { "ok" } else { "error" })
+        .or_default() += 1;
+    registry.hops += hops as u64;
+}
+
+/// Render all metrics collected so far in Prometheus text format.
+///
+/// # Example
+/// ```rust
+/// let metrics = translator_mangler::telemetry::render_metrics();
+/// assert!(metrics.contains("# TYPE translator_requests_total counter"));
+/// ```
+pub fn render_metrics() -> String {
+    let registry = REGISTRY.lock().unwrap();
+    let mut out = String::new();
+
+    out += "# HELP translator_requests_total Backend requests by outcome.\n";
+    out += "# TYPE translator_requests_total counter\n";
+    for ((backend, outcome), count) in &registry.requests {
+        let _ = writeln!(
+            out,
+            "translator_requests_total{{backend=\"{}\",outcome=\"{}\"}} {}",
+            backend, outcome, count
+        );
+    }
+
+    out += "# HELP translator_request_duration_seconds Backend request latency.\n";
+    out += "# TYPE translator_request_duration_seconds histogram\n";
+    for (backend, histogram) in &registry.latencies {
+        let mut cumulative = 0;
+        for (le, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets.iter()) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "translator_request_duration_seconds_bucket{{backend=\"{}\",le=\"{}\"}} {}",
+                backend, le, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "translator_request_duration_seconds_bucket{{backend=\"{}\",le=\"+Inf\"}} {}",
+            backend, histogram.count
+        );
+        let _ = writeln!(
+            out,
+            "translator_request_duration_seconds_sum{{backend=\"{}\"}} {}",
+            backend, histogram.sum
+        );
+        let _ = writeln!(
+            out,
+            "translator_request_duration_seconds_count{{backend=\"{}\"}} {}",
+            backend, histogram.count
+        );
+    }
+
+    out += "# HELP translator_retries_total Retried backend requests.\n";
+    out += "# TYPE translator_retries_total counter\n";
+    for (backend, count) in &registry.retries {
+        let _ = writeln!(
+            out,
+            "translator_retries_total{{backend=\"{}\"}} {}",
+            backend, count
+        );
+    }
+
+    out += "# HELP mangle_runs_total Mangling runs by outcome.\n";
+    out += "# TYPE mangle_runs_total counter\n";
+    for (outcome, count) in &registry.mangles {
+        let _ = writeln!(
+            out,
+            "mangle_runs_total{{outcome=\"{}\"}} {}",
+            outcome, count
+        );
+    }
+
+    out += "# HELP mangle_hops_total Successful translations over all mangling runs.\n";
+    out += "# TYPE mangle_hops_total counter\n";
+    let _ = writeln!(out, "mangle_hops_total {}", registry.hops);
+
+    out
+}
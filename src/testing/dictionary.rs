@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use super::{check_langs, map_words, DEFAULT_LANGS};
+use crate::translator::{TranslationDirection, TranslationError, Translator};
+
+/// A translator swapping words according to a dictionary.
+///
+/// Each pair in the dictionary is swapped both ways, so translating twice
+/// gives back the original text. Words not in the dictionary are kept.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::testing::TranslatorDictionary;
+/// use translator_mangler::{TranslationDirection, Translator};
+///
+/// let translator = TranslatorDictionary::new(&[("cat", "dog"), ("black", "white")]);
+/// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
+///
+/// assert_eq!(
+///     translator.translate("The black cat chased a white dog.", &lang).unwrap(),
+///     "The white dog chased a black cat."
+/// );
+/// ```
+#[derive(Debug)]
+pub struct TranslatorDictionary {
+    words: HashMap<String, String>,
+    langs: Vec<&'static str>,
+}
+
+impl TranslatorDictionary {
+    /// Create an instance of `TranslatorDictionary` swapping each pair in
+    /// `pairs`, supporting [`DEFAULT_LANGS`].
+    pub fn new(pairs: &[(&str, &str)]) -> TranslatorDictionary {
+        let mut words = HashMap::new();
+        for (a, b) in pairs {
+            words.insert(a.to_string(), b.to_string());
+            words.insert(b.to_string(), a.to_string());
+        }
+        TranslatorDictionary {
+            words,
+            langs: DEFAULT_LANGS.to_vec(),
+        }
+    }
+
+    /// Support `langs` instead of the default languages.
+    pub fn with_langs(mut self, langs: &[&'static str]) -> TranslatorDictionary {
+        self.langs = langs.to_vec();
+        self
+    }
+}
+
+impl Translator for TranslatorDictionary {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        check_langs(&self.langs, lang)?;
+        Ok(map_words(text, |word| {
+            self.words
+                .get(word)
+                .cloned()
+                .unwrap_or_else(|| word.to_string())
+        }))
+    }
+
+    fn name(&self) -> &str {
+        "dictionary"
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        &self.langs
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        self.langs.contains(&single_lang)
+    }
+}
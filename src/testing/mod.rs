@@ -0,0 +1,65 @@
+//! Deterministic, offline translators for tests and demos.
+//!
+//! None of these translators talk to a network or need credentials, and
+//! each gives the same output for the same input every time. They support
+//! [`DEFAULT_LANGS`] unless configured otherwise with `with_langs`.
+//!
+//! # Example
+//! ```rust
+//! use translator_mangler::testing::TranslatorReverseWords;
+//! use translator_mangler::{get_random_lang_path, mangle};
+//!
+//! let translator = TranslatorReverseWords::new();
+//! let path = get_random_lang_path("en", &["zh", "ja"], 3);
+//! let result = mangle(&translator, "one two three", &path, 0).unwrap();
+//! assert_eq!(result, "three two one");
+//! ```
+
+mod dictionary;
+mod scripted;
+mod words;
+
+pub use dictionary::TranslatorDictionary;
+pub use scripted::TranslatorScripted;
+pub use words::{TranslatorPigLatin, TranslatorReverseWords, TranslatorShuffle};
+
+use crate::translator::{TranslationDirection, TranslationError, TranslationErrorKind};
+
+/// The languages supported by the fake translators by default.
+pub const DEFAULT_LANGS: [&str; 10] = ["en", "zh", "ja", "ko", "fr", "de", "es", "ru", "ar", "it"];
+
+/// Fail the same way real backends do if either language is not in `langs`.
+fn check_langs(
+    langs: &[&'static str],
+    lang: &TranslationDirection,
+) -> Result<(), TranslationError> {
+    if !langs.contains(&lang.from_lang.as_str()) || !langs.contains(&lang.to_lang.as_str()) {
+        return Err(TranslationError {
+            kind: TranslationErrorKind::UnsupportedLanguage,
+            message: "Unsupported language".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Apply `f` to each maximal run of alphanumeric characters in `text`,
+/// keeping everything else as it is.
+fn map_words(text: &str, mut f: impl FnMut(&str) -> String) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            word.push(c);
+        } else {
+            if !word.is_empty() {
+                result += &f(&word);
+                word.clear();
+            }
+            result.push(c);
+        }
+    }
+    if !word.is_empty() {
+        result += &f(&word);
+    }
+    result
+}
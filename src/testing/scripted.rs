@@ -0,0 +1,139 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use super::{check_langs, DEFAULT_LANGS};
+use crate::translator::{TranslationDirection, TranslationError, TranslationErrorKind, Translator};
+
+/// A translator answering each call with the next queued output or error.
+///
+/// Every call is recorded and can be inspected with `calls`. Once the queue
+/// is empty, calls fail with an error.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::testing::TranslatorScripted;
+/// use translator_mangler::{TranslationDirection, Translator};
+///
+/// let translator = TranslatorScripted::new()
+///     .then_ok("Bonjour")
+///     .then_err("NETWORK ERR: connection reset");
+/// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "fr".to_string() };
+///
+/// assert_eq!(translator.translate("Hello", &lang).unwrap(), "Bonjour");
+/// assert!(translator.translate("Hello", &lang).is_err());
+/// assert_eq!(translator.calls().len(), 2);
+/// ```
+#[derive(Debug)]
+pub struct TranslatorScripted {
+    script: Mutex<VecDeque<Result<String, TranslationError>>>,
+    calls: Mutex<Vec<(String, TranslationDirection)>>,
+    langs: Vec<&'static str>,
+}
+
+impl TranslatorScripted {
+    /// Create an instance of `TranslatorScripted` with an empty script,
+    /// supporting [`DEFAULT_LANGS`].
+    pub fn new() -> TranslatorScripted {
+        TranslatorScripted {
+            script: Mutex::new(VecDeque::new()),
+            calls: Mutex::new(Vec::new()),
+            langs: DEFAULT_LANGS.to_vec(),
+        }
+    }
+
+    /// Support `langs` instead of the default languages.
+    pub fn with_langs(mut self, langs: &[&'static str]) -> TranslatorScripted {
+        self.langs = langs.to_vec();
+        self
+    }
+
+    /// Queue a successful translation to `output`.
+    pub fn then_ok(self, output: &str) -> TranslatorScripted {
+        self.push_ok(output);
+        self
+    }
+
+    /// Queue a failed translation with the error message `message`.
+    pub fn then_err(self, message: &str) -> TranslatorScripted {
+        self.push_err(message);
+        self
+    }
+
+    /// Queue a failed translation with `error`, for example to test how
+    /// errors of a specific kind are handled.
+    pub fn then_error(self, error: TranslationError) -> TranslatorScripted {
+        self.push_error(error);
+        self
+    }
+
+    /// Queue a successful translation to `output` on a shared instance.
+    pub fn push_ok(&self, output: &str) {
+        self.script
+            .lock()
+            .unwrap()
+            .push_back(Ok(output.to_string()));
+    }
+
+    /// Queue a failed translation on a shared instance.
+    pub fn push_err(&self, message: &str) {
+        self.push_error(TranslationError {
+            kind: TranslationErrorKind::Other,
+            message: message.to_string(),
+        });
+    }
+
+    /// Queue a failed translation with `error` on a shared instance.
+    pub fn push_error(&self, error: TranslationError) {
+        self.script.lock().unwrap().push_back(Err(error));
+    }
+
+    /// Get the number of queued outputs and errors not yet used.
+    pub fn remaining(&self) -> usize {
+        self.script.lock().unwrap().len()
+    }
+
+    /// Get the text and direction of every call so far, in order.
+    pub fn calls(&self) -> Vec<(String, TranslationDirection)> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+impl Default for TranslatorScripted {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Translator for TranslatorScripted {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push((text.to_string(), lang.clone()));
+        check_langs(&self.langs, lang)?;
+
+        match self.script.lock().unwrap().pop_front() {
+            Some(result) => result,
+            None => Err(TranslationError {
+                kind: TranslationErrorKind::Other,
+                message: "Script exhausted".to_string(),
+            }),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "scripted"
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        &self.langs
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        self.langs.contains(&single_lang)
+    }
+}
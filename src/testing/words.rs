@@ -0,0 +1,235 @@
+use super::{check_langs, map_words, DEFAULT_LANGS};
+use crate::translator::{TranslationDirection, TranslationError, Translator};
+
+/// A translator reversing the order of the words on each line.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::testing::TranslatorReverseWords;
+/// use translator_mangler::{TranslationDirection, Translator};
+///
+/// let translator = TranslatorReverseWords::new();
+/// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
+///
+/// assert_eq!(translator.translate("Hello, world!", &lang).unwrap(), "world! Hello,");
+/// ```
+#[derive(Debug)]
+pub struct TranslatorReverseWords {
+    langs: Vec<&'static str>,
+}
+
+impl TranslatorReverseWords {
+    /// Create an instance of `TranslatorReverseWords` supporting [`DEFAULT_LANGS`].
+    pub fn new() -> TranslatorReverseWords {
+        TranslatorReverseWords {
+            langs: DEFAULT_LANGS.to_vec(),
+        }
+    }
+
+    /// Support `langs` instead of the default languages.
+    pub fn with_langs(mut self, langs: &[&'static str]) -> TranslatorReverseWords {
+        self.langs = langs.to_vec();
+        self
+    }
+}
+
+impl Default for TranslatorReverseWords {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Translator for TranslatorReverseWords {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        check_langs(&self.langs, lang)?;
+        Ok(text
+            .split('\n')
+            .map(|line| {
+                line.split_whitespace()
+                    .rev()
+                    .collect::<Vec<&str>>()
+                    .join(" ")
+            })
+            .collect::<Vec<String>>()
+            .join("\n"))
+    }
+
+    fn name(&self) -> &str {
+        "reverse_words"
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        &self.langs
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        self.langs.contains(&single_lang)
+    }
+}
+
+/// A translator turning every ASCII word into Pig Latin.
+///
+/// Words starting with a vowel get `way` appended. Otherwise the leading
+/// consonants are moved to the end, followed by `ay`. Other words are kept.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::testing::TranslatorPigLatin;
+/// use translator_mangler::{TranslationDirection, Translator};
+///
+/// let translator = TranslatorPigLatin::new();
+/// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
+///
+/// assert_eq!(translator.translate("Hello, apple!", &lang).unwrap(), "elloHay, appleway!");
+/// ```
+#[derive(Debug)]
+pub struct TranslatorPigLatin {
+    langs: Vec<&'static str>,
+}
+
+impl TranslatorPigLatin {
+    /// Create an instance of `TranslatorPigLatin` supporting [`DEFAULT_LANGS`].
+    pub fn new() -> TranslatorPigLatin {
+        TranslatorPigLatin {
+            langs: DEFAULT_LANGS.to_vec(),
+        }
+    }
+
+    /// Support `langs` instead of the default languages.
+    pub fn with_langs(mut self, langs: &[&'static str]) -> TranslatorPigLatin {
+        self.langs = langs.to_vec();
+        self
+    }
+
+    fn pig_latin(word: &str) -> String {
+        if !word.chars().all(|c| c.is_ascii_alphabetic()) {
+            return word.to_string();
+        }
+        let is_vowel = |c: char| "aeiouAEIOU".contains(c);
+        match word.find(is_vowel) {
+            Some(0) => format!("{}way", word),
+            Some(i) => format!("{}{}ay", &word[i..], &word[..i]),
+            None => format!("{}ay", word),
+        }
+    }
+}
+
+impl Default for TranslatorPigLatin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Translator for TranslatorPigLatin {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        check_langs(&self.langs, lang)?;
+        Ok(map_words(text, Self::pig_latin))
+    }
+
+    fn name(&self) -> &str {
+        "pig_latin"
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        &self.langs
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        self.langs.contains(&single_lang)
+    }
+}
+
+/// A translator shuffling the words on each line.
+///
+/// The order depends only on the seed, the text and the direction, so the
+/// same call always gives the same result, regardless of what was
+/// translated before.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::testing::TranslatorShuffle;
+/// use translator_mangler::{TranslationDirection, Translator};
+///
+/// let translator = TranslatorShuffle::new(42);
+/// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
+///
+/// let first = translator.translate("one two three four", &lang).unwrap();
+/// let second = translator.translate("one two three four", &lang).unwrap();
+/// assert_eq!(first, second);
+/// ```
+#[derive(Debug)]
+pub struct TranslatorShuffle {
+    seed: u64,
+    langs: Vec<&'static str>,
+}
+
+impl TranslatorShuffle {
+    /// Create an instance of `TranslatorShuffle` with the given seed,
+    /// supporting [`DEFAULT_LANGS`].
+    pub fn new(seed: u64) -> TranslatorShuffle {
+        TranslatorShuffle {
+            seed,
+            langs: DEFAULT_LANGS.to_vec(),
+        }
+    }
+
+    /// Support `langs` instead of the default languages.
+    pub fn with_langs(mut self, langs: &[&'static str]) -> TranslatorShuffle {
+        self.langs = langs.to_vec();
+        self
+    }
+
+    /// Mix `parts` into the seed with FNV-1a, which, unlike the standard
+    /// library's hasher, is stable across Rust releases.
+    fn seed_for(&self, parts: &[&str]) -> u64 {
+        let mut hash = 0xcbf29ce484222325 ^ self.seed;
+        for part in parts {
+            for byte in part.bytes().chain(std::iter::once(0xff)) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        hash
+    }
+}
+
+impl Translator for TranslatorShuffle {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        check_langs(&self.langs, lang)?;
+        let mut rng =
+            fastrand::Rng::with_seed(self.seed_for(&[&lang.from_lang, &lang.to_lang, text]));
+        Ok(text
+            .split('\n')
+            .map(|line| {
+                let mut words = line.split_whitespace().collect::<Vec<&str>>();
+                rng.shuffle(&mut words);
+                words.join(" ")
+            })
+            .collect::<Vec<String>>()
+            .join("\n"))
+    }
+
+    fn name(&self) -> &str {
+        "shuffle"
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        &self.langs
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        self.langs.contains(&single_lang)
+    }
+}
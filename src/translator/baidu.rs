@@ -1,6 +1,12 @@
-use super::{TranslationDirection, TranslationError, Translator};
-
-use md5::Digest;
+use super::layout;
+use super::options::{TranslateOption, TranslateOptions};
+use super::signing;
+use super::{
+    check_status, decode_response, BatchLimits, TranslationDirection, TranslationError,
+    TranslationErrorKind, Translator,
+};
+use crate::credentials::{CredentialError, Credentials, Secret};
+use crate::telemetry;
 
 #[derive(serde::Deserialize)]
 struct ResultBaiduNode {
@@ -23,9 +29,12 @@ struct ResultBaidu {
 /// A translator using Baidu Translate as its backend.
 ///
 /// Reference: [Baidu Translate Docs](https://api.fanyi.baidu.com/)
+#[derive(Debug)]
 pub struct TranslatorBaidu {
-    pub app_id: Box<String>,
-    pub api_key: Box<String>,
+    pub app_id: String,
+    api_key: Secret,
+    endpoint: String,
+    field_endpoint: String,
     client: reqwest::blocking::Client,
 }
 
@@ -41,87 +50,214 @@ impl TranslatorBaidu {
     ///
     /// # Example
     /// ```rust
+    /// use translator_mangler::TranslatorBaidu;
+    ///
     /// let translator = TranslatorBaidu::new("[YOUR_APP_ID]", "[YOUR_API_KEY]");
     /// ```
     pub fn new(app_id: &str, api_key: &str) -> TranslatorBaidu {
         TranslatorBaidu {
-            app_id: Box::new(app_id.to_string()),
-            api_key: Box::new(api_key.to_string()),
+            app_id: app_id.to_string(),
+            api_key: Secret::new(api_key),
+            endpoint: Self::ENDPOINT.to_string(),
+            field_endpoint: Self::FIELD_ENDPOINT.to_string(),
             client: reqwest::blocking::Client::new(),
         }
     }
 
-    /// A list of supported languages.
-    const SUPPORTED_LANGS: [&'static str; 28] = [
-        "zh", "en", "yue", "wyw", "jp", "kor", "fra", "spa", "th", "ara", "ru", "pt", "de", "it",
-        "el", "nl", "pl", "bul", "est", "dan", "fin", "cs", "rom", "slo", "swe", "hu", "cht",
-        "vie",
-    ];
-}
+    /// Create an instance of `TranslatorBaidu` with the given credentials.
+    ///
+    /// # Arguments
+    /// * `credentials` - Your App ID and API key.
+    ///
+    /// # Returns
+    /// A new instance of `TranslatorBaidu`, or an error if the App ID is missing.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use translator_mangler::credentials::{Backend, CredentialProvider, EnvProvider};
+    /// use translator_mangler::TranslatorBaidu;
+    ///
+    /// let credentials = EnvProvider.credentials(Backend::Baidu).unwrap().unwrap();
+    /// let translator = TranslatorBaidu::from_credentials(credentials).unwrap();
+    /// ```
+    pub fn from_credentials(credentials: Credentials) -> Result<TranslatorBaidu, CredentialError> {
+        let Some(app_id) = credentials.id.as_deref() else {
+            return Err(CredentialError {
+                message: "Baidu requires an App ID".to_string(),
+            });
+        };
+        Ok(TranslatorBaidu {
+            app_id: app_id.to_string(),
+            api_key: credentials.secret,
+            endpoint: Self::ENDPOINT.to_string(),
+            field_endpoint: Self::FIELD_ENDPOINT.to_string(),
+            client: reqwest::blocking::Client::new(),
+        })
+    }
 
-impl Translator for TranslatorBaidu {
-    /// Translate `text` from one language to another with Baidu Translation API.
+    /// Send requests to `endpoint` instead of the official Baidu endpoint.
     ///
-    /// Reference: [通用翻译API接入文档](https://api.fanyi.baidu.com/doc/21)
+    /// This is useful for proxies and for testing against a local server.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The full URL of the translation API.
     ///
     /// # Example
     /// ```rust
-    /// let translator = TranslatorBaidu::new("[YOUR_APP_ID]", "[YOUR_API_KEY]");
-    /// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
+    /// use translator_mangler::TranslatorBaidu;
     ///
-    /// println!("{}", translator.translate("Hello, world!", &lang).unwrap());
+    /// let translator = TranslatorBaidu::new("[YOUR_APP_ID]", "[YOUR_API_KEY]")
+    ///     .with_endpoint("http://127.0.0.1:8080/api/trans/vip/translate");
     /// ```
-    fn translate(
-        &self,
-        text: &str,
-        lang: &TranslationDirection,
-    ) -> Result<String, TranslationError> {
+    pub fn with_endpoint(mut self, endpoint: &str) -> TranslatorBaidu {
+        self.endpoint = endpoint.to_string();
+        self
+    }
+
+    /// Send domain translation requests to `endpoint` instead of the official
+    /// Baidu endpoint.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The full URL of the domain translation API.
+    ///
+    /// # Example
+    /// ```rust
+    /// use translator_mangler::TranslatorBaidu;
+    ///
+    /// let translator = TranslatorBaidu::new("[YOUR_APP_ID]", "[YOUR_API_KEY]")
+    ///     .with_field_endpoint("http://127.0.0.1:8080/api/trans/vip/fieldtranslate");
+    /// ```
+    pub fn with_field_endpoint(mut self, endpoint: &str) -> TranslatorBaidu {
+        self.field_endpoint = endpoint.to_string();
+        self
+    }
+
+    /// Classify an error code returned by the API.
+    ///
+    /// Reference: [错误码列表](https://api.fanyi.baidu.com/doc/21)
+    fn error_kind(error_code: &str) -> TranslationErrorKind {
+        match error_code {
+            // Request timeout, system error
+            "52001" | "52002" => TranslationErrorKind::Server,
+            // Access frequency limited, long query too frequent
+            "54003" | "54005" => TranslationErrorKind::RateLimited,
+            _ => TranslationErrorKind::Api,
+        }
+    }
+
+    /// The official endpoint of the translation API.
+    const ENDPOINT: &'static str = "https://fanyi-api.baidu.com/api/trans/vip/translate";
+
+    /// The official endpoint of the domain translation API.
+    const FIELD_ENDPOINT: &'static str = "https://fanyi-api.baidu.com/api/trans/vip/fieldtranslate";
+
+    /// The options honored by the translation API.
+    const SUPPORTED_OPTIONS: [TranslateOption; 1] = [TranslateOption::Domain];
+
+    /// A list of supported languages.
+    const SUPPORTED_LANGS: [&'static str; 28] = [
+        "zh", "en", "yue", "wyw", "jp", "kor", "fra", "spa", "th", "ara", "ru", "pt", "de", "it",
+        "el", "nl", "pl", "bul", "est", "dan", "fin", "cs", "rom", "slo", "swe", "hu", "cht",
+        "vie",
+    ];
+
+    /// The most a single request may carry. The API advises to keep
+    /// requests under 6000 bytes, about 2000 Chinese characters but 6000
+    /// English ones.
+    const BATCH_LIMITS: BatchLimits = BatchLimits {
+        max_texts: 100,
+        max_chars: usize::MAX,
+        max_bytes: 6000,
+    };
+
+    /// Check that both languages of `lang` are supported.
+    fn check_langs(&self, lang: &TranslationDirection) -> Result<(), TranslationError> {
         if !self.is_lang_supported(lang.from_lang.as_str())
             || !self.is_lang_supported(lang.to_lang.as_str())
         {
             return Err(TranslationError {
+                kind: TranslationErrorKind::UnsupportedLanguage,
                 message: "Unsupported language".to_string(),
             });
         }
+        Ok(())
+    }
+
+    /// Send a single translation request, without instrumentation.
+    fn send(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        self.check_langs(lang)?;
+
+        // Blank lines and indentation are dropped by the API, so only
+        // send the content and keep the layout ourselves.
+        layout::translate_text(text, |content| self.send_content(content, lang, options))
+    }
 
+    /// Send a request translating `content`, made of non-blank lines
+    /// without indentation, without instrumentation.
+    ///
+    /// # Returns
+    /// The translated segments, one for each line sent.
+    fn send_content(
+        &self,
+        content: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<Vec<String>, TranslationError> {
         // Create salt for randomness
-        let salt = std::iter::repeat_with(|| fastrand::char(..))
-            .take(4)
-            .collect::<String>();
-        // Calculate query signature
-        let signature = hex::encode(
-            md5::Md5::new()
-                .chain_update(format!("{}{}{}{}", self.app_id, text, salt, self.api_key).as_bytes())
-                .finalize(),
-        );
+        let salt = signing::salt(8);
+        // Calculate query signature, and pick the API to use
+        let (signature, endpoint) = match &options.domain {
+            Some(domain) => (
+                signing::baidu_field_sign(
+                    &self.app_id,
+                    content,
+                    &salt,
+                    domain,
+                    self.api_key.expose(),
+                ),
+                &self.field_endpoint,
+            ),
+            None => (
+                signing::baidu_sign(&self.app_id, content, &salt, self.api_key.expose()),
+                &self.endpoint,
+            ),
+        };
         // Generate request body
-        let req_obj = [
-            ("q", text),
+        let mut req_obj = vec![
+            ("q", content),
             ("from", &lang.from_lang),
             ("to", &lang.to_lang),
             ("appid", &self.app_id),
             ("salt", &salt),
             ("sign", &signature),
         ];
+        if let Some(domain) = &options.domain {
+            req_obj.push(("domain", domain));
+        }
 
         // Send request
-        let result = self
-            .client
-            .post("https://fanyi-api.baidu.com/api/trans/vip/translate")
-            .json(&req_obj)
-            .send();
+        let result = self.client.post(endpoint).json(&req_obj).send();
 
         // Handle network error
         if let Err(e) = result {
             return Err(TranslationError {
+                kind: TranslationErrorKind::Network,
                 message: format!("NETWORK ERR: {}", e),
             });
         }
-        let result_json = result.unwrap().json::<ResultBaidu>().unwrap();
-        // Handle API error
+        let response = result.unwrap();
+        check_status(&response)?;
+        let result_json = decode_response::<ResultBaidu>(response)?;
+        // Handle API error, 52000 meaning success
         let error_code = &result_json.error_code;
-        if !error_code.is_empty() && error_code.parse::<i32>().unwrap() != 0 {
+        if !error_code.is_empty() && error_code != "0" && error_code != "52000" {
             return Err(TranslationError {
+                kind: Self::error_kind(error_code),
                 message: format!(
                     "API ERR: {} {}",
                     result_json.error_code, result_json.error_msg
@@ -129,7 +265,107 @@ impl Translator for TranslatorBaidu {
             });
         }
 
-        Ok(result_json.trans_result[0].dst.clone())
+        if result_json.trans_result.is_empty() {
+            return Err(TranslationError {
+                kind: TranslationErrorKind::Api,
+                message: "API ERR: empty translation result".to_string(),
+            });
+        }
+
+        // One node is returned for each line sent.
+        Ok(result_json
+            .trans_result
+            .into_iter()
+            .map(|node| node.dst)
+            .collect())
+    }
+}
+
+impl Translator for TranslatorBaidu {
+    /// Translate `text` from one language to another with Baidu Translation API.
+    ///
+    /// Reference: [通用翻译API接入文档](https://api.fanyi.baidu.com/doc/21)
+    ///
+    /// Multi-line text is translated line by line. Blank lines and
+    /// indentation are kept as they are in `text`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use translator_mangler::{TranslationDirection, Translator, TranslatorBaidu};
+    ///
+    /// let translator = TranslatorBaidu::new("[YOUR_APP_ID]", "[YOUR_API_KEY]");
+    /// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
+    ///
+    /// println!("{}", translator.translate("Hello, world!", &lang).unwrap());
+    /// ```
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        self.translate_with(text, lang, &TranslateOptions::new())
+    }
+
+    /// Translate `text` like [`translate`](Translator::translate), with the
+    /// domain chosen in `options`.
+    ///
+    /// Reference: [垂直领域翻译API接入文档](https://api.fanyi.baidu.com/doc/22)
+    ///
+    /// With a domain such as `medicine`, `electronics` or `finance`, the
+    /// domain translation API is used instead of the general one.
+    fn translate_with(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        telemetry::instrument_request(self.name(), text, lang, options.context.attempt(), || {
+            self.send(text, lang, options)
+        })
+    }
+
+    /// Translate `texts` like [`translate_with`](Translator::translate_with),
+    /// sending the lines of many texts in a single request.
+    fn translate_many(
+        &self,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        if let Err(e) = self.check_langs(lang) {
+            return texts.iter().map(|_| Err(e.clone())).collect();
+        }
+        Self::BATCH_LIMITS
+            .chunks(texts)
+            .into_iter()
+            .flat_map(|chunk| {
+                layout::translate_lines(
+                    &texts[chunk],
+                    |content| {
+                        telemetry::instrument_request(
+                            self.name(),
+                            content,
+                            lang,
+                            options.context.attempt(),
+                            || self.send_content(content, lang, options),
+                        )
+                    },
+                    |text| self.translate_with(text, lang, options),
+                )
+            })
+            .collect()
+    }
+
+    fn batch_limits(&self) -> BatchLimits {
+        Self::BATCH_LIMITS
+    }
+
+    fn supported_options(&self) -> &[TranslateOption] {
+        &Self::SUPPORTED_OPTIONS
+    }
+
+    fn name(&self) -> &str {
+        "baidu"
     }
 
     fn get_supported_langs(&self) -> &[&'static str] {
@@ -0,0 +1,383 @@
+//! Recording translation calls to a cassette file and replaying them offline.
+//!
+//! A cassette is a JSON Lines file with one [`CassetteEntry`] per call, in
+//! the order the calls were made.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::options::{TranslateOption, TranslateOptions};
+use super::{
+    BatchLimits, TranslationDirection, TranslationError, TranslationErrorKind, Translator,
+};
+
+/// A single recorded translation call.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct CassetteEntry {
+    /// The direction of the translation.
+    pub direction: TranslationDirection,
+    /// The text to translate.
+    pub input: String,
+    /// The options the text was translated with.
+    #[serde(default, skip_serializing_if = "TranslateOptions::is_default")]
+    pub options: TranslateOptions,
+    /// The translated text, if the call succeeded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    /// The error message, if the call failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The kind of the error, if the call failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<TranslationErrorKind>,
+}
+
+/// A translator recording every call to another translator in a cassette.
+///
+/// Each call is written and flushed as soon as it completes, so a cassette
+/// is usable even if the process dies halfway. A call that cannot be
+/// written is logged and its result passed on all the same, since the
+/// translation was made and billed already.
+///
+/// # Example
+/// ```rust,no_run
+/// use translator_mangler::cassette::RecordingTranslator;
+/// use translator_mangler::{get_random_lang_path, mangle, TranslatorBaidu};
+///
+/// let translator = TranslatorBaidu::new("[YOUR_APP_ID]", "[YOUR_API_KEY]");
+/// let recorder = RecordingTranslator::create(translator, "session.jsonl").unwrap();
+/// let path = get_random_lang_path("en", &["zh", "jp"], 10);
+/// let result = mangle(&recorder, "Ignorance is strength.", &path, 1000);
+/// ```
+pub struct RecordingTranslator<T: Translator> {
+    inner: T,
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl<T: Translator> RecordingTranslator<T> {
+    /// Create an instance of `RecordingTranslator` wrapping `inner`, and
+    /// recording to a new cassette at `path`.
+    ///
+    /// # Arguments
+    /// * `inner` - The translator to record.
+    /// * `path` - The cassette file. It is truncated if it exists.
+    pub fn create(inner: T, path: impl AsRef<Path>) -> std::io::Result<RecordingTranslator<T>> {
+        Ok(RecordingTranslator {
+            inner,
+            writer: Mutex::new(BufWriter::new(File::create(path)?)),
+        })
+    }
+
+    /// Get the wrapped translator back.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Record a call translating `text` with its `result`, and pass the
+    /// result on even if it could not be recorded.
+    fn record_call(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+        result: Result<String, TranslationError>,
+    ) -> Result<String, TranslationError> {
+        let (output, error, error_kind) = match &result {
+            Ok(output) => (Some(output.clone()), None, None),
+            Err(e) => (None, Some(e.message.clone()), Some(e.kind)),
+        };
+        let entry = CassetteEntry {
+            direction: lang.clone(),
+            input: text.to_string(),
+            options: options.without_context(),
+            output,
+            error,
+            error_kind,
+        };
+        if let Err(e) = self.record(&entry) {
+            tracing::warn!(error = %e, "call not recorded to the cassette");
+        }
+
+        result
+    }
+
+    fn record(&self, entry: &CassetteEntry) -> std::io::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        serde_json::to_writer(&mut *writer, entry)?;
+        writer.write_all(b"\n")?;
+        writer.flush()
+    }
+}
+
+impl<T: Translator> Translator for RecordingTranslator<T> {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        self.translate_with(text, lang, &TranslateOptions::new())
+    }
+
+    fn translate_with(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        let result = self.inner.translate_with(text, lang, options);
+        self.record_call(text, lang, options, result)
+    }
+
+    /// Translate `texts` with the wrapped translator, recording one entry
+    /// per text so that the cassette replays the same with or without
+    /// batches.
+    fn translate_many(
+        &self,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        let results = self.inner.translate_many(texts, lang, options);
+        texts
+            .iter()
+            .zip(results)
+            .map(|(text, result)| self.record_call(text, lang, options, result))
+            .collect()
+    }
+
+    fn batch_limits(&self) -> BatchLimits {
+        self.inner.batch_limits()
+    }
+
+    fn supported_options(&self) -> &[TranslateOption] {
+        self.inner.supported_options()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        self.inner.get_supported_langs()
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        self.inner.is_lang_supported(single_lang)
+    }
+
+    fn is_pair_supported(&self, from_lang: &str, to_lang: &str) -> bool {
+        self.inner.is_pair_supported(from_lang, to_lang)
+    }
+
+    fn pivot_lang(&self) -> Option<&str> {
+        self.inner.pivot_lang()
+    }
+}
+
+/// How a [`ReplayTranslator`] matches calls to recorded entries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayMode {
+    /// Calls must come in exactly the recorded order, with the recorded
+    /// direction, input and options.
+    Strict,
+    /// Calls may come in any order. Each call is answered by the first
+    /// unused entry with the same direction, input and options, or by a used one if
+    /// all of them have been used.
+    Lenient,
+}
+
+/// A translator answering calls from a cassette, without any network.
+///
+/// Recorded errors are replayed as errors.
+///
+/// # Example
+/// ```rust,no_run
+/// use translator_mangler::cassette::{ReplayMode, ReplayTranslator};
+/// use translator_mangler::{get_random_lang_path, mangle};
+///
+/// let replay = ReplayTranslator::open("session.jsonl", ReplayMode::Lenient).unwrap();
+/// let path = get_random_lang_path("en", &["zh", "jp"], 10);
+/// let result = mangle(&replay, "Ignorance is strength.", &path, 0);
+/// ```
+pub struct ReplayTranslator {
+    entries: Vec<CassetteEntry>,
+    used: Mutex<Vec<bool>>,
+    mode: ReplayMode,
+    langs: Vec<&'static str>,
+}
+
+impl ReplayTranslator {
+    /// Create an instance of `ReplayTranslator` from the cassette at `path`.
+    ///
+    /// # Arguments
+    /// * `path` - The cassette file.
+    /// * `mode` - How calls are matched to entries.
+    pub fn open(path: impl AsRef<Path>, mode: ReplayMode) -> std::io::Result<ReplayTranslator> {
+        let mut entries = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            entries.push(serde_json::from_str(&line)?);
+        }
+        Ok(Self::from_entries(entries, mode))
+    }
+
+    /// Create an instance of `ReplayTranslator` from recorded `entries`.
+    ///
+    /// The supported languages are all languages appearing in `entries`.
+    pub fn from_entries(entries: Vec<CassetteEntry>, mode: ReplayMode) -> ReplayTranslator {
+        let mut seen = HashSet::new();
+        let mut langs = Vec::new();
+        for entry in &entries {
+            for lang in [&entry.direction.from_lang, &entry.direction.to_lang] {
+                if seen.insert(lang.clone()) {
+                    langs.push(intern_lang(lang));
+                }
+            }
+        }
+
+        ReplayTranslator {
+            used: Mutex::new(vec![false; entries.len()]),
+            entries,
+            mode,
+            langs,
+        }
+    }
+
+    /// Support `langs` instead of the languages found in the cassette.
+    pub fn with_langs(mut self, langs: &[&'static str]) -> ReplayTranslator {
+        self.langs = langs.to_vec();
+        self
+    }
+
+    /// Get the number of entries not yet replayed.
+    pub fn remaining(&self) -> usize {
+        self.used
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|used| !**used)
+            .count()
+    }
+
+    fn find_entry(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<usize, String> {
+        let mut used = self.used.lock().unwrap();
+        let matches = |entry: &CassetteEntry| {
+            entry.direction == *lang && entry.input == text && entry.options == *options
+        };
+        let index = match self.mode {
+            ReplayMode::Strict => {
+                let Some(next) = used.iter().position(|used| !*used) else {
+                    return Err("cassette exhausted".to_string());
+                };
+                if !matches(&self.entries[next]) {
+                    let expected = &self.entries[next].direction;
+                    return Err(format!(
+                        "call {} expected {}->{} with the recorded input and options, got {}->{}",
+                        next, expected.from_lang, expected.to_lang, lang.from_lang, lang.to_lang
+                    ));
+                }
+                next
+            }
+            ReplayMode::Lenient => {
+                let mut candidates = (0..self.entries.len()).filter(|i| matches(&self.entries[*i]));
+                let first = candidates.clone().next();
+                match candidates.find(|i| !used[*i]).or(first) {
+                    Some(i) => i,
+                    None => {
+                        return Err(format!(
+                            "no recorded call for {}->{} with this input and options",
+                            lang.from_lang, lang.to_lang
+                        ));
+                    }
+                }
+            }
+        };
+        used[index] = true;
+        Ok(index)
+    }
+}
+
+impl Translator for ReplayTranslator {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        self.translate_with(text, lang, &TranslateOptions::new())
+    }
+
+    /// Answer a call from the cassette. Every option is honored, as entries
+    /// only match calls with the same options.
+    fn translate_with(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        let index = self
+            .find_entry(text, lang, options)
+            .map_err(|e| TranslationError {
+                kind: TranslationErrorKind::Other,
+                message: format!("REPLAY ERR: {}", e),
+            })?;
+        let entry = &self.entries[index];
+        match (&entry.output, &entry.error) {
+            (Some(output), _) => Ok(output.clone()),
+            (None, Some(error)) => Err(TranslationError {
+                kind: entry.error_kind.unwrap_or(TranslationErrorKind::Other),
+                message: error.clone(),
+            }),
+            (None, None) => Err(TranslationError {
+                kind: TranslationErrorKind::Other,
+                message: format!("REPLAY ERR: entry {} has no output", index),
+            }),
+        }
+    }
+
+    fn supported_options(&self) -> &[TranslateOption] {
+        &TranslateOption::ALL
+    }
+
+    fn name(&self) -> &str {
+        "replay"
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        &self.langs
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        self.langs.contains(&single_lang)
+    }
+}
+
+/// The language codes found in every cassette loaded so far.
+static INTERNED_LANGS: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+/// Get a `'static` copy of the language code `lang`, as supported languages
+/// are in [`Translator`].
+///
+/// Each code is only ever allocated once for the whole process, so loading
+/// cassettes again and again does not leak memory.
+fn intern_lang(lang: &str) -> &'static str {
+    let mut interned = INTERNED_LANGS.lock().unwrap();
+    match interned.get(lang) {
+        Some(lang) => lang,
+        None => {
+            let lang: &'static str = Box::leak(lang.into());
+            interned.insert(lang);
+            lang
+        }
+    }
+}
@@ -1,4 +1,10 @@
-use super::{TranslationDirection, TranslationError, Translator};
+use super::options::{TranslateOption, TranslateOptions};
+use super::{
+    check_status, decode_response, BatchLimits, TranslationDirection, TranslationError,
+    TranslationErrorKind, Translator,
+};
+use crate::credentials::{Credentials, Secret};
+use crate::telemetry;
 
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -21,8 +27,9 @@ struct ResultGoogleCloud {
 /// A translator using Google Cloud API as its backend.
 ///
 /// See: [Google Cloud](https://cloud.google.com/translate/docs/reference/rest/v2/Translate)
+#[derive(Debug)]
 pub struct TranslatorGoogleCloud {
-    pub api_key: Box<String>,
+    api_key: Secret,
     client: reqwest::blocking::Client,
 }
 
@@ -37,11 +44,36 @@ impl TranslatorGoogleCloud {
     ///
     /// # Example
     /// ```rust
+    /// use translator_mangler::TranslatorGoogleCloud;
+    ///
     /// let translator = TranslatorGoogleCloud::new("[YOUR_API_KEY]");
     /// ```
     pub fn new(api_key: &str) -> TranslatorGoogleCloud {
         TranslatorGoogleCloud {
-            api_key: Box::new(api_key.to_string()),
+            api_key: Secret::new(api_key),
+            client: reqwest::blocking::Client::new(),
+        }
+    }
+
+    /// Create an instance of `TranslatorGoogleCloud` with the given credentials.
+    ///
+    /// # Arguments
+    /// * `credentials` - Your API key. The identifier is ignored.
+    ///
+    /// # Returns
+    /// A new instance of `TranslatorGoogleCloud`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use translator_mangler::credentials::{Backend, CredentialProvider, EnvProvider};
+    /// use translator_mangler::TranslatorGoogleCloud;
+    ///
+    /// let credentials = EnvProvider.credentials(Backend::GoogleCloud).unwrap().unwrap();
+    /// let translator = TranslatorGoogleCloud::from_credentials(credentials);
+    /// ```
+    pub fn from_credentials(credentials: Credentials) -> TranslatorGoogleCloud {
+        TranslatorGoogleCloud {
+            api_key: credentials.secret,
             client: reqwest::blocking::Client::new(),
         }
     }
@@ -56,34 +88,39 @@ impl TranslatorGoogleCloud {
         "sw", "sv", "tl", "tg", "ta", "tt", "te", "th", "tr", "tk", "uk", "ur", "ug", "uz", "vi",
         "cy", "xh", "yi", "yo", "zu",
     ];
-}
 
-impl Translator for TranslatorGoogleCloud {
-    /// Translate `text` from one language to another with Google Cloud Translation API.
-    ///
-    /// Reference: [Google Cloud Docs](https://cloud.google.com/translate/docs/reference/rest/v2/translate)
-    ///
-    /// # Example
-    /// ```rust
-    /// let translator = TranslatorGoogleCloud::new("[YOUR_API_KEY]");
-    /// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
+    /// The options honored by the translation API.
+    const SUPPORTED_OPTIONS: [TranslateOption; 2] =
+        [TranslateOption::Format, TranslateOption::Model];
+
+    /// The most a single request may carry: 128 texts, and about 5000
+    /// characters as advised by the API.
+    const BATCH_LIMITS: BatchLimits = BatchLimits {
+        max_texts: 128,
+        max_chars: 5000,
+        max_bytes: usize::MAX,
+    };
+
+    /// Send a single translation request for all `texts`, without
+    /// instrumentation.
     ///
-    /// println!("{}", translator.translate("Hello, world!", &lang).unwrap());
-    /// ```
-    fn translate(
+    /// # Returns
+    /// The translation of every text, in order.
+    fn send(
         &self,
-        text: &str,
+        texts: &[&str],
         lang: &TranslationDirection,
-    ) -> Result<String, TranslationError> {
-        // Generate request body
-        let req_obj = [
-            ("q", text),
-            ("source", &lang.from_lang),
-            ("target", &lang.to_lang),
-            ("key", &self.api_key),
-            ("format", "text"),
-            ("model", "base"),
-        ];
+        options: &TranslateOptions,
+    ) -> Result<Vec<String>, TranslationError> {
+        // Generate request body, with one `q` per text
+        let mut req_obj = texts.iter().map(|text| ("q", *text)).collect::<Vec<_>>();
+        req_obj.extend([
+            ("source", lang.from_lang.as_str()),
+            ("target", lang.to_lang.as_str()),
+            ("key", self.api_key.expose()),
+            ("format", options.format.map_or("text", |f| f.as_str())),
+            ("model", options.model.as_deref().unwrap_or("base")),
+        ]);
 
         // Send request
         let result = self
@@ -94,21 +131,111 @@ impl Translator for TranslatorGoogleCloud {
         // Handle network error
         if let Err(e) = result {
             return Err(TranslationError {
+                kind: TranslationErrorKind::Network,
                 message: format!("NETWORK ERR: {}", e),
             });
         }
         let unwrapped_result = result.unwrap();
+        check_status(&unwrapped_result)?;
 
-        let status = unwrapped_result.status();
-        if !status.is_success() {
+        let result_json = decode_response::<ResultGoogleCloud>(unwrapped_result)?;
+        // Handle API error
+        if result_json.data.translations.len() != texts.len() {
             return Err(TranslationError {
-                message: format!("REQUEST ERR: HTTP {}", status),
+                kind: TranslationErrorKind::Api,
+                message: format!(
+                    "API ERR: {} translations for {} texts",
+                    result_json.data.translations.len(),
+                    texts.len()
+                ),
             });
         }
+        // One translation is returned for each text sent.
+        Ok(result_json
+            .data
+            .translations
+            .into_iter()
+            .map(|translation| translation.translated_text)
+            .collect())
+    }
+}
 
-        let result_json = unwrapped_result.json::<ResultGoogleCloud>().unwrap();
-        // Handle API error
-        Ok(result_json.data.translations[0].translated_text.clone())
+impl Translator for TranslatorGoogleCloud {
+    /// Translate `text` from one language to another with Google Cloud Translation API.
+    ///
+    /// Reference: [Google Cloud Docs](https://cloud.google.com/translate/docs/reference/rest/v2/translate)
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use translator_mangler::{TranslationDirection, Translator, TranslatorGoogleCloud};
+    ///
+    /// let translator = TranslatorGoogleCloud::new("[YOUR_API_KEY]");
+    /// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
+    ///
+    /// println!("{}", translator.translate("Hello, world!", &lang).unwrap());
+    /// ```
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        self.translate_with(text, lang, &TranslateOptions::new())
+    }
+
+    /// Translate `text` like [`translate`](Translator::translate), with the
+    /// format and model chosen in `options`.
+    ///
+    /// The format defaults to `text` and the model to `base`.
+    fn translate_with(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        telemetry::instrument_request(self.name(), text, lang, options.context.attempt(), || {
+            self.send(&[text], lang, options)
+                .map(|mut translations| translations.remove(0))
+        })
+    }
+
+    /// Translate `texts` like [`translate_with`](Translator::translate_with),
+    /// sending many texts in a single request.
+    fn translate_many(
+        &self,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        Self::BATCH_LIMITS
+            .chunks(texts)
+            .into_iter()
+            .flat_map(|chunk| {
+                let chunk = &texts[chunk];
+                let content = chunk.join("\n");
+                match telemetry::instrument_request(
+                    self.name(),
+                    &content,
+                    lang,
+                    options.context.attempt(),
+                    || self.send(chunk, lang, options),
+                ) {
+                    Ok(translations) => translations.into_iter().map(Ok).collect::<Vec<_>>(),
+                    Err(e) => chunk.iter().map(|_| Err(e.clone())).collect(),
+                }
+            })
+            .collect()
+    }
+
+    fn batch_limits(&self) -> BatchLimits {
+        Self::BATCH_LIMITS
+    }
+
+    fn supported_options(&self) -> &[TranslateOption] {
+        &Self::SUPPORTED_OPTIONS
+    }
+
+    fn name(&self) -> &str {
+        "google_cloud"
     }
 
     fn get_supported_langs(&self) -> &[&'static str] {
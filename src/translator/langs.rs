@@ -0,0 +1,48 @@
+/// Codes the backends use for the same language, the common code first.
+const EQUIVALENT_CODES: &[&[&str]] = &[
+    &["zh", "zh-CN", "zh-CHS"],
+    &["zh-TW", "cht", "zh-CHT"],
+    &["ja", "jp"],
+    &["ko", "kor"],
+    &["fr", "fra"],
+    &["es", "spa"],
+    &["ar", "ara"],
+    &["vi", "vie"],
+    &["bg", "bul"],
+    &["et", "est"],
+    &["da", "dan"],
+    &["fi", "fin"],
+    &["ro", "rom"],
+    &["sl", "slo"],
+    &["sv", "swe"],
+    &["he", "iw"],
+];
+
+/// Get the codes of the same language as `lang` used by any backend,
+/// including `lang` itself, or nothing for an unknown language.
+pub(crate) fn equivalent_langs(lang: &str) -> &'static [&'static str] {
+    EQUIVALENT_CODES
+        .iter()
+        .find(|codes| codes.contains(&lang))
+        .copied()
+        .unwrap_or(&[])
+}
+
+/// Get the common code of `lang`, which may be the code of a specific
+/// backend, or `lang` itself if it has no other code.
+///
+/// Paths written with common codes can be translated by any backend, each
+/// hop being sent with the codes of its backend as given by
+/// [`Translator::native_lang`](crate::Translator::native_lang).
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::common_lang;
+///
+/// assert_eq!(common_lang("jp"), "ja");
+/// assert_eq!(common_lang("zh-CHS"), "zh");
+/// assert_eq!(common_lang("en"), "en");
+/// ```
+pub fn common_lang(lang: &str) -> &str {
+    equivalent_langs(lang).first().copied().unwrap_or(lang)
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use super::TranslatorMiddleware;
+use crate::translator::options::TranslateOptions;
+use crate::translator::{TranslationDirection, TranslationError, Translator};
+
+/// Cached translations, keyed by source language, target language, text and
+/// options.
+type CacheEntries = HashMap<(String, String, String, TranslateOptions), String>;
+
+/// A layer remembering successful translations, so that translating the
+/// same text in the same direction with the same options again costs no
+/// request.
+///
+/// Clones of a `CacheLayer` share the same cache. Errors are never cached.
+#[derive(Clone, Default)]
+pub struct CacheLayer {
+    entries: Arc<Mutex<CacheEntries>>,
+}
+
+impl CacheLayer {
+    /// Create a layer with an empty cache.
+    pub fn new() -> CacheLayer {
+        CacheLayer {
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Get the number of cached translations.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Check if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl TranslatorMiddleware for CacheLayer {
+    fn translate(
+        &self,
+        inner: &dyn Translator,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        let key = cache_key(text, lang, options);
+        if let Some(cached) = self.entries.lock().unwrap().get(&key) {
+            return Ok(cached.clone());
+        }
+
+        let result = inner.translate_with(text, lang, options)?;
+        self.entries.lock().unwrap().insert(key, result.clone());
+        Ok(result)
+    }
+
+    fn translate_many(
+        &self,
+        inner: &dyn Translator,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        let mut results = {
+            let entries = self.entries.lock().unwrap();
+            texts
+                .iter()
+                .map(|text| {
+                    entries
+                        .get(&cache_key(text, lang, options))
+                        .cloned()
+                        .map(Ok)
+                })
+                .collect::<Vec<Option<Result<String, TranslationError>>>>()
+        };
+
+        // Only the texts missing from the cache are sent, in one batch.
+        let misses = (0..texts.len())
+            .filter(|&index| results[index].is_none())
+            .collect::<Vec<usize>>();
+        if !misses.is_empty() {
+            let miss_texts = misses
+                .iter()
+                .map(|&index| texts[index])
+                .collect::<Vec<&str>>();
+            let translated = inner.translate_many(&miss_texts, lang, options);
+            let mut entries = self.entries.lock().unwrap();
+            for (index, result) in misses.into_iter().zip(translated) {
+                if let Ok(output) = &result {
+                    entries.insert(cache_key(texts[index], lang, options), output.clone());
+                }
+                results[index] = Some(result);
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.expect("every text is translated"))
+            .collect()
+    }
+}
+
+/// Get the key of the translation of `text` in the cache.
+fn cache_key(
+    text: &str,
+    lang: &TranslationDirection,
+    options: &TranslateOptions,
+) -> (String, String, String, TranslateOptions) {
+    (
+        lang.from_lang.clone(),
+        lang.to_lang.clone(),
+        text.to_string(),
+        options.without_context(),
+    )
+}
@@ -0,0 +1,94 @@
+use std::time::Instant;
+
+use super::TranslatorMiddleware;
+use crate::translator::options::TranslateOptions;
+use crate::translator::{TranslationDirection, TranslationError, Translator};
+
+/// A layer logging every call with `tracing`: the wrapped translator, the
+/// direction, the number and length of the texts, the time taken and the
+/// failures.
+///
+/// Like the `translate` spans of the backends, the log never carries the
+/// texts themselves. Unlike them, it sees calls answered by inner layers,
+/// such as a cache, and covers translators without instrumentation.
+#[derive(Clone, Debug, Default)]
+pub struct LogLayer;
+
+impl LogLayer {
+    /// Create a logging layer.
+    pub fn new() -> LogLayer {
+        LogLayer
+    }
+}
+
+impl TranslatorMiddleware for LogLayer {
+    fn translate(
+        &self,
+        inner: &dyn Translator,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        let start = Instant::now();
+        let result = inner.translate_with(text, lang, options);
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+        match &result {
+            Ok(_) => tracing::info!(
+                translator = inner.name(),
+                from = %lang.from_lang,
+                to = %lang.to_lang,
+                text_len = text.chars().count(),
+                elapsed_ms,
+                "translation done"
+            ),
+            Err(e) => tracing::warn!(
+                translator = inner.name(),
+                from = %lang.from_lang,
+                to = %lang.to_lang,
+                text_len = text.chars().count(),
+                elapsed_ms,
+                error_kind = e.kind.as_str(),
+                error = %e.message,
+                "translation failed"
+            ),
+        }
+        result
+    }
+
+    fn translate_many(
+        &self,
+        inner: &dyn Translator,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        let start = Instant::now();
+        let results = inner.translate_many(texts, lang, options);
+        let elapsed_ms = start.elapsed().as_millis() as u64;
+        let failures = results.iter().filter(|result| result.is_err()).count();
+        let text_len = texts.iter().map(|text| text.chars().count()).sum::<usize>();
+        if failures == 0 {
+            tracing::info!(
+                translator = inner.name(),
+                from = %lang.from_lang,
+                to = %lang.to_lang,
+                texts = texts.len(),
+                text_len,
+                elapsed_ms,
+                "batch done"
+            );
+        } else {
+            tracing::warn!(
+                translator = inner.name(),
+                from = %lang.from_lang,
+                to = %lang.to_lang,
+                texts = texts.len(),
+                text_len,
+                elapsed_ms,
+                failures,
+                "batch done with failures"
+            );
+        }
+        results
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::{Arc, Mutex};
+
+use super::TranslatorMiddleware;
+use crate::translator::options::TranslateOptions;
+use crate::translator::{TranslationDirection, TranslationError, Translator};
+
+/// The usage counted by a [`MeterLayer`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Usage {
+    /// The number of calls, a batch counting as one.
+    pub calls: u64,
+    /// The number of texts sent.
+    pub texts: u64,
+    /// The number of characters in the texts sent, which most backends
+    /// bill for.
+    pub chars: u64,
+    /// The number of texts that failed to translate.
+    pub failures: u64,
+}
+
+/// A layer counting the calls, texts and characters going through it, to
+/// keep an eye on quotas and costs.
+///
+/// Clones of a `MeterLayer` share the same counts, so one meter can add up
+/// the usage of every translator using the same backend account. Placed
+/// inside a [`CacheLayer`](super::CacheLayer), it only counts what reaches
+/// the backend.
+#[derive(Clone, Debug, Default)]
+pub struct MeterLayer {
+    usage: Arc<Mutex<Usage>>,
+}
+
+impl MeterLayer {
+    /// Create a layer with all counts at zero.
+    pub fn new() -> MeterLayer {
+        MeterLayer {
+            usage: Arc::new(Mutex::new(Usage::default())),
+        }
+    }
+
+    /// Get the usage counted so far.
+    pub fn usage(&self) -> Usage {
+        *self.usage.lock().unwrap()
+    }
+
+    /// Get the usage counted so far and set all counts back to zero.
+    pub fn take(&self) -> Usage {
+        std::mem::take(&mut *self.usage.lock().unwrap())
+    }
+
+    /// Count a call translating `texts`, of which `failures` failed.
+    fn record(&self, texts: &[&str], failures: usize) {
+        let mut usage = self.usage.lock().unwrap();
+        usage.calls += 1;
+        usage.texts += texts.len() as u64;
+        usage.chars += texts
+            .iter()
+            .map(|text| text.chars().count() as u64)
+            .sum::<u64>();
+        usage.failures += failures as u64;
+    }
+}
+
+impl TranslatorMiddleware for MeterLayer {
+    fn translate(
+        &self,
+        inner: &dyn Translator,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        let result = inner.translate_with(text, lang, options);
+        self.record(&[text], usize::from(result.is_err()));
+        result
+    }
+
+    fn translate_many(
+        &self,
+        inner: &dyn Translator,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        let results = inner.translate_many(texts, lang, options);
+        self.record(
+            texts,
+            results.iter().filter(|result| result.is_err()).count(),
+        );
+        results
+    }
+}
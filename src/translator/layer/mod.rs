@@ -0,0 +1,215 @@
+//! Composable middleware wrapping a [`Translator`].
+//!
+//! A [`TranslatorMiddleware`] only decides what happens to each call. It is
+//! turned into a full `Translator` by [`Layered`], which takes care of
+//! delegating everything else to the wrapped translator. Middleware is
+//! stacked around a backend with a [`TranslatorBuilder`].
+//!
+//! # Example
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use translator_mangler::layer::{
+//!     CacheLayer, LogLayer, MeterLayer, RateLimitLayer, RetryLayer, TranslatorBuilder,
+//! };
+//! use translator_mangler::testing::TranslatorReverseWords;
+//! use translator_mangler::{TranslationDirection, Translator};
+//!
+//! let meter = MeterLayer::new();
+//! let translator = TranslatorBuilder::new()
+//!     .layer(LogLayer::new())
+//!     .layer(CacheLayer::new())
+//!     .layer(meter.clone())
+//!     .layer(RetryLayer::new(3))
+//!     .layer(RateLimitLayer::new(Duration::from_millis(10)))
+//!     .build(TranslatorReverseWords::new());
+//! let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
+//!
+//! assert_eq!(translator.translate("Hello, world!", &lang).unwrap(), "world! Hello,");
+//! assert_eq!(translator.translate("Hello, world!", &lang).unwrap(), "world! Hello,");
+//! // The second call was answered by the cache.
+//! assert_eq!(meter.usage().calls, 1);
+//! ```
+
+mod cache;
+mod log;
+mod meter;
+mod rate_limit;
+mod retry;
+
+pub use cache::CacheLayer;
+pub use log::LogLayer;
+pub use meter::{MeterLayer, Usage};
+pub use rate_limit::{RateLimitLayer, RateLimiter};
+pub use retry::RetryLayer;
+
+use super::options::{TranslateOption, TranslateOptions};
+use super::{BatchLimits, TranslationDirection, TranslationError, Translator};
+
+/// Something that turns a translator into another, wrapped translator.
+pub trait TranslatorLayer {
+    /// Wrap `inner` in this layer.
+    fn layer(&self, inner: Box<dyn Translator>) -> Box<dyn Translator>;
+}
+
+/// Middleware deciding what happens to each translation call.
+///
+/// Any `Clone` middleware is also a [`TranslatorLayer`]. Middleware that
+/// should share state between the translators it wraps, such as a cache,
+/// keeps that state behind an `Arc`, and behind a `Mutex` or atomics since
+/// translators are shared between threads.
+pub trait TranslatorMiddleware: Send + Sync {
+    /// Handle a call to translate `text`, usually by calling `inner` at
+    /// some point.
+    ///
+    /// # Arguments
+    /// * `inner` - The wrapped translator.
+    /// * `text` - The text to translate.
+    /// * `lang` - The direction to translate `text` in.
+    /// * `options` - The options to translate `text` with.
+    fn translate(
+        &self,
+        inner: &dyn Translator,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError>;
+
+    /// Handle a call to translate many texts at once, usually by calling
+    /// `inner` with some of them.
+    ///
+    /// By default, each text is handled on its own with
+    /// [`translate`](TranslatorMiddleware::translate), so middleware that
+    /// does not know about batches still sees every text.
+    fn translate_many(
+        &self,
+        inner: &dyn Translator,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        texts
+            .iter()
+            .map(|text| self.translate(inner, text, lang, options))
+            .collect()
+    }
+}
+
+/// A translator made of `middleware` around an inner translator.
+pub struct Layered<M: TranslatorMiddleware> {
+    inner: Box<dyn Translator>,
+    middleware: M,
+}
+
+impl<M: TranslatorMiddleware> Layered<M> {
+    /// Wrap `inner` with `middleware`.
+    pub fn new(inner: Box<dyn Translator>, middleware: M) -> Layered<M> {
+        Layered { inner, middleware }
+    }
+}
+
+impl<M: TranslatorMiddleware> Translator for Layered<M> {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        self.translate_with(text, lang, &TranslateOptions::new())
+    }
+
+    fn translate_with(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        self.middleware
+            .translate(self.inner.as_ref(), text, lang, options)
+    }
+
+    fn translate_many(
+        &self,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        self.middleware
+            .translate_many(self.inner.as_ref(), texts, lang, options)
+    }
+
+    fn batch_limits(&self) -> BatchLimits {
+        self.inner.batch_limits()
+    }
+
+    fn supported_options(&self) -> &[TranslateOption] {
+        self.inner.supported_options()
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        self.inner.get_supported_langs()
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        self.inner.is_lang_supported(single_lang)
+    }
+
+    fn is_pair_supported(&self, from_lang: &str, to_lang: &str) -> bool {
+        self.inner.is_pair_supported(from_lang, to_lang)
+    }
+
+    fn pivot_lang(&self) -> Option<&str> {
+        self.inner.pivot_lang()
+    }
+}
+
+impl<M: TranslatorMiddleware + Clone + 'static> TranslatorLayer for M {
+    fn layer(&self, inner: Box<dyn Translator>) -> Box<dyn Translator> {
+        Box::new(Layered::new(inner, self.clone()))
+    }
+}
+
+/// A builder stacking layers around a backend.
+///
+/// Layers added first are the outermost ones: they see each call first and
+/// its result last.
+#[derive(Default)]
+pub struct TranslatorBuilder {
+    layers: Vec<Box<dyn TranslatorLayer>>,
+}
+
+impl TranslatorBuilder {
+    /// Create a builder without any layers.
+    pub fn new() -> TranslatorBuilder {
+        TranslatorBuilder { layers: Vec::new() }
+    }
+
+    /// Add `layer` inside all layers added so far.
+    pub fn layer(mut self, layer: impl TranslatorLayer + 'static) -> TranslatorBuilder {
+        self.layers.push(Box::new(layer));
+        self
+    }
+
+    /// Add `layer` if it is `Some`, which is handy for optional
+    /// configuration.
+    pub fn optional_layer(
+        self,
+        layer: Option<impl TranslatorLayer + 'static>,
+    ) -> TranslatorBuilder {
+        match layer {
+            Some(layer) => self.layer(layer),
+            None => self,
+        }
+    }
+
+    /// Wrap `backend` in all layers.
+    pub fn build(self, backend: impl Translator + 'static) -> Box<dyn Translator> {
+        self.layers
+            .iter()
+            .rev()
+            .fold(Box::new(backend), |inner, layer| layer.layer(inner))
+    }
+}
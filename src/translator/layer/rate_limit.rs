@@ -0,0 +1,99 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::TranslatorMiddleware;
+use crate::translator::options::TranslateOptions;
+use crate::translator::{TranslationDirection, TranslationError, Translator};
+
+/// A limiter keeping a minimum interval between requests.
+///
+/// Clones share the same schedule, so one limiter can cover every
+/// translator using the same backend account.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RateLimiter {
+    /// Create a limiter allowing one request per `interval`.
+    pub fn new(interval: Duration) -> RateLimiter {
+        RateLimiter {
+            interval,
+            next_slot: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Get the minimum interval between requests.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Block until a request may be sent.
+    pub fn wait(&self) {
+        // Reserve the slot while holding the lock, but sleep without it so
+        // that other callers can reserve the following slots meanwhile.
+        let slot = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let now = Instant::now();
+            let slot = next_slot.map_or(now, |next| next.max(now));
+            *next_slot = Some(slot + self.interval);
+            slot
+        };
+        std::thread::sleep(slot.saturating_duration_since(Instant::now()));
+    }
+}
+
+/// A layer keeping a minimum interval between requests.
+///
+/// A batch of texts is counted as one request per chunk within the
+/// [`batch_limits`](Translator::batch_limits) of the wrapped translator.
+#[derive(Clone, Debug)]
+pub struct RateLimitLayer {
+    limiter: RateLimiter,
+}
+
+impl RateLimitLayer {
+    /// Create a layer allowing one request per `interval`.
+    pub fn new(interval: Duration) -> RateLimitLayer {
+        RateLimitLayer {
+            limiter: RateLimiter::new(interval),
+        }
+    }
+
+    /// Create a layer sharing `limiter` with others.
+    pub fn from_limiter(limiter: RateLimiter) -> RateLimitLayer {
+        RateLimitLayer { limiter }
+    }
+}
+
+impl TranslatorMiddleware for RateLimitLayer {
+    fn translate(
+        &self,
+        inner: &dyn Translator,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        self.limiter.wait();
+        inner.translate_with(text, lang, options)
+    }
+
+    fn translate_many(
+        &self,
+        inner: &dyn Translator,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        inner
+            .batch_limits()
+            .chunks(texts)
+            .into_iter()
+            .flat_map(|chunk| {
+                self.limiter.wait();
+                inner.translate_many(&texts[chunk], lang, options)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use super::TranslatorMiddleware;
+use crate::telemetry;
+use crate::translator::options::TranslateOptions;
+use crate::translator::{TranslationDirection, TranslationError, Translator};
+
+/// A layer sending a request again when it fails with a transient error.
+///
+/// Only errors whose kind [is transient](crate::TranslationErrorKind::is_transient)
+/// are retried. The wait before each retry doubles, starting from the
+/// initial backoff. In a batch, only the texts that failed are sent again,
+/// together.
+///
+/// Each retry is told to the [`CallContext`](crate::CallContext) of the
+/// call before it is sent, and sent with its attempt number in the context,
+/// which backends record on their `translate` spans.
+#[derive(Clone, Debug)]
+pub struct RetryLayer {
+    max_retries: u32,
+    backoff: Duration,
+}
+
+impl RetryLayer {
+    /// Create a layer retrying up to `max_retries` times, with an initial
+    /// backoff of 500 milliseconds.
+    pub fn new(max_retries: u32) -> RetryLayer {
+        RetryLayer {
+            max_retries,
+            backoff: Duration::from_millis(500),
+        }
+    }
+
+    /// Wait `backoff` before the first retry instead.
+    pub fn with_backoff(mut self, backoff: Duration) -> RetryLayer {
+        self.backoff = backoff;
+        self
+    }
+}
+
+impl TranslatorMiddleware for RetryLayer {
+    fn translate(
+        &self,
+        inner: &dyn Translator,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        let mut retries = 0;
+        let mut result = inner.translate_with(text, lang, options);
+        while let Err(e) = &result
+            && e.kind.is_transient()
+            && retries < self.max_retries
+        {
+            options.context.notify_retry(retries + 1, e);
+            telemetry::record_retry(inner.name(), retries + 1, e);
+            std::thread::sleep(self.backoff.saturating_mul(1 << retries.min(16)));
+            retries += 1;
+            result = inner.translate_with(text, lang, &attempt_options(options, retries));
+        }
+        result
+    }
+
+    fn translate_many(
+        &self,
+        inner: &dyn Translator,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        let mut results = inner.translate_many(texts, lang, options);
+        let mut retries = 0;
+        while retries < self.max_retries {
+            let failed = (0..texts.len())
+                .filter(|&index| {
+                    results[index]
+                        .as_ref()
+                        .is_err_and(|e| e.kind.is_transient())
+                })
+                .collect::<Vec<usize>>();
+            let Some(Err(e)) = failed.first().map(|&index| &results[index]) else {
+                break;
+            };
+            options.context.notify_retry(retries + 1, e);
+            telemetry::record_retry(inner.name(), retries + 1, e);
+            std::thread::sleep(self.backoff.saturating_mul(1 << retries.min(16)));
+            retries += 1;
+
+            let failed_texts = failed
+                .iter()
+                .map(|&index| texts[index])
+                .collect::<Vec<&str>>();
+            let retried =
+                inner.translate_many(&failed_texts, lang, &attempt_options(options, retries));
+            for (index, result) in failed.into_iter().zip(retried) {
+                results[index] = result;
+            }
+        }
+        results
+    }
+}
+
+/// Get `options` for the attempt after `retries` retries.
+fn attempt_options(options: &TranslateOptions, retries: u32) -> TranslateOptions {
+    let context = options.context.clone().with_attempt(retries + 1);
+    options.clone().with_context(context)
+}
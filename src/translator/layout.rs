@@ -0,0 +1,195 @@
+use super::TranslationError;
+
+/// The shape of a single line of the original text.
+enum LineShape {
+    /// A line containing nothing but whitespace. It is kept verbatim and
+    /// never sent to the backend.
+    Blank(String),
+    /// A line with some content. Only its leading indentation is kept
+    /// locally; the content itself is sent to the backend.
+    Content { indent: String },
+}
+
+/// The line structure of a piece of text.
+///
+/// Backends like Baidu and Youdao split their input on line breaks, drop
+/// blank lines and strip indentation, and return one segment per line.
+/// `LineLayout` remembers what was dropped so that the translated segments
+/// can be put back into the original structure.
+pub(crate) struct LineLayout {
+    lines: Vec<LineShape>,
+    newline: &'static str,
+}
+
+impl LineLayout {
+    /// Split `text` into its layout and the content to send to the backend.
+    ///
+    /// # Returns
+    /// The layout of `text`, and the content of all non-blank lines with
+    /// indentation stripped, separated by `\n`.
+    pub(crate) fn split(text: &str) -> (LineLayout, String) {
+        let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        let mut lines = Vec::new();
+        let mut content = Vec::new();
+        for line in text.split('\n') {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                lines.push(LineShape::Blank(line.to_string()));
+            } else {
+                let indent_len = line.len() - line.trim_start().len();
+                lines.push(LineShape::Content {
+                    indent: line[..indent_len].to_string(),
+                });
+                content.push(trimmed);
+            }
+        }
+
+        (LineLayout { lines, newline }, content.join("\n"))
+    }
+
+    /// Check whether the text has no content at all.
+    pub(crate) fn is_blank(&self) -> bool {
+        self.lines
+            .iter()
+            .all(|line| matches!(line, LineShape::Blank(_)))
+    }
+
+    /// Get the number of lines with some content.
+    fn content_lines(&self) -> usize {
+        self.lines
+            .iter()
+            .filter(|line| matches!(line, LineShape::Content { .. }))
+            .count()
+    }
+
+    /// Put the translated `segments` back into the original structure.
+    ///
+    /// The segments are joined and re-split on line breaks, so it does not
+    /// matter whether the backend returned one segment per line or a single
+    /// segment containing line breaks.
+    ///
+    /// # Returns
+    /// The translated text, or `None` if the number of translated lines
+    /// does not match the number of content lines, the backend having
+    /// merged or split some lines.
+    pub(crate) fn restore(&self, segments: &[String]) -> Option<String> {
+        let translated = lines(segments);
+        (translated.len() == self.content_lines()).then(|| self.fill(translated))
+    }
+
+    /// Put one translated line back into each content line, in order.
+    fn fill(&self, translated: Vec<String>) -> String {
+        let mut translated = translated.into_iter();
+        self.lines
+            .iter()
+            .map(|line| match line {
+                LineShape::Blank(blank) => blank.clone(),
+                LineShape::Content { indent } => {
+                    indent.clone() + &translated.next().unwrap_or_default()
+                }
+            })
+            .collect::<Vec<String>>()
+            .join(self.newline)
+    }
+}
+
+/// Get the non-blank lines of `segments`, trimmed.
+fn lines(segments: &[String]) -> Vec<String> {
+    segments
+        .join("\n")
+        .split('\n')
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect()
+}
+
+/// Translate `text` in a single request sent with `send`, which gives back
+/// the translated segments, keeping its layout.
+///
+/// If the backend merged or split some lines, each content line is sent on
+/// its own instead, and the lines it comes back as are joined by spaces. A
+/// text of a single content line is not sent again. Blank texts are
+/// returned as they are.
+pub(crate) fn translate_text(
+    text: &str,
+    send: impl Fn(&str) -> Result<Vec<String>, TranslationError>,
+) -> Result<String, TranslationError> {
+    let (layout, content) = LineLayout::split(text);
+    if layout.is_blank() {
+        return Ok(text.to_string());
+    }
+
+    let segments = send(&content)?;
+    if let Some(output) = layout.restore(&segments) {
+        return Ok(output);
+    }
+    if layout.content_lines() == 1 {
+        return Ok(layout.fill(vec![lines(&segments).join(" ")]));
+    }
+    tracing::debug!(
+        expected = layout.content_lines(),
+        received = lines(&segments).len(),
+        "lines mismatched, translating them one by one"
+    );
+    let translated = content
+        .split('\n')
+        .map(|line| Ok(lines(&send(line)?).join(" ")))
+        .collect::<Result<Vec<String>, TranslationError>>()?;
+    Ok(layout.fill(translated))
+}
+
+/// Translate `texts` as the content lines of a single request sent with
+/// `send`, which gives back the translated segments.
+///
+/// The translated lines are handed back to each text in turn, so the
+/// backend must return as many lines as were sent. If it does not, having
+/// merged or split some lines, each text is translated on its own with
+/// `translate_one` instead. Blank texts are returned as they are.
+pub(crate) fn translate_lines(
+    texts: &[&str],
+    send: impl FnOnce(&str) -> Result<Vec<String>, TranslationError>,
+    translate_one: impl Fn(&str) -> Result<String, TranslationError>,
+) -> Vec<Result<String, TranslationError>> {
+    if texts.len() < 2 {
+        return texts.iter().map(|text| translate_one(text)).collect();
+    }
+
+    let (layouts, contents): (Vec<LineLayout>, Vec<String>) =
+        texts.iter().map(|text| LineLayout::split(text)).unzip();
+    let content = contents
+        .into_iter()
+        .filter(|content| !content.is_empty())
+        .collect::<Vec<String>>()
+        .join("\n");
+    if content.is_empty() {
+        return texts.iter().map(|text| Ok(text.to_string())).collect();
+    }
+
+    let segments = match send(&content) {
+        Ok(segments) => segments,
+        Err(e) => return texts.iter().map(|_| Err(e.clone())).collect(),
+    };
+    let translated = lines(&segments);
+    let expected = layouts.iter().map(LineLayout::content_lines).sum::<usize>();
+    if translated.len() != expected {
+        tracing::debug!(
+            expected,
+            received = translated.len(),
+            "batched lines mismatched, translating texts one by one"
+        );
+        return texts.iter().map(|text| translate_one(text)).collect();
+    }
+
+    let mut start = 0;
+    layouts
+        .iter()
+        .map(|layout| {
+            let end = start + layout.content_lines();
+            let output = layout.fill(translated[start..end].to_vec());
+            start = end;
+            Ok(output)
+        })
+        .collect()
+}
@@ -1,10 +1,63 @@
 pub mod baidu;
+pub mod cassette;
 pub mod google;
+pub mod langs;
+pub mod layer;
+mod layout;
+pub mod options;
+pub mod signing;
 pub mod youdao;
 
+use options::{TranslateOption, TranslateOptions};
+
+/// The kinds of errors that may occur when translating.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum TranslationErrorKind {
+    /// The request could not be sent, or no response was received.
+    Network,
+    /// The backend refused the request because of too many requests.
+    RateLimited,
+    /// The backend failed temporarily on its side.
+    Server,
+    /// The backend refused the request, for example because of bad
+    /// credentials or an exhausted quota.
+    Api,
+    /// A language is not supported by the backend.
+    UnsupportedLanguage,
+    /// Any other error.
+    Other,
+}
+
+impl TranslationErrorKind {
+    /// Get a short, stable name of the kind, used in logs and metrics.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TranslationErrorKind::Network => "network",
+            TranslationErrorKind::RateLimited => "rate_limited",
+            TranslationErrorKind::Server => "server",
+            TranslationErrorKind::Api => "api",
+            TranslationErrorKind::UnsupportedLanguage => "unsupported_language",
+            TranslationErrorKind::Other => "other",
+        }
+    }
+
+    /// Check if an error of this kind may go away when the request is sent
+    /// again later.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            TranslationErrorKind::Network
+                | TranslationErrorKind::RateLimited
+                | TranslationErrorKind::Server
+        )
+    }
+}
+
 /// Errors that may occur when translating.
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TranslationError {
+    /// The kind of the error.
+    pub kind: TranslationErrorKind,
     /// The message string describing the error.
     pub message: String,
 }
@@ -16,7 +69,11 @@ impl std::fmt::Display for TranslationError {
 }
 
 /// A trait to support text translation.
-pub trait Translator {
+///
+/// Translators are shared between threads, so that one instance, with its
+/// rate limit and cache, can serve concurrent runs like those of
+/// [`mangle_batch`](crate::mangle_batch).
+pub trait Translator: Send + Sync {
     /// Translate `text` from one language to another by some means.
     ///
     /// # Arguments
@@ -25,8 +82,10 @@ pub trait Translator {
     /// * `to_lang` - The language to translate `text` to.
     ///
     /// # Example
-    /// ```rust
-    /// let translator: Translator = ...;
+    /// ```rust,no_run
+    /// use translator_mangler::{TranslationDirection, Translator, TranslatorBaidu};
+    ///
+    /// let translator = TranslatorBaidu::new("[YOUR_APP_ID]", "[YOUR_API_KEY]");
     /// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
     ///
     /// let text = translator.translate("Hello, world!", &lang).unwrap();
@@ -37,13 +96,97 @@ pub trait Translator {
         lang: &TranslationDirection,
     ) -> Result<String, TranslationError>;
 
+    /// Translate `text` like [`translate`](Translator::translate), with
+    /// vendor features chosen in `options`.
+    ///
+    /// Options not listed by [`supported_options`](Translator::supported_options)
+    /// are ignored. By default, all options are ignored.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use translator_mangler::{
+    ///     TranslateOptions, TranslationDirection, Translator, TranslatorGoogleCloud,
+    /// };
+    ///
+    /// let translator = TranslatorGoogleCloud::new("[YOUR_API_KEY]");
+    /// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
+    /// let options = TranslateOptions::new().with_model("nmt");
+    ///
+    /// let text = translator.translate_with("Hello, world!", &lang, &options).unwrap();
+    /// ```
+    fn translate_with(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        let _ = options;
+        self.translate(text, lang)
+    }
+
+    /// Translate every text of `texts` in the same direction with the same
+    /// options, in as few requests as the backend allows.
+    ///
+    /// Backends able to carry many texts in one request send them in
+    /// batches within their [`batch_limits`](Translator::batch_limits). By
+    /// default, each text is translated on its own with
+    /// [`translate_with`](Translator::translate_with).
+    ///
+    /// # Returns
+    /// The result of every text, in the order of `texts`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use translator_mangler::testing::TranslatorReverseWords;
+    /// use translator_mangler::{TranslateOptions, TranslationDirection, Translator};
+    ///
+    /// let translator = TranslatorReverseWords::new();
+    /// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
+    ///
+    /// let results = translator.translate_many(&["one two", "three"], &lang, &TranslateOptions::new());
+    /// assert_eq!(results[0].as_ref().unwrap(), "two one");
+    /// ```
+    fn translate_many(
+        &self,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        texts
+            .iter()
+            .map(|text| self.translate_with(text, lang, options))
+            .collect()
+    }
+
+    /// Get how much a single request of
+    /// [`translate_many`](Translator::translate_many) may carry.
+    ///
+    /// A single text per request by default.
+    fn batch_limits(&self) -> BatchLimits {
+        BatchLimits::SINGLE
+    }
+
+    /// Get the options honored by [`translate_with`](Translator::translate_with).
+    fn supported_options(&self) -> &[TranslateOption] {
+        &[]
+    }
+
+    /// Get a short, stable name of the translator, used in logs and metrics.
+    ///
+    /// Translators wrapping another report the name of the wrapped one.
+    fn name(&self) -> &str {
+        "unknown"
+    }
+
     /// Get a list of all supported languages.
     ///
     /// # Example
     /// ```rust
-    /// let translator: Translator = ...;
+    /// use translator_mangler::{Translator, TranslatorBaidu};
     ///
-    /// let languages = translator.get_languages();
+    /// let translator = TranslatorBaidu::new("[YOUR_APP_ID]", "[YOUR_API_KEY]");
+    ///
+    /// let languages = translator.get_supported_langs();
     /// ```
     fn get_supported_langs(&self) -> &[&'static str];
 
@@ -54,15 +197,373 @@ pub trait Translator {
     ///
     /// # Example
     /// ```rust
-    /// let translator: Translator = ...;
+    /// use translator_mangler::{Translator, TranslatorBaidu};
+    ///
+    /// let translator = TranslatorBaidu::new("[YOUR_APP_ID]", "[YOUR_API_KEY]");
     ///
     /// let is_supported = translator.is_lang_supported("klingon");
     /// ```
     fn is_lang_supported(&self, single_lang: &str) -> bool;
+
+    /// Check if the translator translates directly from `from_lang` to
+    /// `to_lang`, both in its own codes.
+    ///
+    /// Any two different supported languages by default.
+    ///
+    /// # Example
+    /// ```rust
+    /// use translator_mangler::{Translator, TranslatorYoudao};
+    ///
+    /// let translator = TranslatorYoudao::new("[YOUR_APP_KEY]", "[YOUR_APP_SECRET]");
+    ///
+    /// assert!(translator.is_pair_supported("ja", "zh-CHS"));
+    /// assert!(!translator.is_pair_supported("ja", "ko"));
+    /// ```
+    fn is_pair_supported(&self, from_lang: &str, to_lang: &str) -> bool {
+        from_lang != to_lang && self.is_lang_supported(from_lang) && self.is_lang_supported(to_lang)
+    }
+
+    /// Get the language the translator goes through internally when
+    /// translating between two other languages, if it does.
+    fn pivot_lang(&self) -> Option<&str> {
+        None
+    }
+
+    /// Get the code this translator uses for `lang`, which may be the code
+    /// of another backend for the same language, like `jp` for `ja` on
+    /// Baidu.
+    ///
+    /// `lang` is returned as is if it is supported or has no supported
+    /// equivalent.
+    ///
+    /// # Example
+    /// ```rust
+    /// use translator_mangler::{Translator, TranslatorBaidu, TranslatorYoudao};
+    ///
+    /// let baidu = TranslatorBaidu::new("[YOUR_APP_ID]", "[YOUR_API_KEY]");
+    /// let youdao = TranslatorYoudao::new("[YOUR_APP_KEY]", "[YOUR_APP_SECRET]");
+    ///
+    /// assert_eq!(baidu.native_lang("ja"), "jp");
+    /// assert_eq!(youdao.native_lang("jp"), "ja");
+    /// assert_eq!(youdao.native_lang("zh"), "zh-CHS");
+    /// ```
+    fn native_lang<'a>(&self, lang: &'a str) -> &'a str {
+        if self.is_lang_supported(lang) {
+            return lang;
+        }
+        langs::equivalent_langs(lang)
+            .iter()
+            .copied()
+            .find(|code| self.is_lang_supported(code))
+            .unwrap_or(lang)
+    }
+}
+
+/// How much a single batched request to a backend may carry.
+///
+/// Texts batched in a request are joined into lines, so each text after
+/// the first also counts the newline separating it from the one before.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::BatchLimits;
+///
+/// let limits = BatchLimits::new(2, 10);
+///
+/// // At most 2 texts and 10 characters per request, but a longer text
+/// // still gets a request of its own.
+/// let chunks = limits.chunks(&["one", "two", "three", "a very long text"]);
+/// assert_eq!(chunks, [0..2, 2..3, 3..4]);
+///
+/// // "12345" and "67890" make 10 characters, 11 with the newline.
+/// assert_eq!(limits.chunks(&["12345", "67890"]), [0..1, 1..2]);
+///
+/// // Chinese characters take 3 bytes each in UTF-8.
+/// let limits = BatchLimits::new(10, 100).with_max_bytes(12);
+/// assert_eq!(limits.chunks(&["一二", "三", "四五"]), [0..2, 2..3]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct BatchLimits {
+    /// The most texts in a request.
+    pub max_texts: usize,
+    /// The most characters in a request, over all its texts and the
+    /// separators between them.
+    pub max_chars: usize,
+    /// The most bytes in a request once encoded in UTF-8, over all its
+    /// texts and the separators between them, for backends limiting the
+    /// size of requests rather than their length.
+    pub max_bytes: usize,
+}
+
+impl BatchLimits {
+    /// A single text per request, of any length.
+    pub const SINGLE: BatchLimits = BatchLimits {
+        max_texts: 1,
+        max_chars: usize::MAX,
+        max_bytes: usize::MAX,
+    };
+
+    /// Create limits of `max_texts` texts and `max_chars` characters per
+    /// request, of any size in bytes.
+    pub fn new(max_texts: usize, max_chars: usize) -> BatchLimits {
+        BatchLimits {
+            max_texts,
+            max_chars,
+            max_bytes: usize::MAX,
+        }
+    }
+
+    /// Also limit requests to `max_bytes` bytes in UTF-8.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> BatchLimits {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Split `texts` into consecutive chunks, each fitting in a request.
+    ///
+    /// A text over the character or byte limit on its own gets a chunk of
+    /// its own.
+    ///
+    /// # Returns
+    /// The range of indices of `texts` in every chunk, in order.
+    pub fn chunks(&self, texts: &[&str]) -> Vec<std::ops::Range<usize>> {
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        let mut chars: usize = 0;
+        let mut bytes: usize = 0;
+        for (index, text) in texts.iter().enumerate() {
+            let len = text.chars().count();
+            if index > start {
+                // With the newline separating it from the text before.
+                let with_chars = chars.saturating_add(len).saturating_add(1);
+                let with_bytes = bytes.saturating_add(text.len()).saturating_add(1);
+                if index - start < self.max_texts
+                    && with_chars <= self.max_chars
+                    && with_bytes <= self.max_bytes
+                {
+                    chars = with_chars;
+                    bytes = with_bytes;
+                    continue;
+                }
+                chunks.push(start..index);
+                start = index;
+            }
+            chars = len;
+            bytes = text.len();
+        }
+        if start < texts.len() {
+            chunks.push(start..texts.len());
+        }
+        chunks
+    }
 }
 
 /// A pair of languages to translate between.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct TranslationDirection {
     pub from_lang: String,
     pub to_lang: String,
 }
+
+impl<T: Translator + ?Sized> Translator for Box<T> {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        (**self).translate(text, lang)
+    }
+
+    fn translate_with(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        (**self).translate_with(text, lang, options)
+    }
+
+    fn translate_many(
+        &self,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        (**self).translate_many(texts, lang, options)
+    }
+
+    fn batch_limits(&self) -> BatchLimits {
+        (**self).batch_limits()
+    }
+
+    fn supported_options(&self) -> &[TranslateOption] {
+        (**self).supported_options()
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        (**self).get_supported_langs()
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        (**self).is_lang_supported(single_lang)
+    }
+
+    fn is_pair_supported(&self, from_lang: &str, to_lang: &str) -> bool {
+        (**self).is_pair_supported(from_lang, to_lang)
+    }
+
+    fn pivot_lang(&self) -> Option<&str> {
+        (**self).pivot_lang()
+    }
+}
+
+impl<T: Translator + ?Sized> Translator for &T {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        (**self).translate(text, lang)
+    }
+
+    fn translate_with(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        (**self).translate_with(text, lang, options)
+    }
+
+    fn translate_many(
+        &self,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        (**self).translate_many(texts, lang, options)
+    }
+
+    fn batch_limits(&self) -> BatchLimits {
+        (**self).batch_limits()
+    }
+
+    fn supported_options(&self) -> &[TranslateOption] {
+        (**self).supported_options()
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        (**self).get_supported_langs()
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        (**self).is_lang_supported(single_lang)
+    }
+
+    fn is_pair_supported(&self, from_lang: &str, to_lang: &str) -> bool {
+        (**self).is_pair_supported(from_lang, to_lang)
+    }
+
+    fn pivot_lang(&self) -> Option<&str> {
+        (**self).pivot_lang()
+    }
+}
+
+impl<T: Translator + ?Sized> Translator for std::sync::Arc<T> {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        (**self).translate(text, lang)
+    }
+
+    fn translate_with(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        (**self).translate_with(text, lang, options)
+    }
+
+    fn translate_many(
+        &self,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        (**self).translate_many(texts, lang, options)
+    }
+
+    fn batch_limits(&self) -> BatchLimits {
+        (**self).batch_limits()
+    }
+
+    fn supported_options(&self) -> &[TranslateOption] {
+        (**self).supported_options()
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        (**self).get_supported_langs()
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        (**self).is_lang_supported(single_lang)
+    }
+
+    fn is_pair_supported(&self, from_lang: &str, to_lang: &str) -> bool {
+        (**self).is_pair_supported(from_lang, to_lang)
+    }
+
+    fn pivot_lang(&self) -> Option<&str> {
+        (**self).pivot_lang()
+    }
+}
+
+/// Check the HTTP status of `response` from a backend.
+///
+/// Too many requests and server errors are transient, any other failure is
+/// an API error.
+fn check_status(response: &reqwest::blocking::Response) -> Result<(), TranslationError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let kind = if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        TranslationErrorKind::RateLimited
+    } else if status.is_server_error() {
+        TranslationErrorKind::Server
+    } else {
+        TranslationErrorKind::Api
+    };
+    Err(TranslationError {
+        kind,
+        message: format!("REQUEST ERR: HTTP {}", status),
+    })
+}
+
+/// Decode the JSON body of `response` from a backend.
+///
+/// A body that is not the expected JSON, such as the error page of a proxy
+/// or a body cut short, is a server error, worth retrying.
+fn decode_response<T: serde::de::DeserializeOwned>(
+    response: reqwest::blocking::Response,
+) -> Result<T, TranslationError> {
+    response.json::<T>().map_err(|e| TranslationError {
+        kind: TranslationErrorKind::Server,
+        message: format!("RESPONSE ERR: {}", e),
+    })
+}
@@ -0,0 +1,291 @@
+//! Per-request options for vendor features such as models and domains.
+//!
+//! Each backend honors only some of the options, listed by
+//! [`Translator::supported_options`](super::Translator::supported_options).
+//! Options a backend does not honor are ignored.
+//!
+//! The options of a call also carry its [`CallContext`], which middleware
+//! passes down to the translators it wraps.
+
+use std::sync::Arc;
+
+use super::TranslationError;
+
+/// The format of the text to translate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextFormat {
+    /// Plain text.
+    Text,
+    /// HTML, whose markup is kept as it is.
+    Html,
+}
+
+impl TextFormat {
+    /// Get the name of the format as used by most APIs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TextFormat::Text => "text",
+            TextFormat::Html => "html",
+        }
+    }
+}
+
+/// How formal the translated text should be.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Formality {
+    /// A more formal language.
+    More,
+    /// A more informal language.
+    Less,
+}
+
+impl Formality {
+    /// Get the name of the formality as used by most APIs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Formality::More => "more",
+            Formality::Less => "less",
+        }
+    }
+}
+
+/// The options a backend may honor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TranslateOption {
+    /// [`TranslateOptions::format`].
+    Format,
+    /// [`TranslateOptions::formality`].
+    Formality,
+    /// [`TranslateOptions::model`].
+    Model,
+    /// [`TranslateOptions::domain`].
+    Domain,
+    /// [`TranslateOptions::glossary`].
+    Glossary,
+}
+
+impl TranslateOption {
+    /// All options.
+    pub const ALL: [TranslateOption; 5] = [
+        TranslateOption::Format,
+        TranslateOption::Formality,
+        TranslateOption::Model,
+        TranslateOption::Domain,
+        TranslateOption::Glossary,
+    ];
+}
+
+/// A callback told about each retry of a call with the retry number, from 1,
+/// and the error that caused it.
+type RetryListener = Arc<dyn Fn(u32, &TranslationError) + Send + Sync>;
+
+/// What a single call is part of, rather than what is asked of the backend.
+///
+/// The context travels with the [`TranslateOptions`] of the call through
+/// every layer, on whatever thread the call is made. It is not part of the
+/// options themselves: options differing only in their context are equal,
+/// hash the same and are serialized the same.
+///
+/// # Example
+/// ```rust
+/// use std::sync::atomic::{AtomicU32, Ordering};
+/// use std::sync::Arc;
+///
+/// use translator_mangler::{CallContext, TranslateOptions};
+///
+/// let retries = Arc::new(AtomicU32::new(0));
+/// let counter = retries.clone();
+/// let options = TranslateOptions::new().with_context(
+///     CallContext::new().with_retry_listener(move |_, _| {
+///         counter.fetch_add(1, Ordering::Relaxed);
+///     }),
+/// );
+/// assert_eq!(options, TranslateOptions::new());
+/// ```
+#[derive(Clone)]
+pub struct CallContext {
+    attempt: u32,
+    on_retry: Option<RetryListener>,
+}
+
+impl CallContext {
+    /// Create an empty context, for the first attempt of a call.
+    pub fn new() -> CallContext {
+        CallContext::default()
+    }
+
+    /// Mark the call as its `attempt`-th attempt, from 1.
+    ///
+    /// Middleware retrying calls should set this on the options of each
+    /// retry, so that backends can report it.
+    pub fn with_attempt(mut self, attempt: u32) -> CallContext {
+        self.attempt = attempt.max(1);
+        self
+    }
+
+    /// Get which attempt of the call this is, from 1.
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Tell `listener` about every retry of the call, with the retry number,
+    /// from 1, and the error that caused it.
+    pub fn with_retry_listener(
+        mut self,
+        listener: impl Fn(u32, &TranslationError) + Send + Sync + 'static,
+    ) -> CallContext {
+        self.on_retry = Some(Arc::new(listener));
+        self
+    }
+
+    /// Note that the call failed with `error` and is tried again, for the
+    /// `retry`-th time.
+    ///
+    /// Middleware retrying calls should call this before each retry, so that
+    /// callers such as [`mangle_with`](crate::mangle_with) can count them.
+    pub fn notify_retry(&self, retry: u32, error: &TranslationError) {
+        if let Some(listener) = &self.on_retry {
+            listener(retry, error);
+        }
+    }
+}
+
+impl Default for CallContext {
+    fn default() -> CallContext {
+        CallContext {
+            attempt: 1,
+            on_retry: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for CallContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("CallContext")
+            .field("attempt", &self.attempt)
+            .field("on_retry", &self.on_retry.is_some())
+            .finish()
+    }
+}
+
+impl PartialEq for CallContext {
+    fn eq(&self, _: &CallContext) -> bool {
+        true
+    }
+}
+
+impl Eq for CallContext {}
+
+impl std::hash::Hash for CallContext {
+    fn hash<H: std::hash::Hasher>(&self, _: &mut H) {}
+}
+
+/// Options for a single translation request.
+///
+/// Every option is unset by default, leaving the choice to the backend.
+///
+/// # Example
+/// ```rust
+/// use translator_mangler::{TextFormat, TranslateOption, TranslateOptions};
+///
+/// let options = TranslateOptions::new()
+///     .with_format(TextFormat::Html)
+///     .with_model("nmt");
+/// assert_eq!(options.set_options(), [TranslateOption::Format, TranslateOption::Model]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct TranslateOptions {
+    /// The format of the text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<TextFormat>,
+    /// How formal the translation should be.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formality: Option<Formality>,
+    /// The translation model, such as `nmt` or `base` on Google Cloud.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// The domain or field of the text, such as `medicine` on Baidu.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    /// The ID of a glossary or custom vocabulary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glossary: Option<String>,
+    /// The context of the call, which is not sent to the backend.
+    #[serde(skip)]
+    pub context: CallContext,
+}
+
+impl TranslateOptions {
+    /// Create options with nothing set.
+    pub fn new() -> TranslateOptions {
+        TranslateOptions::default()
+    }
+
+    /// Set the format of the text.
+    pub fn with_format(mut self, format: TextFormat) -> TranslateOptions {
+        self.format = Some(format);
+        self
+    }
+
+    /// Set how formal the translation should be.
+    pub fn with_formality(mut self, formality: Formality) -> TranslateOptions {
+        self.formality = Some(formality);
+        self
+    }
+
+    /// Set the translation model.
+    pub fn with_model(mut self, model: &str) -> TranslateOptions {
+        self.model = Some(model.to_string());
+        self
+    }
+
+    /// Set the domain or field of the text.
+    pub fn with_domain(mut self, domain: &str) -> TranslateOptions {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    /// Set the ID of a glossary or custom vocabulary.
+    pub fn with_glossary(mut self, glossary: &str) -> TranslateOptions {
+        self.glossary = Some(glossary.to_string());
+        self
+    }
+
+    /// Make the call in `context`.
+    pub fn with_context(mut self, context: CallContext) -> TranslateOptions {
+        self.context = context;
+        self
+    }
+
+    /// Get the same options outside of any context, to keep them without
+    /// holding on to the callbacks of the call.
+    pub(crate) fn without_context(&self) -> TranslateOptions {
+        TranslateOptions {
+            context: CallContext::default(),
+            ..self.clone()
+        }
+    }
+
+    /// Check if no option is set.
+    pub fn is_default(&self) -> bool {
+        *self == TranslateOptions::default()
+    }
+
+    /// Get the options that are set.
+    pub fn set_options(&self) -> Vec<TranslateOption> {
+        let set = [
+            self.format.is_some(),
+            self.formality.is_some(),
+            self.model.is_some(),
+            self.domain.is_some(),
+            self.glossary.is_some(),
+        ];
+        TranslateOption::ALL
+            .into_iter()
+            .zip(set)
+            .filter_map(|(option, set)| set.then_some(option))
+            .collect()
+    }
+}
@@ -1,6 +1,12 @@
-use super::{TranslationDirection, TranslationError, Translator};
-
-use sha2::Digest;
+use super::layout;
+use super::options::{TranslateOption, TranslateOptions};
+use super::signing;
+use super::{
+    check_status, decode_response, BatchLimits, TranslationDirection, TranslationError,
+    TranslationErrorKind, Translator,
+};
+use crate::credentials::{CredentialError, Credentials, Secret};
+use crate::telemetry;
 
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -15,9 +21,11 @@ struct ResultYoudao {
 /// A translator using Youdao AI as its backend.
 ///
 /// Reference: [有道智云AI开放平台](https://ai.youdao.com/)
+#[derive(Debug)]
 pub struct TranslatorYoudao {
-    pub app_key: Box<String>,
-    pub app_secret: Box<String>,
+    pub app_key: String,
+    app_secret: Secret,
+    endpoint: String,
     client: reqwest::blocking::Client,
 }
 
@@ -33,76 +41,160 @@ impl TranslatorYoudao {
     ///
     /// # Example
     /// ```rust
+    /// use translator_mangler::TranslatorYoudao;
+    ///
     /// let translator = TranslatorYoudao::new("[YOUR_APP_KEY]", "[YOUR_APP_SECRET]");
     /// ```
     pub fn new(app_key: &str, app_secret: &str) -> TranslatorYoudao {
         TranslatorYoudao {
-            app_key: Box::new(app_key.to_string()),
-            app_secret: Box::new(app_secret.to_string()),
+            app_key: app_key.to_string(),
+            app_secret: Secret::new(app_secret),
+            endpoint: Self::ENDPOINT.to_string(),
             client: reqwest::blocking::Client::new(),
         }
     }
 
-    const SUPPORTED_LANGS: [&'static str; 15] = [
-        "zh-CHS", "zh-CHT", "vi", "en", "id", "it", "es", "ja", "pt", "ko", "fr", "ru", "de", "ar",
-        "th",
-    ];
-}
+    /// Create an instance of `TranslatorYoudao` with the given credentials.
+    ///
+    /// # Arguments
+    /// * `credentials` - Your App key and App secret.
+    ///
+    /// # Returns
+    /// A new instance of `TranslatorYoudao`, or an error if the App key is missing.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use translator_mangler::credentials::{Backend, CredentialProvider, EnvProvider};
+    /// use translator_mangler::TranslatorYoudao;
+    ///
+    /// let credentials = EnvProvider.credentials(Backend::Youdao).unwrap().unwrap();
+    /// let translator = TranslatorYoudao::from_credentials(credentials).unwrap();
+    /// ```
+    pub fn from_credentials(credentials: Credentials) -> Result<TranslatorYoudao, CredentialError> {
+        let Some(app_key) = credentials.id.as_deref() else {
+            return Err(CredentialError {
+                message: "Youdao AI requires an App key".to_string(),
+            });
+        };
+        Ok(TranslatorYoudao {
+            app_key: app_key.to_string(),
+            app_secret: credentials.secret,
+            endpoint: Self::ENDPOINT.to_string(),
+            client: reqwest::blocking::Client::new(),
+        })
+    }
 
-impl Translator for TranslatorYoudao {
-    /// Translate `text` from one language to another with Youdao AI.
+    /// Send requests to `endpoint` instead of the official Youdao endpoint.
     ///
-    /// Reference: [有道智云AI开放平台](https://ai.youdao.com/)
+    /// This is useful for proxies and for testing against a local server.
+    ///
+    /// # Arguments
+    /// * `endpoint` - The full URL of the translation API.
     ///
     /// # Example
     /// ```rust
-    /// let translator = TranslatorYoudao::new("[YOUR_APP_KEY]", "[YOUR_APP_SECRET]");
-    /// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
+    /// use translator_mangler::TranslatorYoudao;
     ///
-    /// println!("{}", translator.translate("Hello, world!", &lang).unwrap());
+    /// let translator = TranslatorYoudao::new("[YOUR_APP_KEY]", "[YOUR_APP_SECRET]")
+    ///     .with_endpoint("http://127.0.0.1:8080/api");
     /// ```
-    fn translate(
-        &self,
-        text: &str,
-        lang: &TranslationDirection,
-    ) -> Result<String, TranslationError> {
+    pub fn with_endpoint(mut self, endpoint: &str) -> TranslatorYoudao {
+        self.endpoint = endpoint.to_string();
+        self
+    }
+
+    /// Classify an error code returned by the API.
+    ///
+    /// Reference: [错误代码列表](https://ai.youdao.com/DOCSIRMA/html/trans/api/wbfy/index.html)
+    fn error_kind(error_code: &str) -> TranslationErrorKind {
+        match error_code {
+            // Access frequency limited, long request too frequent
+            "411" | "412" => TranslationErrorKind::RateLimited,
+            // Translation service temporarily unavailable
+            "302" | "303" => TranslationErrorKind::Server,
+            _ => TranslationErrorKind::Api,
+        }
+    }
+
+    /// The official endpoint of the translation API.
+    const ENDPOINT: &'static str = "https://openapi.youdao.com/api";
+
+    /// The options honored by the translation API.
+    const SUPPORTED_OPTIONS: [TranslateOption; 2] =
+        [TranslateOption::Domain, TranslateOption::Glossary];
+
+    const SUPPORTED_LANGS: [&'static str; 15] = [
+        "zh-CHS", "zh-CHT", "vi", "en", "id", "it", "es", "ja", "pt", "ko", "fr", "ru", "de", "ar",
+        "th",
+    ];
+
+    /// The languages on at least one side of every supported pair.
+    const PAIR_HUBS: [&'static str; 3] = ["zh-CHS", "zh-CHT", "en"];
+
+    /// The most a single request may carry. The API takes up to 5000
+    /// characters per request.
+    const BATCH_LIMITS: BatchLimits = BatchLimits {
+        max_texts: 100,
+        max_chars: 5000,
+        max_bytes: usize::MAX,
+    };
+
+    /// Check that both languages of `lang` are supported.
+    fn check_langs(&self, lang: &TranslationDirection) -> Result<(), TranslationError> {
         if !self.is_lang_supported(lang.from_lang.as_str())
             || !self.is_lang_supported(lang.to_lang.as_str())
         {
             return Err(TranslationError {
+                kind: TranslationErrorKind::UnsupportedLanguage,
                 message: "Unsupported language".to_string(),
             });
         }
+        Ok(())
+    }
+
+    /// Send a single translation request, without instrumentation.
+    fn send(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        self.check_langs(lang)?;
+
+        // Blank lines and indentation are dropped by the API, so only
+        // send the content and keep the layout ourselves.
+        layout::translate_text(text, |content| self.send_content(content, lang, options))
+    }
 
+    /// Send a request translating `text`, made of non-blank lines without
+    /// indentation, without instrumentation.
+    ///
+    /// # Returns
+    /// The translated segments, which may hold many lines each.
+    fn send_content(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<Vec<String>, TranslationError> {
         // Get current UNIX timestamp
         let time_utc_unix = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs();
-        // Create truncated input
-        let input = if text.len() > 20 {
-            text[0..10].to_string() + &text.len().to_string() + &text[text.len() - 10..]
-        } else {
-            text.to_string()
-        };
         // Create salt for randomness
-        let salt = std::iter::repeat_with(|| fastrand::char(..))
-            .take(4)
-            .collect::<String>();
+        let salt = signing::salt(8);
         // Create signature
-        let signature = hex::encode(
-            sha2::Sha256::new()
-                .chain_update(
-                    format!(
-                        "{}{}{}{}{}",
-                        self.app_key, input, salt, time_utc_unix, self.app_secret
-                    )
-                    .as_bytes(),
-                )
-                .finalize(),
+        let signature = signing::youdao_sign_v3(
+            &self.app_key,
+            text,
+            &salt,
+            time_utc_unix,
+            self.app_secret.expose(),
         );
         // Generate request body
-        let req_obj = [
+        let curtime = time_utc_unix.to_string();
+        let mut req_obj = vec![
             ("q", text),
             ("from", &lang.from_lang),
             ("to", &lang.to_lang),
@@ -110,32 +202,131 @@ impl Translator for TranslatorYoudao {
             ("salt", &salt),
             ("sign", &signature),
             ("signType", "v3"),
-            ("curtime", &time_utc_unix.to_string()),
+            ("curtime", &curtime),
         ];
+        if let Some(domain) = &options.domain {
+            req_obj.push(("domain", domain));
+        }
+        if let Some(glossary) = &options.glossary {
+            req_obj.push(("vocabId", glossary));
+        }
 
         // Send request
-        let result = self
-            .client
-            .post("https://openapi.youdao.com/api")
-            .json(&req_obj)
-            .send();
+        let result = self.client.post(&self.endpoint).json(&req_obj).send();
 
         // Handle network error
         if let Err(e) = result {
             return Err(TranslationError {
+                kind: TranslationErrorKind::Network,
                 message: format!("NETWORK ERR: {}", e),
             });
         }
-        let result_json = result.unwrap().json::<ResultYoudao>().unwrap();
+        let response = result.unwrap();
+        check_status(&response)?;
+        let result_json = decode_response::<ResultYoudao>(response)?;
         // Handle API error
         let error_code = &result_json.error_code;
-        if !error_code.is_empty() && error_code.parse::<i32>().unwrap() != 0 {
+        if !error_code.is_empty() && error_code != "0" {
             return Err(TranslationError {
+                kind: Self::error_kind(error_code),
                 message: format!("API ERR: {}", result_json.error_code),
             });
         }
 
-        Ok(result_json.translation[0].clone())
+        if result_json.translation.is_empty() {
+            return Err(TranslationError {
+                kind: TranslationErrorKind::Api,
+                message: "API ERR: empty translation result".to_string(),
+            });
+        }
+
+        Ok(result_json.translation)
+    }
+}
+
+impl Translator for TranslatorYoudao {
+    /// Translate `text` from one language to another with Youdao AI.
+    ///
+    /// Reference: [有道智云AI开放平台](https://ai.youdao.com/)
+    ///
+    /// Multi-line text is translated line by line. Blank lines and
+    /// indentation are kept as they are in `text`.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use translator_mangler::{TranslationDirection, Translator, TranslatorYoudao};
+    ///
+    /// let translator = TranslatorYoudao::new("[YOUR_APP_KEY]", "[YOUR_APP_SECRET]");
+    /// let lang = TranslationDirection { from_lang: "en".to_string(), to_lang: "zh".to_string() };
+    ///
+    /// println!("{}", translator.translate("Hello, world!", &lang).unwrap());
+    /// ```
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        self.translate_with(text, lang, &TranslateOptions::new())
+    }
+
+    /// Translate `text` like [`translate`](Translator::translate), with the
+    /// domain and custom vocabulary chosen in `options`.
+    ///
+    /// The glossary is the ID of a custom vocabulary (`vocabId`) created in
+    /// the Youdao AI console.
+    fn translate_with(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        telemetry::instrument_request(self.name(), text, lang, options.context.attempt(), || {
+            self.send(text, lang, options)
+        })
+    }
+
+    /// Translate `texts` like [`translate_with`](Translator::translate_with),
+    /// sending the lines of many texts in a single request.
+    fn translate_many(
+        &self,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        if let Err(e) = self.check_langs(lang) {
+            return texts.iter().map(|_| Err(e.clone())).collect();
+        }
+        Self::BATCH_LIMITS
+            .chunks(texts)
+            .into_iter()
+            .flat_map(|chunk| {
+                layout::translate_lines(
+                    &texts[chunk],
+                    |content| {
+                        telemetry::instrument_request(
+                            self.name(),
+                            content,
+                            lang,
+                            options.context.attempt(),
+                            || self.send_content(content, lang, options),
+                        )
+                    },
+                    |text| self.translate_with(text, lang, options),
+                )
+            })
+            .collect()
+    }
+
+    fn batch_limits(&self) -> BatchLimits {
+        Self::BATCH_LIMITS
+    }
+
+    fn supported_options(&self) -> &[TranslateOption] {
+        &Self::SUPPORTED_OPTIONS
+    }
+
+    fn name(&self) -> &str {
+        "youdao"
     }
 
     fn get_supported_langs(&self) -> &[&'static str] {
@@ -145,4 +336,11 @@ impl Translator for TranslatorYoudao {
     fn is_lang_supported(&self, single_lang: &str) -> bool {
         Self::SUPPORTED_LANGS.contains(&single_lang)
     }
+
+    fn is_pair_supported(&self, from_lang: &str, to_lang: &str) -> bool {
+        from_lang != to_lang
+            && self.is_lang_supported(from_lang)
+            && self.is_lang_supported(to_lang)
+            && (Self::PAIR_HUBS.contains(&from_lang) || Self::PAIR_HUBS.contains(&to_lang))
+    }
 }
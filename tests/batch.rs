@@ -0,0 +1,154 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use translator_mangler::layer::{RateLimitLayer, TranslatorBuilder};
+use translator_mangler::testing::{TranslatorPigLatin, TranslatorReverseWords};
+use translator_mangler::{
+    get_random_lang_path, mangle_batch, mangle_mixed_batch, CancellationToken, MangleError,
+    MangleJob, MangleOptions, MixedHop, MixedJob, TranslationDirection, TranslationError,
+    TranslationErrorKind, Translator,
+};
+
+/// A translator reversing words slowly, failing on texts with "fail" and
+/// counting the requests in flight.
+#[derive(Default)]
+struct TranslatorSlow {
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+impl Translator for TranslatorSlow {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(20));
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        if text.contains("fail") {
+            return Err(TranslationError {
+                kind: TranslationErrorKind::Api,
+                message: format!("refused {}", text),
+            });
+        }
+        TranslatorReverseWords::new().translate(text, lang)
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        &["en", "zh", "ja"]
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        self.get_supported_langs().contains(&single_lang)
+    }
+}
+
+fn jobs(texts: &[&str], rounds: usize) -> Vec<MangleJob> {
+    texts
+        .iter()
+        .map(|text| MangleJob {
+            text: text.to_string(),
+            path: get_random_lang_path("en", &["zh", "ja"], rounds),
+        })
+        .collect()
+}
+
+#[test]
+fn results_keep_the_order_of_jobs() {
+    let translator = TranslatorSlow::default();
+    let texts = (0..12)
+        .map(|index| format!("job {}", index))
+        .collect::<Vec<String>>();
+    let texts = texts.iter().map(String::as_str).collect::<Vec<&str>>();
+    let results = mangle_batch(&translator, &jobs(&texts, 1), 4, &MangleOptions::new());
+
+    for (index, result) in results.into_iter().enumerate() {
+        assert_eq!(result.unwrap(), format!("{} job", index));
+    }
+}
+
+#[test]
+fn concurrency_is_limited() {
+    let translator = TranslatorSlow::default();
+    let texts = ["a b"; 8];
+    let start = Instant::now();
+    let results = mangle_batch(&translator, &jobs(&texts, 2), 3, &MangleOptions::new());
+
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(translator.max_in_flight.load(Ordering::SeqCst), 3);
+    // 8 jobs of 2 hops of 20ms on 3 threads take 3 turns.
+    assert!(start.elapsed() < Duration::from_millis(16 * 20));
+
+    let translator = TranslatorSlow::default();
+    mangle_batch(&translator, &jobs(&texts, 1), 0, &MangleOptions::new());
+    assert_eq!(translator.max_in_flight.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn failures_are_isolated() {
+    let translator = TranslatorSlow::default();
+    let results = mangle_batch(
+        &translator,
+        &jobs(&["one two", "please fail", "three four"], 2),
+        3,
+        &MangleOptions::new(),
+    );
+
+    assert_eq!(results[0].as_ref().unwrap(), "one two");
+    assert!(
+        matches!(&results[1], Err(MangleError::HopFailed(f)) if f.error.message.contains("fail"))
+    );
+    assert_eq!(results[2].as_ref().unwrap(), "three four");
+}
+
+#[test]
+fn rate_limits_are_shared_by_all_jobs() {
+    let translator = TranslatorBuilder::new()
+        .layer(RateLimitLayer::new(Duration::from_millis(20)))
+        .build(TranslatorReverseWords::new());
+    let start = Instant::now();
+    let results = mangle_batch(&translator, &jobs(&["a b"; 5], 2), 5, &MangleOptions::new());
+
+    assert!(results.iter().all(Result::is_ok));
+    // 10 requests, the first one sent at once.
+    assert!(start.elapsed() >= Duration::from_millis(9 * 20));
+}
+
+#[test]
+fn cancellation_stops_every_job() {
+    let token = CancellationToken::new();
+    token.cancel();
+    let options = MangleOptions::new().with_cancellation(token);
+    let translator = TranslatorReverseWords::new();
+    let results = mangle_batch(&translator, &jobs(&["a b", "c d"], 2), 2, &options);
+
+    assert!(results
+        .iter()
+        .all(|result| matches!(result, Err(MangleError::Stopped { hops: 0, .. }))));
+}
+
+#[test]
+fn mixed_jobs_use_their_translators() {
+    let reverse = TranslatorReverseWords::new();
+    let pig_latin = TranslatorPigLatin::new();
+    let translators: [&dyn Translator; 2] = [&reverse, &pig_latin];
+    let jobs = (0..4)
+        .map(|index| MixedJob {
+            text: "one two".to_string(),
+            path: get_random_lang_path("en", &["zh"], 2)
+                .into_iter()
+                .map(|direction| MixedHop {
+                    direction,
+                    translator: index % 2,
+                })
+                .collect(),
+        })
+        .collect::<Vec<MixedJob>>();
+    let results = mangle_mixed_batch(&translators, &jobs, 2, &MangleOptions::new());
+
+    assert_eq!(results[0].as_ref().unwrap(), "one two");
+    assert_eq!(results[1].as_ref().unwrap(), "onewayway otwayway");
+    assert_eq!(results[2].as_ref().unwrap(), "one two");
+}
@@ -0,0 +1,184 @@
+use std::sync::Arc;
+
+use translator_mangler::testing::{TranslatorReverseWords, TranslatorScripted};
+use translator_mangler::{
+    get_random_lang_path, mangle_best_of, mangle_mixed_best_of, BestOf, CancellationToken,
+    MangleError, MangleObserver, MangleOptions, MangleStep, MixedHop, Objective, StopReason,
+    TranslationDirection, Translator,
+};
+
+const ORIGINAL: &str = "The quick brown fox jumps over the lazy dog.";
+
+/// A translator giving, in two hops each, a close, a far and a medium
+/// version of the original text.
+fn three_candidates() -> TranslatorScripted {
+    TranslatorScripted::new()
+        .then_ok("甲")
+        .then_ok("The quick brown fox leaps over the lazy dog.")
+        .then_ok("乙")
+        .then_ok("Nothing.")
+        .then_ok("丙")
+        .then_ok("A quick fox jumps over a dog.")
+}
+
+fn two_hops() -> Vec<TranslationDirection> {
+    get_random_lang_path("en", &["zh"], 2)
+}
+
+#[test]
+fn objectives_pick_their_candidate() {
+    for (objective, best) in [
+        (Objective::MostMangled, 1),
+        (Objective::LeastMangled, 0),
+        (Objective::TargetLength(30), 2),
+        (Objective::TargetScore(40.0), 2),
+    ] {
+        let translator = three_candidates();
+        let outcome = mangle_best_of(
+            &translator,
+            ORIGINAL,
+            &mut two_hops,
+            &BestOf::new(3, objective),
+            &MangleOptions::new(),
+        )
+        .unwrap();
+
+        assert_eq!(outcome.candidates.len(), 3, "{:?}", objective);
+        assert_eq!(outcome.best, Some(best), "{:?}", objective);
+        assert_eq!(outcome.best().unwrap().index, best);
+        assert_eq!(outcome.skipped, 0);
+        assert_eq!(outcome.stopped, None);
+    }
+}
+
+#[test]
+fn candidates_over_budget_are_skipped() {
+    let translator = TranslatorReverseWords::new();
+    let mut lengths = [4, 2, 4, 2].into_iter();
+    let mut next_path = || get_random_lang_path("en", &["zh", "ja"], lengths.next().unwrap());
+    let search = BestOf::new(4, Objective::MostMangled).with_max_requests(7);
+    let outcome = mangle_best_of(
+        &translator,
+        "one two",
+        &mut next_path,
+        &search,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    let indices = outcome
+        .candidates
+        .iter()
+        .map(|candidate| candidate.index)
+        .collect::<Vec<usize>>();
+    assert_eq!(indices, [0, 1]);
+    assert_eq!(outcome.skipped, 2);
+}
+
+#[test]
+fn cancellation_keeps_completed_candidates() {
+    /// Cancel the run once `hops` hops are done.
+    struct CancelAfter {
+        token: CancellationToken,
+        hops: std::sync::atomic::AtomicUsize,
+    }
+
+    impl MangleObserver for CancelAfter {
+        fn on_step_done(&self, _: usize, _: usize, _: &MangleStep) {
+            if self.hops.fetch_sub(1, std::sync::atomic::Ordering::SeqCst) == 1 {
+                self.token.cancel();
+            }
+        }
+    }
+
+    let token = CancellationToken::new();
+    let observer = Arc::new(CancelAfter {
+        token: token.clone(),
+        hops: 3.into(),
+    });
+    let options = MangleOptions::new()
+        .with_cancellation(token)
+        .with_shared_observer(observer);
+    let translator = TranslatorReverseWords::new();
+    let outcome = mangle_best_of(
+        &translator,
+        "one two",
+        &mut two_hops,
+        &BestOf::new(5, Objective::MostMangled),
+        &options,
+    )
+    .unwrap();
+
+    assert_eq!(outcome.candidates.len(), 1);
+    assert_eq!(outcome.stopped, Some(StopReason::Cancelled));
+
+    let cancelled = mangle_best_of(
+        &translator,
+        "one two",
+        &mut two_hops,
+        &BestOf::new(5, Objective::MostMangled),
+        &options,
+    );
+    assert!(matches!(cancelled, Err(MangleError::Stopped { .. })));
+}
+
+#[test]
+fn failures_are_recorded_and_the_search_goes_on() {
+    let translator = TranslatorScripted::new()
+        .then_ok("甲")
+        .then_ok("one")
+        .then_err("boom")
+        .then_ok("乙")
+        .then_ok("two");
+    let outcome = mangle_best_of(
+        &translator,
+        ORIGINAL,
+        &mut two_hops,
+        &BestOf::new(3, Objective::MostMangled),
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    let indices = outcome
+        .candidates
+        .iter()
+        .map(|candidate| candidate.index)
+        .collect::<Vec<usize>>();
+    assert_eq!(indices, [0, 2]);
+    assert_eq!(outcome.failed.len(), 1);
+    assert!(matches!(
+        &outcome.failed[0],
+        (1, MangleError::HopFailed(f)) if f.error.message == "boom"
+    ));
+    assert!(outcome.best().is_some());
+}
+
+#[test]
+fn mixed_candidates_use_their_translators() {
+    let first = TranslatorScripted::new().then_ok("a").then_ok("b");
+    let second = TranslatorReverseWords::new();
+    let translators: [&dyn Translator; 2] = [&first, &second];
+    let mut round = 0;
+    let mut next_path = || {
+        round += 1;
+        Ok(two_hops()
+            .into_iter()
+            .map(|direction| MixedHop {
+                direction,
+                translator: round % 2,
+            })
+            .collect())
+    };
+    let outcome = mangle_mixed_best_of(
+        &translators,
+        "one two",
+        &mut next_path,
+        &BestOf::new(2, Objective::LeastMangled),
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    assert_eq!(outcome.candidates[0].trace.result, "one two");
+    assert_eq!(outcome.candidates[1].trace.result, "b");
+    assert_eq!(outcome.best, Some(0));
+}
@@ -0,0 +1,115 @@
+use std::time::{Duration, Instant};
+
+use translator_mangler::testing::{TranslatorReverseWords, TranslatorScripted};
+use translator_mangler::{
+    get_random_lang_path, mangle_with, CancellationToken, MangleError, MangleOptions, StopReason,
+    TranslationDirection, TranslationError, Translator,
+};
+
+/// A translator cancelling `token` once it has been called `after` times.
+struct CancelAfter {
+    inner: TranslatorScripted,
+    token: CancellationToken,
+    after: usize,
+}
+
+impl Translator for CancelAfter {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        let result = self.inner.translate(text, lang);
+        if self.inner.calls().len() >= self.after {
+            self.token.cancel();
+        }
+        result
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        self.inner.get_supported_langs()
+    }
+
+    fn is_lang_supported(&self, lang: &str) -> bool {
+        self.inner.is_lang_supported(lang)
+    }
+}
+
+#[test]
+fn cancel_keeps_last_completed_hop() {
+    let token = CancellationToken::new();
+    let translator = CancelAfter {
+        inner: TranslatorScripted::new()
+            .then_ok("你好")
+            .then_ok("こんにちは")
+            .then_ok("unused"),
+        token: token.clone(),
+        after: 2,
+    };
+    let path = get_random_lang_path("en", &["zh", "ja"], 3);
+    let options = MangleOptions::new().with_cancellation(token);
+
+    match mangle_with(&translator, "Hello", &path, &options) {
+        Err(MangleError::Stopped { reason, text, hops }) => {
+            assert_eq!(reason, StopReason::Cancelled);
+            assert_eq!(text, "こんにちは");
+            assert_eq!(hops, 2);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(translator.inner.calls().len(), 2);
+}
+
+#[test]
+fn cancel_interrupts_delay() {
+    let token = CancellationToken::new();
+    let handle = token.clone();
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        handle.cancel();
+    });
+    let path = get_random_lang_path("en", &["zh", "ja"], 3);
+    let options = MangleOptions::new()
+        .with_delay(Duration::from_secs(30))
+        .with_cancellation(token);
+
+    let start = Instant::now();
+    let result = mangle_with(&TranslatorReverseWords::new(), "one two", &path, &options);
+    canceller.join().unwrap();
+
+    assert!(start.elapsed() < Duration::from_secs(10));
+    match result {
+        Err(MangleError::Stopped { reason, text, hops }) => {
+            assert_eq!(reason, StopReason::Cancelled);
+            assert_eq!(text, "one two");
+            assert_eq!(hops, 0);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn deadline_stops_run() {
+    let path = get_random_lang_path("en", &["zh", "ja"], 3);
+    let options = MangleOptions::new().with_deadline(Instant::now());
+
+    match mangle_with(&TranslatorReverseWords::new(), "one two", &path, &options) {
+        Err(MangleError::Stopped { reason, hops, .. }) => {
+            assert_eq!(reason, StopReason::DeadlineExceeded);
+            assert_eq!(hops, 0);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn failures_are_not_stops() {
+    let translator = TranslatorScripted::new().then_ok("你好").then_err("boom");
+    let path = get_random_lang_path("en", &["zh", "ja"], 3);
+    let options = MangleOptions::new().with_timeout(Duration::from_secs(60));
+
+    match mangle_with(&translator, "Hello", &path, &options) {
+        Err(MangleError::HopFailed(failure)) => assert_eq!(failure.error.message, "boom"),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
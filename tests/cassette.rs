@@ -0,0 +1,108 @@
+use translator_mangler::cassette::{RecordingTranslator, ReplayMode, ReplayTranslator};
+use translator_mangler::testing::{TranslatorScripted, TranslatorShuffle};
+use translator_mangler::{
+    get_random_lang_path, mangle, mangle_lockstep, MangleOptions, TranslationDirection, Translator,
+};
+
+fn cassette_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!(
+        "translator_mangler_{}_{}.jsonl",
+        name,
+        std::process::id()
+    ))
+}
+
+#[test]
+fn replay_reproduces_recorded_mangle() {
+    let path = cassette_path("mangle");
+    let lang_path = get_random_lang_path("en", &["zh", "ja", "ko"], 8);
+    let text = "The quick brown fox jumps over the lazy dog";
+
+    let recorder = RecordingTranslator::create(TranslatorShuffle::new(7), &path).unwrap();
+    let recorded = mangle(&recorder, text, &lang_path, 0).unwrap();
+
+    for mode in [ReplayMode::Strict, ReplayMode::Lenient] {
+        let replay = ReplayTranslator::open(&path, mode).unwrap();
+        assert_eq!(mangle(&replay, text, &lang_path, 0).unwrap(), recorded);
+        assert_eq!(replay.remaining(), 0);
+    }
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn replay_errors_and_matching() {
+    let path = cassette_path("errors");
+    let en_zh = TranslationDirection {
+        from_lang: "en".to_string(),
+        to_lang: "zh".to_string(),
+    };
+    let zh_en = TranslationDirection {
+        from_lang: "zh".to_string(),
+        to_lang: "en".to_string(),
+    };
+
+    let scripted = TranslatorScripted::new()
+        .then_ok("你好")
+        .then_err("API ERR: 54003 Invalid Access Limit");
+    let recorder = RecordingTranslator::create(scripted, &path).unwrap();
+    recorder.translate("Hello", &en_zh).unwrap();
+    recorder.translate("你好", &zh_en).unwrap_err();
+
+    let strict = ReplayTranslator::open(&path, ReplayMode::Strict).unwrap();
+    assert!(strict.translate("你好", &zh_en).is_err());
+    assert!(strict.get_supported_langs().contains(&"zh"));
+    // Language codes are shared by every replay of the process.
+    let again = ReplayTranslator::open(&path, ReplayMode::Strict).unwrap();
+    for (first, second) in strict
+        .get_supported_langs()
+        .iter()
+        .zip(again.get_supported_langs())
+    {
+        assert!(std::ptr::eq(*first, *second));
+    }
+
+    let lenient = ReplayTranslator::open(&path, ReplayMode::Lenient).unwrap();
+    let error = lenient.translate("你好", &zh_en).unwrap_err();
+    assert_eq!(error.message, "API ERR: 54003 Invalid Access Limit");
+    assert_eq!(lenient.translate("Hello", &en_zh).unwrap(), "你好");
+    assert_eq!(lenient.translate("Hello", &en_zh).unwrap(), "你好");
+    assert!(lenient.translate("Goodbye", &en_zh).is_err());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn batches_are_recorded_per_text() {
+    let path = cassette_path("batch");
+    let lang_path = get_random_lang_path("en", &["zh", "ja"], 4);
+    let texts = ["the quick brown fox", "jumps over", "the lazy dog"];
+
+    let recorder = RecordingTranslator::create(TranslatorShuffle::new(3), &path).unwrap();
+    let recorded = mangle_lockstep(&recorder, &texts, &lang_path, &MangleOptions::new());
+
+    let replay = ReplayTranslator::open(&path, ReplayMode::Lenient).unwrap();
+    assert_eq!(replay.remaining(), 12);
+    for (text, recorded) in texts.iter().zip(recorded) {
+        assert_eq!(
+            mangle(&replay, text, &lang_path, 0).unwrap(),
+            recorded.unwrap()
+        );
+    }
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn translations_survive_failed_writes() {
+    // Every write to /dev/full fails with "no space left on device".
+    let recorder = RecordingTranslator::create(TranslatorShuffle::new(3), "/dev/full").unwrap();
+    let lang_path = get_random_lang_path("en", &["zh", "ja"], 4);
+    let text = "the quick brown fox";
+
+    assert_eq!(
+        mangle(&recorder, text, &lang_path, 0).unwrap(),
+        mangle(&TranslatorShuffle::new(3), text, &lang_path, 0).unwrap()
+    );
+}
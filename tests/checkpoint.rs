@@ -0,0 +1,341 @@
+use std::path::{Path, PathBuf};
+
+use translator_mangler::testing::{
+    TranslatorPigLatin, TranslatorReverseWords, TranslatorScripted, DEFAULT_LANGS,
+};
+use translator_mangler::{
+    get_seeded_lang_path, mangle_checkpointed, CancellationToken, Checkpoint, Execution,
+    MangleError, MangleJob, MangleObserver, MangleOptions, MixedHop, MixedJob, Routing,
+    TranslationDirection, TranslationError, TranslationErrorKind, Translator,
+};
+
+fn checkpoint_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!(
+        "translator_mangler_checkpoint_{}_{}.jsonl",
+        name,
+        std::process::id()
+    ))
+}
+
+fn jobs(texts: &[&str], rounds: usize) -> Vec<MangleJob> {
+    texts
+        .iter()
+        .map(|text| MangleJob {
+            text: text.to_string(),
+            path: get_seeded_lang_path("en", &["zh", "ja"], rounds, 42),
+        })
+        .collect()
+}
+
+/// Run every input of `checkpoint` one after the other with `translator`,
+/// up to the first error.
+fn sequential(
+    translator: &dyn Translator,
+    checkpoint: &mut Checkpoint,
+    path: &Path,
+    options: &MangleOptions,
+) -> Result<Vec<String>, MangleError> {
+    mangle_checkpointed(
+        Routing::Single(translator),
+        checkpoint,
+        path,
+        Execution::Sequential,
+        options,
+    )
+    .into_iter()
+    .collect()
+}
+
+/// A translator reversing words, failing on texts with `word` in them.
+struct FailOn(&'static str);
+
+impl Translator for FailOn {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        if text.contains(self.0) {
+            return Err(TranslationError {
+                kind: TranslationErrorKind::Server,
+                message: format!("no {}", self.0),
+            });
+        }
+        TranslatorReverseWords::new().translate(text, lang)
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        &DEFAULT_LANGS
+    }
+
+    fn is_lang_supported(&self, lang: &str) -> bool {
+        DEFAULT_LANGS.contains(&lang)
+    }
+}
+
+/// An observer checking that every hop before the current one is saved.
+struct CheckSaved(PathBuf);
+
+impl MangleObserver for CheckSaved {
+    fn on_step_start(&self, index: usize, _total: usize, _direction: &TranslationDirection) {
+        let checkpoint = Checkpoint::load(&self.0).unwrap();
+        assert_eq!(checkpoint.inputs[0].steps.len(), index);
+    }
+}
+
+#[test]
+fn progress_is_saved_after_every_hop() {
+    let path = checkpoint_path("every_hop");
+    let mut checkpoint = Checkpoint::new(&jobs(&["one two"], 5)).with_seed(42);
+    let options = MangleOptions::new().with_observer(CheckSaved(path.clone()));
+    let results = sequential(
+        &TranslatorReverseWords::new(),
+        &mut checkpoint,
+        &path,
+        &options,
+    )
+    .unwrap();
+
+    assert_eq!(results, ["two one"]);
+    let saved = Checkpoint::load(&path).unwrap();
+    assert_eq!(saved, checkpoint);
+    assert_eq!(saved.seed, Some(42));
+    assert!(saved.is_done());
+    assert_eq!(saved.inputs[0].trace().steps.len(), 5);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn failed_job_resumes_on_another_backend() {
+    let path = checkpoint_path("failed");
+    let scripted = TranslatorScripted::new()
+        .then_ok("first")
+        .then_ok("second")
+        .then_err("quota exhausted");
+    let mut checkpoint = Checkpoint::new(&jobs(&["one two"], 4));
+    let result = sequential(&scripted, &mut checkpoint, &path, &MangleOptions::new());
+    match result {
+        Err(MangleError::HopFailed(failure)) => {
+            assert_eq!(failure.error.message, "quota exhausted");
+            assert_eq!(failure.index, 2);
+            assert_eq!(failure.text(), "second");
+            assert_eq!(failure.remaining.len(), 2);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let mut checkpoint = Checkpoint::load(&path).unwrap();
+    assert_eq!(checkpoint.inputs[0].steps.len(), 2);
+    assert_eq!(checkpoint.inputs[0].text, "second");
+    let results = sequential(
+        &TranslatorPigLatin::new(),
+        &mut checkpoint,
+        &path,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    assert_eq!(results, ["econdsayway"]);
+    let backends = checkpoint.inputs[0]
+        .steps
+        .iter()
+        .map(|step| step.backend.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(backends, ["scripted", "scripted", "pig_latin", "pig_latin"]);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn stopped_job_resumes_where_it_stopped() {
+    let path = checkpoint_path("stopped");
+    let token = CancellationToken::new();
+    token.cancel();
+    let options = MangleOptions::new().with_cancellation(token);
+    let translator = TranslatorReverseWords::new();
+    let mut checkpoint = Checkpoint::new(&jobs(&["one two", "three four"], 2));
+    let result = sequential(&translator, &mut checkpoint, &path, &options);
+    assert!(matches!(result, Err(MangleError::Stopped { hops: 0, .. })));
+
+    let mut checkpoint = Checkpoint::load(&path).unwrap();
+    assert!(!checkpoint.is_done());
+    let results = sequential(&translator, &mut checkpoint, &path, &MangleOptions::new()).unwrap();
+    assert_eq!(results, ["one two", "three four"]);
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn completed_hops_are_not_sent_again() {
+    let path = checkpoint_path("completed");
+    let mut checkpoint = Checkpoint::new(&jobs(&["one two", "three four"], 3));
+    sequential(
+        &TranslatorReverseWords::new(),
+        &mut checkpoint,
+        &path,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    let scripted = TranslatorScripted::new();
+    let results = sequential(&scripted, &mut checkpoint, &path, &MangleOptions::new()).unwrap();
+    assert_eq!(results, ["two one", "four three"]);
+    assert!(scripted.calls().is_empty());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn mixed_jobs_keep_their_translators() {
+    let path = checkpoint_path("mixed");
+    let reverse = TranslatorReverseWords::new();
+    let pig_latin = TranslatorPigLatin::new();
+    let translators: [&dyn Translator; 2] = [&reverse, &pig_latin];
+    let job = MixedJob {
+        text: "one two".to_string(),
+        path: get_seeded_lang_path("en", &["zh"], 2, 42)
+            .into_iter()
+            .zip([1, 0])
+            .map(|(direction, translator)| MixedHop {
+                direction,
+                translator,
+            })
+            .collect(),
+    };
+    for execution in [
+        Execution::Sequential,
+        Execution::Pool(2),
+        Execution::Lockstep,
+    ] {
+        let mut checkpoint = Checkpoint::new_mixed(&[job.clone(), job.clone()]);
+        let results = mangle_checkpointed(
+            Routing::Mixed(&translators),
+            &mut checkpoint,
+            &path,
+            execution,
+            &MangleOptions::new(),
+        );
+        for result in results {
+            assert_eq!(result.unwrap(), "otway oneway", "{:?}", execution);
+        }
+    }
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn unwritable_checkpoint_fails() {
+    let path = std::env::temp_dir()
+        .join("translator_mangler_missing_dir")
+        .join("checkpoint.json");
+    let mut checkpoint = Checkpoint::new(&jobs(&["one two"], 2));
+    let result = sequential(
+        &TranslatorReverseWords::new(),
+        &mut checkpoint,
+        &path,
+        &MangleOptions::new(),
+    );
+
+    assert!(matches!(
+        result,
+        Err(MangleError::Failed(e)) if e.kind == TranslationErrorKind::Other
+    ));
+    assert!(checkpoint.inputs[0].steps.is_empty());
+}
+
+#[test]
+fn hops_are_appended_one_line_each() {
+    let path = checkpoint_path("lines");
+    let mut checkpoint = Checkpoint::new(&jobs(&["one two", "three four"], 3));
+    sequential(
+        &TranslatorReverseWords::new(),
+        &mut checkpoint,
+        &path,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(saved.lines().count(), 1 + 6);
+
+    // A hop cut short while appending is left out.
+    std::fs::write(&path, format!("{}{{\"input\":1,\"st", saved)).unwrap();
+    assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint);
+    let torn = saved.lines().take(6).collect::<Vec<&str>>().join("\n") + "\n{\"inp";
+    std::fs::write(&path, torn).unwrap();
+    let loaded = Checkpoint::load(&path).unwrap();
+    assert_eq!(loaded.inputs[1].steps.len(), 2);
+    assert_eq!(loaded.inputs[1].text, "three four");
+
+    let corrupt = "{\"inputs\":[]}\n{\"input\":0,\"step\":null}\n".repeat(2);
+    std::fs::write(&path, corrupt).unwrap();
+    assert!(Checkpoint::load(&path).is_err());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn batch_jobs_resume_failed_inputs() {
+    let path = checkpoint_path("batch");
+    let mut checkpoint = Checkpoint::new(&jobs(&["one two", "three four", "five six"], 3));
+    let results = mangle_checkpointed(
+        Routing::Single(&FailOn("three")),
+        &mut checkpoint,
+        &path,
+        Execution::Pool(2),
+        &MangleOptions::new(),
+    );
+    assert_eq!(results[0].as_ref().unwrap(), "two one");
+    assert!(matches!(&results[1], Err(MangleError::HopFailed(f)) if f.index == 0));
+    assert_eq!(results[2].as_ref().unwrap(), "six five");
+    assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint);
+
+    let mut checkpoint = Checkpoint::load(&path).unwrap();
+    let results = mangle_checkpointed(
+        Routing::Single(&TranslatorReverseWords::new()),
+        &mut checkpoint,
+        &path,
+        Execution::Pool(2),
+        &MangleOptions::new(),
+    );
+    assert_eq!(results[1].as_ref().unwrap(), "four three");
+    assert!(checkpoint.is_done());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn lockstep_jobs_resume_together() {
+    let path = checkpoint_path("lockstep");
+    let mut checkpoint = Checkpoint::new(&jobs(&["one two", "three four", "five six"], 3));
+    let results = mangle_checkpointed(
+        Routing::Single(&FailOn("four")),
+        &mut checkpoint,
+        &path,
+        Execution::Lockstep,
+        &MangleOptions::new(),
+    );
+    assert_eq!(results[0].as_ref().unwrap(), "two one");
+    match &results[1] {
+        Err(MangleError::HopFailed(failure)) => {
+            assert_eq!(failure.index, 0);
+            assert_eq!(failure.remaining.len(), 3);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint);
+
+    let mut checkpoint = Checkpoint::load(&path).unwrap();
+    let results = mangle_checkpointed(
+        Routing::Single(&TranslatorReverseWords::new()),
+        &mut checkpoint,
+        &path,
+        Execution::Lockstep,
+        &MangleOptions::new(),
+    );
+    assert_eq!(results[1].as_ref().unwrap(), "four three");
+    assert_eq!(results[2].as_ref().unwrap(), "six five");
+    assert_eq!(Checkpoint::load(&path).unwrap(), checkpoint);
+
+    std::fs::remove_file(path).unwrap();
+}
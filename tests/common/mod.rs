@@ -0,0 +1,82 @@
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+
+/// A minimal HTTP server answering every request with `handler(body)`.
+pub struct MockServer {
+    pub url: String,
+}
+
+impl MockServer {
+    /// Start a server on a random local port, serving from a background thread.
+    pub fn start<F>(handler: F) -> MockServer
+    where
+        F: Fn(&str) -> String + Send + 'static,
+    {
+        MockServer::start_with_status(move |body| (200, handler(body)))
+    }
+
+    /// Start a server like [`MockServer::start`], answering with the status
+    /// code chosen by `handler` along with the body.
+    pub fn start_with_status<F>(handler: F) -> MockServer
+    where
+        F: Fn(&str) -> (u16, String) + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("content-length")
+                    {
+                        content_length = value.trim().parse().unwrap_or(0);
+                    }
+                }
+                let mut body = vec![0; content_length];
+                if reader.read_exact(&mut body).is_err() {
+                    continue;
+                }
+
+                let (status, response) = handler(&String::from_utf8_lossy(&body));
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    response.len(),
+                    response
+                );
+            }
+        });
+
+        MockServer { url }
+    }
+}
+
+/// Get the value of `key` from a request body of `[key, value]` pairs.
+pub fn param(body: &str, key: &str) -> String {
+    let pairs: Vec<(String, String)> = serde_json::from_str(body).unwrap();
+    pairs
+        .into_iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v)
+        .unwrap_or_default()
+}
+
+/// A stand-in for translation that visibly changes a line.
+pub fn fake_translate_line(line: &str) -> String {
+    line.split_whitespace()
+        .rev()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
@@ -0,0 +1,60 @@
+use translator_mangler::credentials::{Backend, CredentialProvider, FileProvider};
+use translator_mangler::TranslatorBaidu;
+
+fn write_credentials(name: &str, contents: &str, mode: u32) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "translator_mangler_{}_{}",
+        name,
+        std::process::id()
+    ));
+    std::fs::write(&path, contents).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    path
+}
+
+#[test]
+fn debug_output_is_redacted() {
+    let translator = TranslatorBaidu::new("20150630000000001", "hunter2");
+    let debug = format!("{:?}", translator);
+    assert!(debug.contains("20150630000000001"));
+    assert!(!debug.contains("hunter2"));
+}
+
+#[test]
+fn file_provider_reads_credentials() {
+    let path = write_credentials(
+        "ok",
+        "# Baidu\nBAIDU_APP_ID=20150630000000001\nBAIDU_API_KEY=\"hunter2\"\n\nGOOGLE_CLOUD_API_KEY=abc\n",
+        0o600,
+    );
+    let provider = FileProvider::new(&path);
+
+    let baidu = provider.credentials(Backend::Baidu).unwrap().unwrap();
+    assert_eq!(baidu.id.as_deref(), Some("20150630000000001"));
+    assert_eq!(baidu.secret.expose(), "hunter2");
+    let google = provider.credentials(Backend::GoogleCloud).unwrap().unwrap();
+    assert_eq!(google.id, None);
+    assert_eq!(google.secret.expose(), "abc");
+    assert!(provider.credentials(Backend::Youdao).unwrap().is_none());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn file_provider_refuses_open_permissions() {
+    let path = write_credentials("open", "BAIDU_API_KEY=hunter2\n", 0o644);
+    let error = FileProvider::new(&path)
+        .credentials(Backend::Baidu)
+        .unwrap_err();
+    assert!(error.message.contains("chmod 600"));
+    assert!(!error.message.contains("hunter2"));
+
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use translator_mangler::strategy::{
+    lang_script, EdgeMetric, EdgeWeights, LanguageGraph, PathStrategy,
+};
+use translator_mangler::testing::TranslatorScripted;
+use translator_mangler::{
+    TranslationDirection, TranslationError, Translator, TranslatorBaidu, TranslatorYoudao,
+};
+
+fn weights(cost: f64, drift: f64) -> EdgeWeights {
+    EdgeWeights {
+        cost,
+        latency: Duration::ZERO,
+        drift,
+    }
+}
+
+/// en <-> fr <-> de <-> ja, with a cheap but drifting shortcut en -> ja.
+fn chain() -> LanguageGraph {
+    let mut graph = LanguageGraph::new();
+    for (a, b) in [("en", "fr"), ("fr", "de"), ("de", "ja")] {
+        graph = graph
+            .with_edge(a, b, weights(1.0, 1.0))
+            .with_edge(b, a, weights(1.0, 1.0));
+    }
+    graph.with_edge("en", "ja", weights(0.5, 5.0))
+}
+
+/// Baidu, as if it translated through English.
+struct ThroughEnglish(TranslatorBaidu);
+
+impl Translator for ThroughEnglish {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        self.0.translate(text, lang)
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        self.0.get_supported_langs()
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        self.0.is_lang_supported(single_lang)
+    }
+
+    fn pivot_lang(&self) -> Option<&str> {
+        Some("en")
+    }
+}
+
+fn stops(path: &[TranslationDirection]) -> Vec<&str> {
+    std::iter::once(path[0].from_lang.as_str())
+        .chain(path.iter().map(|hop| hop.to_lang.as_str()))
+        .collect()
+}
+
+#[test]
+fn graphs_follow_backend_pairs() {
+    let youdao = TranslatorYoudao::new("key", "secret");
+    let graph = LanguageGraph::from_translator(&youdao);
+    assert!(graph.langs().contains(&"zh"));
+    assert!(graph.edge("ja", "zh").is_some());
+    assert!(graph.edge("ja", "en").is_some());
+    assert!(graph.edge("ja", "ko").is_none());
+
+    let baidu = ThroughEnglish(TranslatorBaidu::new("id", "key"));
+    let both = LanguageGraph::from_translators(&[&youdao, &baidu]);
+    // Only Youdao translates ja -> zh without going through English.
+    assert_eq!(both.edge("ja", "zh").unwrap().drift, 1.0);
+    assert_eq!(both.edge("ja", "ko").unwrap().drift, 2.0);
+    assert_eq!(both.edge("en", "ko").unwrap().drift, 1.0);
+    assert!(both.edge("en", "wyw").is_some());
+}
+
+#[test]
+fn backends_declare_pairs_and_pivots() {
+    let youdao = TranslatorYoudao::new("key", "secret");
+    assert!(youdao.is_pair_supported("zh-CHT", "th"));
+    assert!(!youdao.is_pair_supported("fr", "de"));
+    assert!(!youdao.is_pair_supported("en", "en"));
+    assert_eq!(youdao.pivot_lang(), None);
+
+    let scripted = TranslatorScripted::new();
+    assert!(scripted.is_pair_supported("fr", "de"));
+    assert_eq!(TranslatorBaidu::new("id", "key").pivot_lang(), None);
+}
+
+#[test]
+fn shortest_and_longest_paths_follow_metrics() {
+    let graph = chain();
+    let by_hops = graph.shortest_path("en", "ja", EdgeMetric::Hops).unwrap();
+    assert_eq!(stops(&by_hops), ["en", "ja"]);
+    let by_drift = graph.shortest_path("en", "ja", EdgeMetric::Drift).unwrap();
+    assert_eq!(stops(&by_drift), ["en", "fr", "de", "ja"]);
+    assert!(graph
+        .shortest_path("ja", "ja", EdgeMetric::Cost)
+        .unwrap()
+        .is_empty());
+    assert!(graph.shortest_path("en", "ko", EdgeMetric::Hops).is_none());
+
+    let longest = graph
+        .longest_path("en", "en", 4, EdgeMetric::Drift)
+        .unwrap();
+    assert_eq!(longest.len(), 4);
+    assert_eq!(stops(&longest)[1], "ja");
+    assert!(graph
+        .longest_path("en", "de", 1, EdgeMetric::Drift)
+        .is_none());
+}
+
+#[test]
+fn random_walks_stay_on_edges() {
+    let graph = chain();
+    for seed in 0..30 {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let walk = graph.random_walk("en", "en", 8, &mut rng).unwrap();
+        assert_eq!(walk.len(), 8);
+        let stops = stops(&walk);
+        assert_eq!(stops[0], "en");
+        assert_eq!(stops[8], "en");
+        for hop in &walk {
+            assert!(graph.edge(&hop.from_lang, &hop.to_lang).is_some());
+        }
+        assert_eq!(
+            walk,
+            graph
+                .random_walk("en", "en", 8, &mut fastrand::Rng::with_seed(seed))
+                .unwrap()
+        );
+    }
+    // Every edge joins {en, de} to {fr, ja}, so walks back home are even.
+    assert!(graph
+        .random_walk("en", "en", 7, &mut fastrand::Rng::new())
+        .is_none());
+    // ja can only be left towards de, which is two hops away from en.
+    assert!(graph
+        .random_walk("ja", "en", 1, &mut fastrand::Rng::new())
+        .is_none());
+}
+
+#[test]
+fn walks_visit_distinct_scripts() {
+    let youdao = TranslatorYoudao::new("key", "secret");
+    let graph = LanguageGraph::from_translator(&youdao);
+    for seed in 0..10 {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let walk = graph
+            .distinct_scripts_walk("en", "en", 8, 5, &mut rng)
+            .unwrap();
+        let mut scripts = stops(&walk)
+            .into_iter()
+            .map(lang_script)
+            .collect::<Vec<&str>>();
+        scripts.sort();
+        scripts.dedup();
+        assert!(scripts.len() >= 5);
+    }
+    // Two hops from en and back visit at most two scripts.
+    assert!(graph
+        .distinct_scripts_walk("en", "en", 2, 3, &mut fastrand::Rng::new())
+        .is_none());
+}
+
+#[test]
+fn graphs_plan_within_the_bank() {
+    let graph = chain();
+    let mut rng = fastrand::Rng::with_seed(2);
+    let path = graph.path("en", &["fr", "de"], 6, &mut rng);
+    assert!(stops(&path)
+        .iter()
+        .all(|lang| ["en", "fr", "de"].contains(lang)));
+    for hop in &path {
+        assert!(graph.edge(&hop.from_lang, &hop.to_lang).is_some());
+    }
+}
@@ -0,0 +1,140 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use translator_mangler::layer::{
+    CacheLayer, LogLayer, MeterLayer, RetryLayer, TranslatorBuilder, TranslatorMiddleware, Usage,
+};
+use translator_mangler::testing::TranslatorScripted;
+use translator_mangler::{
+    TranslateOptions, TranslationDirection, TranslationError, TranslationErrorKind, Translator,
+};
+
+fn en_zh() -> TranslationDirection {
+    TranslationDirection {
+        from_lang: "en".to_string(),
+        to_lang: "zh".to_string(),
+    }
+}
+
+fn error(kind: TranslationErrorKind) -> TranslationError {
+    TranslationError {
+        kind,
+        message: format!("{:?}", kind),
+    }
+}
+
+#[test]
+fn retry_only_transient_errors() {
+    let backend = Arc::new(
+        TranslatorScripted::new()
+            .then_error(error(TranslationErrorKind::Network))
+            .then_error(error(TranslationErrorKind::RateLimited))
+            .then_ok("你好")
+            .then_error(error(TranslationErrorKind::Api))
+            .then_ok("unused"),
+    );
+    let translator = TranslatorBuilder::new()
+        .layer(RetryLayer::new(2).with_backoff(Duration::ZERO))
+        .build(backend.clone());
+
+    assert_eq!(translator.translate("Hello", &en_zh()).unwrap(), "你好");
+    assert_eq!(backend.calls().len(), 3);
+    let e = translator.translate("Hello", &en_zh()).unwrap_err();
+    assert_eq!(e.kind, TranslationErrorKind::Api);
+    assert_eq!(backend.calls().len(), 4);
+}
+
+#[test]
+fn outer_layers_see_calls_first() {
+    let backend = Arc::new(
+        TranslatorScripted::new()
+            .then_error(error(TranslationErrorKind::Server))
+            .then_ok("你好"),
+    );
+    let cache = CacheLayer::new();
+    let translator = TranslatorBuilder::new()
+        .layer(cache.clone())
+        .layer(RetryLayer::new(1).with_backoff(Duration::ZERO))
+        .build(backend.clone());
+
+    assert_eq!(translator.translate("Hello", &en_zh()).unwrap(), "你好");
+    // Answered by the cache without reaching the backend.
+    assert_eq!(translator.translate("Hello", &en_zh()).unwrap(), "你好");
+    assert_eq!(backend.calls().len(), 2);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(
+        translator.get_supported_langs(),
+        backend.get_supported_langs()
+    );
+}
+
+#[test]
+fn meters_count_what_reaches_them() {
+    let backend = Arc::new(
+        TranslatorScripted::new()
+            .then_error(error(TranslationErrorKind::Network))
+            .then_ok("你好")
+            .then_ok("世界")
+            .then_error(error(TranslationErrorKind::Api)),
+    );
+    let meter = MeterLayer::new();
+    let translator = TranslatorBuilder::new()
+        .layer(LogLayer::new())
+        .layer(CacheLayer::new())
+        .layer(RetryLayer::new(1).with_backoff(Duration::ZERO))
+        .layer(meter.clone())
+        .build(backend.clone());
+
+    assert_eq!(translator.translate("Hello", &en_zh()).unwrap(), "你好");
+    // Answered by the cache, so not metered.
+    assert_eq!(translator.translate("Hello", &en_zh()).unwrap(), "你好");
+    let results =
+        translator.translate_many(&["Hello", "World", "Bye"], &en_zh(), &Default::default());
+    assert_eq!(results[1].as_deref().unwrap(), "世界");
+    assert!(results[2].is_err());
+
+    // The first text was sent twice, and the batch without "Hello" once.
+    assert_eq!(
+        meter.take(),
+        Usage {
+            calls: 3,
+            texts: 4,
+            chars: 18,
+            failures: 2,
+        }
+    );
+    assert_eq!(meter.usage(), Usage::default());
+}
+
+/// Middleware noting the attempt number of every call going through it.
+#[derive(Clone, Default)]
+struct RecordAttempts(Arc<Mutex<Vec<u32>>>);
+
+impl TranslatorMiddleware for RecordAttempts {
+    fn translate(
+        &self,
+        inner: &dyn Translator,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        self.0.lock().unwrap().push(options.context.attempt());
+        inner.translate_with(text, lang, options)
+    }
+}
+
+#[test]
+fn retries_carry_their_attempt_number() {
+    let backend = TranslatorScripted::new()
+        .then_error(error(TranslationErrorKind::Network))
+        .then_error(error(TranslationErrorKind::Server))
+        .then_ok("你好");
+    let attempts = RecordAttempts::default();
+    let translator = TranslatorBuilder::new()
+        .layer(RetryLayer::new(2).with_backoff(Duration::ZERO))
+        .layer(attempts.clone())
+        .build(backend);
+
+    assert_eq!(translator.translate("Hello", &en_zh()).unwrap(), "你好");
+    assert_eq!(*attempts.0.lock().unwrap(), [1, 2, 3]);
+}
@@ -0,0 +1,222 @@
+mod common;
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use common::{fake_translate_line, param, MockServer};
+use translator_mangler::layer::{CacheLayer, RetryLayer, TranslatorBuilder};
+use translator_mangler::testing::TranslatorReverseWords;
+use translator_mangler::{
+    get_random_lang_path, mangle_lockstep, BatchLimits, CancellationToken, MangleError,
+    MangleOptions, TranslateOptions, TranslationDirection, TranslationError, TranslationErrorKind,
+    Translator, TranslatorBaidu,
+};
+
+/// A translator reversing words up to 3 texts per request, failing on texts
+/// with "fail" and once on texts with "flaky", and recording the size of
+/// every request.
+#[derive(Default)]
+struct TranslatorBatching {
+    batches: Mutex<Vec<usize>>,
+    flaky_seen: Mutex<HashSet<String>>,
+}
+
+impl TranslatorBatching {
+    fn batches(&self) -> Vec<usize> {
+        self.batches.lock().unwrap().clone()
+    }
+}
+
+impl Translator for TranslatorBatching {
+    fn translate(
+        &self,
+        text: &str,
+        lang: &TranslationDirection,
+    ) -> Result<String, TranslationError> {
+        self.translate_many(&[text], lang, &TranslateOptions::new())
+            .remove(0)
+    }
+
+    fn translate_many(
+        &self,
+        texts: &[&str],
+        lang: &TranslationDirection,
+        _options: &TranslateOptions,
+    ) -> Vec<Result<String, TranslationError>> {
+        self.batches.lock().unwrap().push(texts.len());
+        texts
+            .iter()
+            .map(|text| {
+                if text.contains("fail") {
+                    return Err(TranslationError {
+                        kind: TranslationErrorKind::Api,
+                        message: format!("refused {}", text),
+                    });
+                }
+                if text.contains("flaky")
+                    && self.flaky_seen.lock().unwrap().insert(text.to_string())
+                {
+                    return Err(TranslationError {
+                        kind: TranslationErrorKind::Network,
+                        message: format!("dropped {}", text),
+                    });
+                }
+                TranslatorReverseWords::new().translate(text, lang)
+            })
+            .collect()
+    }
+
+    fn batch_limits(&self) -> BatchLimits {
+        BatchLimits::new(3, 1000)
+    }
+
+    fn get_supported_langs(&self) -> &[&'static str] {
+        &["en", "zh", "ja"]
+    }
+
+    fn is_lang_supported(&self, single_lang: &str) -> bool {
+        self.get_supported_langs().contains(&single_lang)
+    }
+}
+
+#[test]
+fn texts_move_through_hops_together() {
+    let translator = TranslatorBatching::default();
+    let texts = (0..7)
+        .map(|index| format!("text {}", index))
+        .collect::<Vec<String>>();
+    let texts = texts.iter().map(String::as_str).collect::<Vec<&str>>();
+    let path = get_random_lang_path("en", &["zh", "ja"], 3);
+    let results = mangle_lockstep(&translator, &texts, &path, &MangleOptions::new());
+
+    for (index, result) in results.into_iter().enumerate() {
+        assert_eq!(result.unwrap(), format!("{} text", index));
+    }
+    // 3 hops of 7 texts, 3 at most per request.
+    assert_eq!(translator.batches(), [3, 3, 1, 3, 3, 1, 3, 3, 1]);
+}
+
+#[test]
+fn failed_texts_drop_out() {
+    let translator = TranslatorBatching::default();
+    let path = get_random_lang_path("en", &["zh", "ja"], 2);
+    let results = mangle_lockstep(
+        &translator,
+        &["one two", "please fail", "three four"],
+        &path,
+        &MangleOptions::new(),
+    );
+
+    assert_eq!(results[0].as_ref().unwrap(), "one two");
+    assert!(
+        matches!(&results[1], Err(MangleError::HopFailed(f)) if f.error.message.contains("fail"))
+    );
+    assert_eq!(results[2].as_ref().unwrap(), "three four");
+    assert_eq!(translator.batches(), [3, 2]);
+}
+
+#[test]
+fn retries_send_only_failed_texts_again() {
+    let backend = Arc::new(TranslatorBatching::default());
+    let translator = TranslatorBuilder::new()
+        .layer(RetryLayer::new(2).with_backoff(Duration::from_millis(1)))
+        .build(backend.clone());
+    let path = get_random_lang_path("en", &["zh", "ja"], 1);
+    let results = mangle_lockstep(
+        &translator,
+        &["one two", "flaky text", "three four"],
+        &path,
+        &MangleOptions::new(),
+    );
+
+    assert_eq!(results[1].as_ref().unwrap(), "text flaky");
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(backend.batches(), [3, 1]);
+}
+
+#[test]
+fn cached_texts_are_not_sent_again() {
+    let backend = Arc::new(TranslatorBatching::default());
+    let translator = TranslatorBuilder::new()
+        .layer(CacheLayer::new())
+        .build(backend.clone());
+    let path = get_random_lang_path("en", &["zh"], 2);
+    mangle_lockstep(&translator, &["a b", "c d"], &path, &MangleOptions::new());
+    let results = mangle_lockstep(&translator, &["a b", "e f"], &path, &MangleOptions::new());
+
+    assert_eq!(results[1].as_ref().unwrap(), "e f");
+    // Only "e f" and "f e" were missing from the cache the second time.
+    assert_eq!(backend.batches(), [2, 2, 1, 1]);
+}
+
+#[test]
+fn cancellation_stops_every_text() {
+    let token = CancellationToken::new();
+    token.cancel();
+    let options = MangleOptions::new().with_cancellation(token);
+    let translator = TranslatorBatching::default();
+    let path = get_random_lang_path("en", &["zh"], 2);
+    let results = mangle_lockstep(&translator, &["a b", "c d"], &path, &options);
+
+    assert!(matches!(
+        &results[1],
+        Err(MangleError::Stopped { text, hops: 0, .. }) if text == "c d"
+    ));
+    assert!(translator.batches().is_empty());
+}
+
+/// Answer like Baidu does, one node per non-blank line, counting requests.
+/// With `merge`, all lines come back as a single node instead.
+fn baidu_server(requests: Arc<AtomicUsize>, merge: bool) -> MockServer {
+    MockServer::start(move |body| {
+        requests.fetch_add(1, Ordering::SeqCst);
+        let lines = param(body, "q")
+            .split('\n')
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(fake_translate_line)
+            .collect::<Vec<String>>();
+        let lines = if merge { vec![lines.join(" ")] } else { lines };
+        let nodes = lines
+            .iter()
+            .map(|line| format!(r#"{{"src":"","dst":{:?}}}"#, line))
+            .collect::<Vec<String>>();
+        format!(r#"{{"trans_result":[{}]}}"#, nodes.join(","))
+    })
+}
+
+#[test]
+fn baidu_sends_many_texts_in_one_request() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server = baidu_server(requests.clone(), false);
+    let translator = TranslatorBaidu::new("id", "key").with_endpoint(&server.url);
+    let path = get_random_lang_path("en", &["jp", "kor"], 3);
+    let texts = ["one two", "three four\n\n  five six", "   ", "seven eight"];
+    let results = mangle_lockstep(&translator, &texts, &path, &MangleOptions::new());
+
+    assert_eq!(results[0].as_ref().unwrap(), "two one");
+    assert_eq!(results[1].as_ref().unwrap(), "four three\n\n  six five");
+    assert_eq!(results[2].as_ref().unwrap(), "   ");
+    assert_eq!(results[3].as_ref().unwrap(), "eight seven");
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn baidu_translates_texts_one_by_one_when_lines_merge() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let server = baidu_server(requests.clone(), true);
+    let translator = TranslatorBaidu::new("id", "key").with_endpoint(&server.url);
+    let lang = TranslationDirection {
+        from_lang: "en".to_string(),
+        to_lang: "zh".to_string(),
+    };
+    let results =
+        translator.translate_many(&["one two", "three four"], &lang, &TranslateOptions::new());
+
+    assert_eq!(results[0].as_ref().unwrap(), "two one");
+    assert_eq!(results[1].as_ref().unwrap(), "four three");
+    // The batch, then each text on its own.
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+}
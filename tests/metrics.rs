@@ -0,0 +1,78 @@
+use translator_mangler::metrics::{
+    bleu, char_edit_distance, chrf, token_jaccard, tokenize, word_edit_distance, Similarity,
+};
+use translator_mangler::testing::TranslatorScripted;
+use translator_mangler::{get_random_lang_path, mangle_with_trace, MangleOptions};
+
+#[test]
+fn tokens_split_cjk_characters() {
+    assert_eq!(
+        tokenize("我爱北京。 I love Beijing!"),
+        ["我", "爱", "北", "京", "。", "I", "love", "Beijing", "!"]
+    );
+    assert_eq!(
+        tokenize("日本語\u{3000}テキスト"),
+        ["日", "本", "語", "テ", "キ", "ス", "ト"]
+    );
+    assert_eq!(tokenize("안녕 세상"), ["안", "녕", "세", "상"]);
+    assert!(tokenize("  ").is_empty());
+}
+
+#[test]
+fn edit_distances() {
+    assert_eq!(char_edit_distance("", "abc"), 3);
+    assert_eq!(char_edit_distance("你好世界", "你好地球"), 2);
+    assert_eq!(word_edit_distance("the cat sat", "the dog sat down"), 2);
+    assert_eq!(word_edit_distance("我爱你", "我恨你"), 1);
+}
+
+#[test]
+fn identical_texts_score_one() {
+    for text in ["", "Hello, world!", "床前明月光，疑是地上霜。"] {
+        assert_eq!(chrf(text, text), 1.0);
+        assert_eq!(bleu(text, text), 1.0);
+        assert_eq!(token_jaccard(text, text), 1.0);
+        assert_eq!(Similarity::between(text, text).mangle_score(), 0.0);
+    }
+}
+
+#[test]
+fn unrelated_texts_score_zero() {
+    assert_eq!(chrf("xyz", "abc"), 0.0);
+    assert_eq!(bleu("xyz", "abc"), 0.0);
+    assert_eq!(token_jaccard("xyz", "abc"), 0.0);
+    assert_eq!(Similarity::between("abc", "xyz").mangle_score(), 100.0);
+    assert_eq!(bleu("", "abc"), 0.0);
+}
+
+#[test]
+fn scores_order_by_closeness() {
+    let original = "The quick brown fox jumps over the lazy dog.";
+    let close = "The quick brown fox leaps over the lazy dog.";
+    let far = "A fast fox hopped past a sleepy hound.";
+    for measure in [chrf, bleu, token_jaccard] {
+        let (close, far) = (measure(close, original), measure(far, original));
+        assert!(0.0 < far && far < close && close < 1.0);
+    }
+    assert!(
+        Similarity::between(original, close).mangle_score()
+            < Similarity::between(original, far).mangle_score()
+    );
+}
+
+#[test]
+fn traces_measure_every_step() {
+    let backend = TranslatorScripted::new()
+        .then_ok("你好，世界")
+        .then_ok("Hi, world")
+        .then_ok("Hi, earth");
+    let path = get_random_lang_path("en", &["zh"], 3);
+    let trace = mangle_with_trace(&backend, "Hello, world", &path, &MangleOptions::new()).unwrap();
+
+    let steps = trace.step_similarities();
+    assert_eq!(steps.len(), 3);
+    assert_eq!(steps[0].jaccard, 0.0);
+    assert_eq!(steps[1].word_distance, 1);
+    assert_eq!(steps[2], trace.similarity());
+    assert_eq!(trace.similarity().char_distance, 8);
+}
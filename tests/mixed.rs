@@ -0,0 +1,117 @@
+use translator_mangler::testing::TranslatorScripted;
+use translator_mangler::{
+    assign_translators, common_lang, mangle_mixed, mangle_mixed_with_trace, MangleError,
+    MangleOptions, MixedHop, TranslationDirection, TranslationErrorKind, Translator,
+    TranslatorBaidu, TranslatorGoogleCloud, TranslatorYoudao,
+};
+
+fn direction(from: &str, to: &str) -> TranslationDirection {
+    TranslationDirection {
+        from_lang: from.to_string(),
+        to_lang: to.to_string(),
+    }
+}
+
+fn hop(from: &str, to: &str, translator: usize) -> MixedHop {
+    MixedHop {
+        direction: direction(from, to),
+        translator,
+    }
+}
+
+#[test]
+fn language_codes_map_between_backends() {
+    let baidu = TranslatorBaidu::new("id", "key");
+    let google = TranslatorGoogleCloud::new("key");
+    let youdao = TranslatorYoudao::new("key", "secret");
+
+    assert_eq!(baidu.native_lang("ja"), "jp");
+    assert_eq!(baidu.native_lang("zh-CHT"), "cht");
+    assert_eq!(google.native_lang("zh"), "zh-CN");
+    assert_eq!(google.native_lang("kor"), "ko");
+    assert_eq!(youdao.native_lang("fra"), "fr");
+    assert_eq!(youdao.native_lang("en"), "en");
+    // Languages with no supported code are left alone.
+    assert_eq!(youdao.native_lang("wyw"), "wyw");
+
+    assert_eq!(common_lang("zh-CN"), "zh");
+    assert_eq!(common_lang("cht"), "zh-TW");
+    assert_eq!(common_lang("wyw"), "wyw");
+}
+
+#[test]
+fn hops_go_to_their_translator_in_its_codes() {
+    let first = TranslatorScripted::new()
+        .with_langs(&["en", "jp"])
+        .then_ok("こんにちは");
+    let second = TranslatorScripted::new()
+        .with_langs(&["en", "ja", "zh-CHS"])
+        .then_ok("你好")
+        .then_ok("Hi");
+    let translators: [&dyn Translator; 2] = [&first, &second];
+    let path = [hop("en", "ja", 0), hop("ja", "zh", 1), hop("zh", "en", 1)];
+
+    let trace =
+        mangle_mixed_with_trace(&translators, "Hello", &path, &MangleOptions::new()).unwrap();
+    assert_eq!(trace.result, "Hi");
+    assert_eq!(
+        first.calls(),
+        [("Hello".to_string(), direction("en", "jp"))]
+    );
+    assert_eq!(
+        second.calls(),
+        [
+            ("こんにちは".to_string(), direction("ja", "zh-CHS")),
+            ("你好".to_string(), direction("zh-CHS", "en")),
+        ]
+    );
+    // The trace keeps the languages of the path.
+    assert_eq!(trace.steps[1].direction, direction("ja", "zh"));
+}
+
+#[test]
+fn translators_are_assigned_by_support() {
+    let wide = TranslatorScripted::new().with_langs(&["en", "zh", "ja"]);
+    let narrow = TranslatorScripted::new().with_langs(&["en", "jp"]);
+    let translators: [&dyn Translator; 2] = [&wide, &narrow];
+    let path = [
+        direction("en", "ja"),
+        direction("ja", "zh"),
+        direction("zh", "en"),
+    ];
+
+    let mut seen = [false; 2];
+    for seed in 0..20 {
+        let hops =
+            assign_translators(&path, &translators, &mut fastrand::Rng::with_seed(seed)).unwrap();
+        assert_eq!(hops[1].translator, 0);
+        assert_eq!(hops[2].translator, 0);
+        seen[hops[0].translator] = true;
+    }
+    assert_eq!(seen, [true, true]);
+
+    let error = assign_translators(
+        &[direction("en", "wyw")],
+        &translators,
+        &mut fastrand::Rng::new(),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        error.kind,
+        TranslationErrorKind::UnsupportedLanguage
+    ));
+}
+
+#[test]
+fn missing_translators_are_errors() {
+    let only = TranslatorScripted::new().then_ok("你好");
+    let translators: [&dyn Translator; 1] = [&only];
+    let result = mangle_mixed(
+        &translators,
+        "Hello",
+        &[hop("en", "zh", 0), hop("zh", "en", 1)],
+        &MangleOptions::new(),
+    );
+    assert!(matches!(result, Err(MangleError::Failed(_))));
+    assert!(only.calls().is_empty());
+}
@@ -0,0 +1,185 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{fake_translate_line, param, MockServer};
+use translator_mangler::{
+    get_random_lang_path, mangle, TranslationDirection, Translator, TranslatorBaidu,
+    TranslatorYoudao,
+};
+
+const POEM: &str = "Shall I compare thee to a summer's day?
+Thou art more lovely and more temperate:
+
+    Rough winds do shake the darling buds of May,
+    And summer's lease hath all too short a date;
+
+So long lives this, and this gives life to thee.";
+
+const LIST: &str = "Groceries:
+- eggs
+- milk
+  - whole
+  - skimmed
+- bread
+";
+
+/// Answer like Baidu does: one node per non-blank line, indentation stripped.
+fn baidu_server() -> MockServer {
+    MockServer::start(|body| {
+        let nodes = param(body, "q")
+            .split('\n')
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| format!(r#"{{"src":"","dst":{:?}}}"#, fake_translate_line(line)))
+            .collect::<Vec<String>>();
+        format!(r#"{{"trans_result":[{}]}}"#, nodes.join(","))
+    })
+}
+
+/// Answer like Youdao does: a single translation with blank lines collapsed.
+fn youdao_server() -> MockServer {
+    MockServer::start(|body| {
+        let lines = param(body, "q")
+            .split('\n')
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .map(fake_translate_line)
+            .collect::<Vec<String>>();
+        format!(
+            r#"{{"errorCode":"0","translation":[{:?}]}}"#,
+            lines.join("\n")
+        )
+    })
+}
+
+fn assert_same_layout(original: &str, mangled: &str) {
+    let original_lines = original.split('\n').collect::<Vec<&str>>();
+    let mangled_lines = mangled.split('\n').collect::<Vec<&str>>();
+    assert_eq!(original_lines.len(), mangled_lines.len());
+    for (o, m) in original_lines.iter().zip(mangled_lines.iter()) {
+        assert_eq!(o.trim().is_empty(), m.trim().is_empty());
+        assert_eq!(
+            o.len() - o.trim_start().len(),
+            m.len() - m.trim_start().len()
+        );
+    }
+}
+
+#[test]
+fn baidu_keeps_all_lines() {
+    let server = baidu_server();
+    let translator = TranslatorBaidu::new("id", "key").with_endpoint(&server.url);
+    let lang = TranslationDirection {
+        from_lang: "en".to_string(),
+        to_lang: "zh".to_string(),
+    };
+
+    let result = translator
+        .translate("one two\n\n  three four", &lang)
+        .unwrap();
+    assert_eq!(result, "two one\n\n  four three");
+}
+
+#[test]
+fn youdao_keeps_all_lines() {
+    let server = youdao_server();
+    let translator = TranslatorYoudao::new("key", "secret").with_endpoint(&server.url);
+    let lang = TranslationDirection {
+        from_lang: "en".to_string(),
+        to_lang: "ja".to_string(),
+    };
+
+    let result = translator
+        .translate("one two\n\n  three four", &lang)
+        .unwrap();
+    assert_eq!(result, "two one\n\n  four three");
+}
+
+#[test]
+fn merged_lines_are_sent_one_by_one() {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    // Answer like Baidu does when it takes many lines for one sentence.
+    let server = {
+        let requests = requests.clone();
+        MockServer::start(move |body| {
+            let q = param(body, "q");
+            requests.lock().unwrap().push(q.clone());
+            let merged = q
+                .split('\n')
+                .map(fake_translate_line)
+                .collect::<Vec<String>>()
+                .join(" ");
+            format!(r#"{{"trans_result":[{{"src":"","dst":{:?}}}]}}"#, merged)
+        })
+    };
+    let translator = TranslatorBaidu::new("id", "key").with_endpoint(&server.url);
+    let lang = TranslationDirection {
+        from_lang: "en".to_string(),
+        to_lang: "zh".to_string(),
+    };
+
+    let result = translator
+        .translate("one two\n\n  three four", &lang)
+        .unwrap();
+    assert_eq!(result, "two one\n\n  four three");
+    assert_eq!(
+        *requests.lock().unwrap(),
+        ["one two\nthree four", "one two", "three four"]
+    );
+}
+
+#[test]
+fn baidu_mangle_preserves_poem_and_list() {
+    let server = baidu_server();
+    let translator = TranslatorBaidu::new("id", "key").with_endpoint(&server.url);
+    let path = get_random_lang_path("en", &["zh", "jp", "kor", "fra"], 20);
+
+    for text in [POEM, LIST] {
+        let mangled = mangle(&translator, text, &path, 0).unwrap();
+        assert_same_layout(text, &mangled);
+    }
+}
+
+#[test]
+fn youdao_mangle_preserves_poem_and_list() {
+    let server = youdao_server();
+    let translator = TranslatorYoudao::new("key", "secret").with_endpoint(&server.url);
+    let path = get_random_lang_path("en", &["zh-CHS", "ja", "ko", "fr"], 20);
+
+    for text in [POEM, LIST] {
+        let mangled = mangle(&translator, text, &path, 0).unwrap();
+        assert_same_layout(text, &mangled);
+    }
+}
+
+#[test]
+fn baidu_batches_stay_under_6000_bytes() {
+    let sizes = Arc::new(Mutex::new(Vec::new()));
+    let server = {
+        let sizes = sizes.clone();
+        MockServer::start(move |body| {
+            let q = param(body, "q");
+            sizes.lock().unwrap().push(q.len());
+            let nodes = q
+                .split('\n')
+                .map(|line| format!(r#"{{"src":"","dst":{:?}}}"#, line))
+                .collect::<Vec<String>>();
+            format!(r#"{{"trans_result":[{}]}}"#, nodes.join(","))
+        })
+    };
+    let translator = TranslatorBaidu::new("id", "key").with_endpoint(&server.url);
+    let lang = TranslationDirection {
+        from_lang: "zh".to_string(),
+        to_lang: "en".to_string(),
+    };
+
+    // 3000 bytes each: two of them and the newline between make 6001.
+    let chinese = "中".repeat(1000);
+    let english = "a".repeat(1000);
+    let texts = [chinese.as_str(), chinese.as_str(), &english, &english];
+    let results = translator.translate_many(&texts, &lang, &Default::default());
+    assert!(results.iter().all(Result::is_ok));
+
+    assert_eq!(*sizes.lock().unwrap(), [3000, 3000 + 1 + 1000 + 1 + 1000]);
+}
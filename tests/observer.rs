@@ -0,0 +1,149 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use translator_mangler::layer::{RetryLayer, TranslatorBuilder, TranslatorMiddleware};
+use translator_mangler::testing::TranslatorScripted;
+use translator_mangler::{
+    get_random_lang_path, mangle_with, MangleObserver, MangleOptions, MangleStep, TranslateOptions,
+    TranslationDirection, TranslationError, TranslationErrorKind, Translator,
+};
+
+/// An observer writing down every callback.
+#[derive(Default)]
+struct Log {
+    events: Mutex<Vec<String>>,
+}
+
+impl Log {
+    fn push(&self, event: String) {
+        self.events.lock().unwrap().push(event);
+    }
+}
+
+impl MangleObserver for Log {
+    fn on_step_start(&self, index: usize, total: usize, direction: &TranslationDirection) {
+        self.push(format!(
+            "start {}/{} {}->{}",
+            index, total, direction.from_lang, direction.to_lang
+        ));
+    }
+
+    fn on_step_done(&self, index: usize, total: usize, step: &MangleStep) {
+        self.push(format!("done {}/{} {}", index, total, step.output));
+    }
+
+    fn on_retry(&self, index: usize, retry: u32, error: &TranslationError) {
+        self.push(format!("retry {} #{} {}", index, retry, error.message));
+    }
+
+    fn on_error(&self, index: usize, error: &TranslationError) {
+        self.push(format!("error {} {}", index, error.message));
+    }
+}
+
+#[test]
+fn observer_sees_every_hop() {
+    let backend = TranslatorScripted::new()
+        .then_ok("你好")
+        .then_error(TranslationError {
+            kind: TranslationErrorKind::Server,
+            message: "busy".to_string(),
+        })
+        .then_ok("Hi")
+        .then_err("boom");
+    let translator = TranslatorBuilder::new()
+        .layer(RetryLayer::new(1).with_backoff(Duration::ZERO))
+        .build(backend);
+    let path = vec![
+        TranslationDirection {
+            from_lang: "en".to_string(),
+            to_lang: "zh".to_string(),
+        },
+        TranslationDirection {
+            from_lang: "zh".to_string(),
+            to_lang: "en".to_string(),
+        },
+        TranslationDirection {
+            from_lang: "en".to_string(),
+            to_lang: "zh".to_string(),
+        },
+    ];
+    let log = Arc::new(Log::default());
+    let options = MangleOptions::new().with_shared_observer(log.clone());
+
+    assert!(mangle_with(translator.as_ref(), "Hello", &path, &options).is_err());
+    assert_eq!(
+        *log.events.lock().unwrap(),
+        [
+            "start 0/3 en->zh",
+            "done 0/3 你好",
+            "start 1/3 zh->en",
+            "retry 1 #1 busy",
+            "done 1/3 Hi",
+            "start 2/3 en->zh",
+            "error 2 boom",
+        ]
+    );
+}
+
+#[test]
+fn retries_outside_runs_are_not_observed() {
+    let backend = TranslatorScripted::new().then_ok("你好").then_ok("Hello");
+    let log = Arc::new(Log::default());
+    let path = get_random_lang_path("en", &["zh"], 2);
+    let options = MangleOptions::new().with_shared_observer(log.clone());
+
+    mangle_with(&backend, "Hello", &path, &options).unwrap();
+    let events = log.events.lock().unwrap().len();
+
+    let retried = TranslatorBuilder::new()
+        .layer(RetryLayer::new(1).with_backoff(Duration::ZERO))
+        .build(TranslatorScripted::new().then_error(TranslationError {
+            kind: TranslationErrorKind::Network,
+            message: "reset".to_string(),
+        }));
+    let _ = retried.translate("Hello", &path[0]);
+    assert_eq!(log.events.lock().unwrap().len(), events);
+}
+
+/// Middleware retrying every failed call once from a thread of its own.
+#[derive(Clone)]
+struct RetryOnThread;
+
+impl TranslatorMiddleware for RetryOnThread {
+    fn translate(
+        &self,
+        inner: &dyn Translator,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        inner.translate_with(text, lang, options).or_else(|e| {
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| {
+                        options.context.notify_retry(1, &e);
+                        inner.translate_with(text, lang, options)
+                    })
+                    .join()
+                    .unwrap()
+            })
+        })
+    }
+}
+
+#[test]
+fn retries_on_other_threads_are_observed() {
+    let translator = TranslatorBuilder::new()
+        .layer(RetryOnThread)
+        .build(TranslatorScripted::new().then_err("reset").then_ok("你好"));
+    let log = Arc::new(Log::default());
+    let path = get_random_lang_path("en", &["zh"], 1);
+    let options = MangleOptions::new().with_shared_observer(log.clone());
+
+    mangle_with(translator.as_ref(), "Hello", &path, &options).unwrap();
+    assert_eq!(
+        *log.events.lock().unwrap(),
+        ["start 0/1 en->en", "retry 0 #1 reset", "done 0/1 你好"]
+    );
+}
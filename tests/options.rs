@@ -0,0 +1,123 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::{param, MockServer};
+use translator_mangler::cassette::{CassetteEntry, ReplayMode, ReplayTranslator};
+use translator_mangler::layer::{CacheLayer, TranslatorBuilder};
+use translator_mangler::signing::{baidu_field_sign, baidu_sign};
+use translator_mangler::testing::TranslatorScripted;
+use translator_mangler::{
+    TranslateOption, TranslateOptions, TranslationDirection, Translator, TranslatorBaidu,
+    TranslatorYoudao,
+};
+
+fn en_zh() -> TranslationDirection {
+    TranslationDirection {
+        from_lang: "en".to_string(),
+        to_lang: "zh".to_string(),
+    }
+}
+
+#[test]
+fn baidu_domain_uses_field_translation() {
+    let general = MockServer::start(|body| {
+        let signed = param(body, "sign") == baidu_sign("id", "Hello", &param(body, "salt"), "key");
+        format!(r#"{{"trans_result":[{{"dst":"general {}"}}]}}"#, signed)
+    });
+    let field = MockServer::start(|body| {
+        let salt = param(body, "salt");
+        let signed =
+            param(body, "sign") == baidu_field_sign("id", "Hello", &salt, "medicine", "key");
+        format!(
+            r#"{{"trans_result":[{{"dst":"{} {}"}}]}}"#,
+            param(body, "domain"),
+            signed
+        )
+    });
+    let translator = TranslatorBaidu::new("id", "key")
+        .with_endpoint(&general.url)
+        .with_field_endpoint(&field.url);
+
+    assert_eq!(translator.supported_options(), [TranslateOption::Domain]);
+    let options = TranslateOptions::new().with_domain("medicine");
+    assert_eq!(
+        translator
+            .translate_with("Hello", &en_zh(), &options)
+            .unwrap(),
+        "medicine true"
+    );
+    assert_eq!(
+        translator.translate("Hello", &en_zh()).unwrap(),
+        "general true"
+    );
+}
+
+#[test]
+fn youdao_glossary_is_vocab_id() {
+    let server = MockServer::start(|body| {
+        format!(
+            r#"{{"errorCode":"0","translation":["{}"]}}"#,
+            param(body, "vocabId")
+        )
+    });
+    let translator = TranslatorYoudao::new("key", "secret").with_endpoint(&server.url);
+    let options = TranslateOptions::new().with_glossary("vocab-42");
+    let en_zh = TranslationDirection {
+        from_lang: "en".to_string(),
+        to_lang: "zh-CHS".to_string(),
+    };
+
+    assert_eq!(
+        translator
+            .translate_with("Hello", &en_zh, &options)
+            .unwrap(),
+        "vocab-42"
+    );
+    assert_eq!(translator.translate("Hello", &en_zh).unwrap(), "");
+}
+
+#[test]
+fn cache_keys_include_options() {
+    let backend = Arc::new(TranslatorScripted::new().then_ok("base").then_ok("nmt"));
+    let translator = TranslatorBuilder::new()
+        .layer(CacheLayer::new())
+        .build(backend.clone());
+    let nmt = TranslateOptions::new().with_model("nmt");
+
+    assert_eq!(translator.translate("Hello", &en_zh()).unwrap(), "base");
+    assert_eq!(
+        translator.translate_with("Hello", &en_zh(), &nmt).unwrap(),
+        "nmt"
+    );
+    assert_eq!(translator.translate("Hello", &en_zh()).unwrap(), "base");
+    assert_eq!(backend.calls().len(), 2);
+}
+
+#[test]
+fn replay_matches_options() {
+    let entry = |options: TranslateOptions, output: &str| CassetteEntry {
+        direction: en_zh(),
+        input: "Hello".to_string(),
+        options,
+        output: Some(output.to_string()),
+        error: None,
+        error_kind: None,
+    };
+    let nmt = TranslateOptions::new().with_model("nmt");
+    let replay = ReplayTranslator::from_entries(
+        vec![
+            entry(TranslateOptions::new(), "base"),
+            entry(nmt.clone(), "nmt"),
+        ],
+        ReplayMode::Lenient,
+    );
+
+    assert_eq!(
+        replay.translate_with("Hello", &en_zh(), &nmt).unwrap(),
+        "nmt"
+    );
+    assert_eq!(replay.translate("Hello", &en_zh()).unwrap(), "base");
+    let domain = TranslateOptions::new().with_domain("medicine");
+    assert!(replay.translate_with("Hello", &en_zh(), &domain).is_err());
+}
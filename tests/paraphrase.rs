@@ -0,0 +1,126 @@
+use translator_mangler::testing::{TranslatorReverseWords, TranslatorScripted};
+use translator_mangler::{
+    paraphrase, MangleError, MangleOptions, ParaphraseOptions, TranslationError,
+    TranslationErrorKind,
+};
+
+const TEXT: &str = "The quick brown fox jumps over the lazy dog.";
+
+#[test]
+fn pivots_of_other_scripts_come_first() {
+    let translator = TranslatorScripted::new()
+        .then_ok("")
+        .then_ok("The quick brown fox leaps over the lazy dog.")
+        .then_ok("")
+        .then_ok("The fast brown fox jumps over the lazy dog.")
+        .then_ok("")
+        .then_ok("The quick brown fox jumps over the idle dog.");
+    let options = ParaphraseOptions::new("en", &["fr", "de", "en", "zh", "ja"]).with_count(3);
+    let paraphrases = paraphrase(&translator, TEXT, &options, &MangleOptions::new()).unwrap();
+
+    let pivots = paraphrases
+        .iter()
+        .map(|paraphrase| paraphrase.pivot.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(pivots, ["fr", "zh", "ja"]);
+    let calls = translator.calls();
+    assert_eq!(calls[0].1.to_lang, "fr");
+    assert_eq!(calls[1].1.from_lang, "fr");
+    assert_eq!(calls[1].1.to_lang, "en");
+    assert!(paraphrases
+        .iter()
+        .all(|paraphrase| paraphrase.similarity < 1.0));
+}
+
+#[test]
+fn unwanted_outputs_are_dropped() {
+    let translator = TranslatorScripted::new()
+        .with_langs(&["en", "fr", "de", "es", "it", "pt"])
+        // The text itself.
+        .then_ok("")
+        .then_ok(TEXT)
+        // Too far from the text.
+        .then_ok("")
+        .then_ok("Nothing at all.")
+        // Kept.
+        .then_ok("")
+        .then_ok("The quick brown fox leaps over the lazy dog.")
+        // A near-duplicate of the one kept.
+        .then_ok("")
+        .then_ok("The quick brown fox leaps over the lazy dog!")
+        // Kept.
+        .then_ok("")
+        .then_ok("A quick brown fox jumps over a lazy dog.");
+    let options = ParaphraseOptions::new("en", &["fr", "de", "es", "it", "pt"])
+        .with_similarity_floor(0.5)
+        .with_duplicate_threshold(0.9);
+    let paraphrases = paraphrase(&translator, TEXT, &options, &MangleOptions::new()).unwrap();
+
+    let texts = paraphrases
+        .iter()
+        .map(|paraphrase| paraphrase.text.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(
+        texts,
+        [
+            "The quick brown fox leaps over the lazy dog.",
+            "A quick brown fox jumps over a lazy dog.",
+        ]
+    );
+    assert_eq!(paraphrases[1].pivot, "pt");
+}
+
+#[test]
+fn stops_at_the_wanted_count() {
+    let translator = TranslatorScripted::new()
+        .then_ok("")
+        .then_ok("The quick brown fox leaps over the lazy dog.")
+        .then_ok("")
+        .then_ok("A quick brown fox jumps over a lazy dog.");
+    let options = ParaphraseOptions::new("en", &["fr", "de", "es"]).with_count(1);
+    let paraphrases = paraphrase(&translator, TEXT, &options, &MangleOptions::new()).unwrap();
+
+    assert_eq!(paraphrases.len(), 1);
+    assert_eq!(translator.remaining(), 2);
+}
+
+#[test]
+fn unsupported_pivots_are_skipped() {
+    let translator = TranslatorReverseWords::new().with_langs(&["en", "fr"]);
+    let options = ParaphraseOptions::new("en", &["de", "fr"]).with_similarity_floor(0.0);
+    let paraphrases = paraphrase(&translator, "one two", &options, &MangleOptions::new()).unwrap();
+    // Reversing twice gives the text back, which is dropped.
+    assert!(paraphrases.is_empty());
+
+    let translator = TranslatorScripted::new()
+        .then_error(TranslationError {
+            kind: TranslationErrorKind::UnsupportedLanguage,
+            message: "no German".to_string(),
+        })
+        .then_ok("")
+        .then_ok("The quick brown fox leaps over the lazy dog.");
+    let options = ParaphraseOptions::new("en", &["de", "fr"]);
+    let paraphrases = paraphrase(&translator, TEXT, &options, &MangleOptions::new()).unwrap();
+    assert_eq!(paraphrases.len(), 1);
+    assert_eq!(paraphrases[0].pivot, "fr");
+}
+
+#[test]
+fn failing_pivots_are_skipped() {
+    let translator = TranslatorScripted::new()
+        .then_err("boom")
+        .then_ok("")
+        .then_ok("The quick brown fox leaps over the lazy dog.");
+    let options = ParaphraseOptions::new("en", &["de", "fr"]);
+    let paraphrases = paraphrase(&translator, TEXT, &options, &MangleOptions::new()).unwrap();
+    assert_eq!(paraphrases.len(), 1);
+    assert_eq!(paraphrases[0].pivot, "fr");
+}
+
+#[test]
+fn failures_are_returned_when_nothing_was_found() {
+    let translator = TranslatorScripted::new().then_err("boom").then_err("bang");
+    let options = ParaphraseOptions::new("en", &["de", "fr"]);
+    let result = paraphrase(&translator, TEXT, &options, &MangleOptions::new());
+    assert!(matches!(result, Err(MangleError::HopFailed(f)) if f.error.message.contains("boom")));
+}
@@ -0,0 +1,394 @@
+use translator_mangler::strategy::{
+    lang_distance, CycleBank, MaxDistance, NoRepeatWindow, PathExpr, PathPlan, PathStrategy,
+    RoundTrip, UniformRandom,
+};
+use translator_mangler::testing::TranslatorShuffle;
+use translator_mangler::{
+    get_lang_path_with_rng, get_seeded_lang_path, mangle, TranslationDirection, TranslatorBaidu,
+};
+
+const BANK: [&str; 6] = ["zh", "ja", "ko", "fr", "de", "es"];
+
+#[test]
+fn seeded_paths_are_reproducible() {
+    let path = get_seeded_lang_path("en", &BANK, 12, 1234);
+    assert_eq!(path, get_seeded_lang_path("en", &BANK, 12, 1234));
+    assert_eq!(
+        path,
+        get_lang_path_with_rng("en", &BANK, 12, &mut fastrand::Rng::with_seed(1234))
+    );
+
+    // Different seeds almost surely choose differently over 11 random hops.
+    assert_ne!(path, get_seeded_lang_path("en", &BANK, 12, 4321));
+}
+
+#[test]
+fn seeded_paths_are_well_formed() {
+    for seed in 0..50 {
+        let path = get_seeded_lang_path("en", &BANK, 8, seed);
+        assert_eq!(path.len(), 8);
+        assert_eq!(path[0].from_lang, "en");
+        assert_eq!(path[7].to_lang, "en");
+        for pair in path.windows(2) {
+            assert_eq!(pair[0].to_lang, pair[1].from_lang);
+        }
+        for hop in &path[..7] {
+            assert_ne!(hop.from_lang, hop.to_lang);
+        }
+    }
+}
+
+#[test]
+fn seed_text_and_backend_determine_output() {
+    let text = "The quick brown fox jumps over the lazy dog";
+    let run = || {
+        let path = get_seeded_lang_path("en", &BANK, 10, 99);
+        mangle(&TranslatorShuffle::new(7), text, &path, 0).unwrap()
+    };
+    assert_eq!(run(), run());
+}
+
+/// Get the languages visited by `path`, including the first and last.
+fn stops(path: &[TranslationDirection]) -> Vec<&str> {
+    std::iter::once(path[0].from_lang.as_str())
+        .chain(path.iter().map(|hop| hop.to_lang.as_str()))
+        .collect()
+}
+
+#[test]
+fn strategies_give_well_formed_paths() {
+    let strategies: [Box<dyn PathStrategy>; 5] = [
+        Box::new(UniformRandom),
+        Box::new(NoRepeatWindow::new(3)),
+        Box::new(RoundTrip),
+        Box::new(CycleBank),
+        Box::new(MaxDistance),
+    ];
+    for strategy in &strategies {
+        for (seed, rounds) in (0..20).zip([1, 2, 3, 7, 10].into_iter().cycle()) {
+            let mut rng = fastrand::Rng::with_seed(seed);
+            let path = strategy.path("en", &BANK, rounds, &mut rng);
+            assert_eq!(path.len(), rounds);
+            let stops = stops(&path);
+            assert_eq!(stops[0], "en");
+            assert_eq!(stops[rounds], "en");
+            for pair in path.windows(2) {
+                assert_eq!(pair[0].to_lang, pair[1].from_lang);
+            }
+            if rounds > 1 {
+                assert!(path.iter().all(|hop| hop.from_lang != hop.to_lang));
+            }
+        }
+    }
+}
+
+#[test]
+fn banks_with_the_original_language_end_away_from_it() {
+    let strategies: [Box<dyn PathStrategy>; 4] = [
+        Box::new(NoRepeatWindow::new(3)),
+        Box::new(RoundTrip),
+        Box::new(CycleBank),
+        Box::new(MaxDistance),
+    ];
+    for strategy in &strategies {
+        for bank in [&["en", "zh", "ja"][..], &["en", "zh", "ja", "ko"]] {
+            for (seed, rounds) in (0..20).zip([2, 3, 5, 8].into_iter().cycle()) {
+                let mut rng = fastrand::Rng::with_seed(seed);
+                let path = strategy.path("en", bank, rounds, &mut rng);
+                assert!(path.iter().all(|hop| hop.from_lang != hop.to_lang));
+            }
+        }
+    }
+}
+
+#[test]
+fn no_repeat_window_with_the_original_language_ends_away_from_it() {
+    for seed in 0..20 {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let path = NoRepeatWindow::new(3).path("en", &["en", "zh", "ja", "ko"], 5, &mut rng);
+        assert_ne!(path[4].from_lang, "en");
+    }
+}
+
+#[test]
+fn uniform_random_is_the_default() {
+    let mut rng = fastrand::Rng::with_seed(5);
+    assert_eq!(
+        UniformRandom.path("en", &BANK, 9, &mut rng),
+        get_seeded_lang_path("en", &BANK, 9, 5)
+    );
+}
+
+#[test]
+fn no_repeat_window_avoids_recent_languages() {
+    let mut rng = fastrand::Rng::with_seed(11);
+    let path = NoRepeatWindow::new(4).path("en", &BANK, 30, &mut rng);
+    let stops = stops(&path);
+    for (i, lang) in stops.iter().enumerate().take(30).skip(1) {
+        assert!(!stops[i.saturating_sub(4)..i].contains(lang));
+    }
+}
+
+#[test]
+fn round_trips_return_home() {
+    let mut rng = fastrand::Rng::with_seed(3);
+    let even = stops(&RoundTrip.path("en", &BANK, 6, &mut rng)).join(" ");
+    let odd = RoundTrip.path("en", &BANK, 5, &mut rng);
+    let odd = stops(&odd);
+
+    assert_eq!(even.matches("en").count(), 4);
+    assert_eq!(odd[2], "en");
+    assert_ne!(odd[3], "en");
+    assert_ne!(odd[4], "en");
+    assert_eq!(odd[5], "en");
+}
+
+#[test]
+fn cycle_bank_visits_all_before_repeating() {
+    let mut rng = fastrand::Rng::with_seed(17);
+    let path = CycleBank.path("en", &BANK, 13, &mut rng);
+    let stops = stops(&path);
+    for cycle in [&stops[1..7], &stops[7..13]] {
+        let mut langs = cycle.to_vec();
+        langs.sort();
+        let mut bank = BANK.to_vec();
+        bank.sort();
+        assert_eq!(langs, bank);
+    }
+}
+
+#[test]
+fn cycle_bank_alternates_a_single_language_with_the_original() {
+    let path = CycleBank.path("en", &["ja"], 4, &mut fastrand::Rng::with_seed(3));
+    assert_eq!(stops(&path), ["en", "ja", "en", "ja", "en"]);
+}
+
+#[test]
+fn max_distance_jumps_between_families() {
+    let mut rng = fastrand::Rng::with_seed(23);
+    let bank = ["fr", "es", "de", "ja", "zh"];
+    let path = MaxDistance.path("fr", &bank, 10, &mut rng);
+    for hop in &path[..9] {
+        assert_eq!(lang_distance(&hop.from_lang, &hop.to_lang), 3);
+    }
+}
+
+#[test]
+fn distances_use_backend_codes() {
+    assert_eq!(lang_distance("jv", "id"), 1);
+    assert_eq!(lang_distance("mk", "bg"), 1);
+    assert_eq!(lang_distance("mk", "en"), 2);
+}
+
+#[test]
+fn path_expressions_expand_groups() {
+    let expr: PathExpr = "en > (ja > ko){3} > en".parse().unwrap();
+    let path = expr.resolve(&[], &mut fastrand::Rng::new()).unwrap();
+    assert_eq!(
+        stops(&path),
+        ["en", "ja", "ko", "ja", "ko", "ja", "ko", "en"]
+    );
+
+    let nested = PathExpr::parse("en→((fr>de){2}>es){2}→en").unwrap();
+    let path = nested.resolve(&[], &mut fastrand::Rng::new()).unwrap();
+    assert_eq!(
+        stops(&path).join(" "),
+        "en fr de fr de es fr de fr de es en"
+    );
+}
+
+#[test]
+fn path_expression_wildcards_are_seeded() {
+    let expr = PathExpr::parse("en > ja > * > ar > *{3} > *[fr, de] > en").unwrap();
+    for seed in 0..30 {
+        let path = expr
+            .resolve(&BANK, &mut fastrand::Rng::with_seed(seed))
+            .unwrap();
+        assert_eq!(
+            path,
+            expr.resolve(&BANK, &mut fastrand::Rng::with_seed(seed))
+                .unwrap()
+        );
+        let stops = stops(&path);
+        assert_eq!(stops.len(), 9);
+        assert_eq!(stops[..2], ["en", "ja"]);
+        assert_eq!(stops[3], "ar");
+        assert!(BANK.contains(&stops[2]) && !["ja", "ar"].contains(&stops[2]));
+        assert!(stops[4..7].iter().all(|lang| BANK.contains(lang)));
+        assert!(["fr", "de"].contains(&stops[7]));
+        for hop in &path {
+            assert_ne!(hop.from_lang, hop.to_lang);
+        }
+    }
+}
+
+#[test]
+fn path_expression_errors() {
+    for (source, message) in [
+        ("en", "the path must visit at least two languages"),
+        (
+            "en > ",
+            "expected a language but the expression ended at column 6",
+        ),
+        (
+            "en > (ja > ko",
+            "expected ')' but the expression ended at column 14",
+        ),
+        ("en > ja{0} > en", "repeating 0 times at column 9"),
+        (
+            "en > ja{x} > en",
+            "expected a number of repetitions but found 'x' at column 9",
+        ),
+        (
+            "en > *[] > en",
+            "expected a language but found ']' at column 8",
+        ),
+        ("en ja", "unexpected 'j' at column 4"),
+        (
+            "en > ja > ja",
+            "'ja' is visited twice in a row at column 11",
+        ),
+        (
+            "en > ja{3} > en",
+            "'ja' is visited twice in a row at column 6",
+        ),
+        (
+            "en > (ja > ko > ja){2}",
+            "'ja' is visited twice in a row at column 7",
+        ),
+        (
+            "en > (ja > ko){999999} > en",
+            "the path visits more than 10000 languages",
+        ),
+    ] {
+        assert_eq!(
+            PathExpr::parse(source).unwrap_err().message,
+            message,
+            "{}",
+            source
+        );
+    }
+
+    let expr = PathExpr::parse("en > * > en").unwrap();
+    assert_eq!(
+        expr.resolve(&[], &mut fastrand::Rng::new())
+            .unwrap_err()
+            .message,
+        "no language to choose from for the wildcard at column 6"
+    );
+}
+
+#[test]
+fn path_expressions_are_validated() {
+    let translator = TranslatorBaidu::new("id", "key");
+    let expr = PathExpr::parse("en > *[jp, kor] > * > en").unwrap();
+    assert!(expr.validate(&translator, &["fra", "de"]).is_ok());
+    assert_eq!(
+        expr.validate(&translator, &["fra", "klingon"])
+            .unwrap_err()
+            .message,
+        "language 'klingon' is not supported by baidu"
+    );
+    // Codes of other backends are understood.
+    let expr = PathExpr::parse("en > ja > en").unwrap();
+    assert!(!expr.uses_bank());
+    assert!(expr.validate(&translator, &["klingon"]).is_ok());
+    let expr = PathExpr::parse("en > tlh > en").unwrap();
+    assert!(expr.validate(&translator, &[]).is_err());
+}
+
+#[test]
+fn path_plans_meet_constraints() {
+    let plan = PathPlan::new("en", 10)
+        .with_bank(&BANK)
+        .with_required(&["ar", "ja"])
+        .with_forbidden(&["ko"])
+        .with_min_distinct(6)
+        .with_max_visits(2)
+        .with_final_lang("fr");
+    for seed in 0..50 {
+        let path = plan.build(&mut fastrand::Rng::with_seed(seed)).unwrap();
+        assert_eq!(
+            path,
+            plan.build(&mut fastrand::Rng::with_seed(seed)).unwrap()
+        );
+        assert_eq!(path.len(), 10);
+        let stops = stops(&path);
+        assert_eq!(stops[0], "en");
+        assert_eq!(stops[10], "fr");
+        assert!(stops.contains(&"ar") && stops.contains(&"ja"));
+        assert!(!stops.contains(&"ko"));
+        let mut distinct = stops.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert!(distinct.len() >= 6);
+        for lang in &distinct {
+            assert!(stops[1..].iter().filter(|stop| *stop == lang).count() <= 2);
+        }
+        for hop in &path {
+            assert_ne!(hop.from_lang, hop.to_lang);
+        }
+    }
+}
+
+#[test]
+fn impossible_path_plans_are_errors() {
+    let mut rng = fastrand::Rng::new();
+    for (plan, message) in [
+        (PathPlan::new("en", 0), "the path needs at least one round"),
+        (
+            PathPlan::new("en", 1).with_bank(&BANK),
+            "a single round cannot go from 'en' back to itself",
+        ),
+        (
+            PathPlan::new("en", 5).with_bank(&["en"]),
+            "no path meeting all constraints was found",
+        ),
+        (
+            PathPlan::new("en", 3).with_final_lang("en"),
+            "the final language must differ from the original one",
+        ),
+        (
+            PathPlan::new("en", 5)
+                .with_required(&["ja"])
+                .with_forbidden(&["ja"]),
+            "language 'ja' is forbidden but must be visited",
+        ),
+        (
+            PathPlan::new("en", 3).with_required(&["ja", "ko", "zh"]),
+            "3 required languages do not fit in 3 rounds",
+        ),
+        (
+            PathPlan::new("en", 4).with_bank(&BANK).with_min_distinct(6),
+            "at most 4 different languages can be visited",
+        ),
+        (
+            PathPlan::new("en", 10)
+                .with_bank(&["ja", "ko"])
+                .with_max_visits(3),
+            "10 rounds need more than 3 visits to each of 3 languages",
+        ),
+        (
+            PathPlan::new("en", 4).with_max_visits(0),
+            "each language must be allowed at least one visit",
+        ),
+    ] {
+        assert_eq!(plan.build(&mut rng).unwrap_err().message, message);
+    }
+}
+
+#[test]
+fn path_plans_check_support() {
+    let translator = TranslatorBaidu::new("id", "key");
+    let plan = PathPlan::new("en", 4).with_bank(&["jp", "kor", "fra"]);
+    assert!(plan
+        .build_for(&translator, &mut fastrand::Rng::new())
+        .is_ok());
+    assert_eq!(
+        plan.with_required(&["klingon"])
+            .build_for(&translator, &mut fastrand::Rng::new())
+            .unwrap_err()
+            .message,
+        "language 'klingon' is not supported by baidu"
+    );
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use common::MockServer;
+use translator_mangler::layer::{RetryLayer, TranslatorBuilder};
+use translator_mangler::{
+    TranslationDirection, TranslationErrorKind, Translator, TranslatorBaidu, TranslatorYoudao,
+};
+
+/// Get the direction from English to Chinese, which is `to_lang` for the
+/// backend.
+fn en_to(to_lang: &str) -> TranslationDirection {
+    TranslationDirection {
+        from_lang: "en".to_string(),
+        to_lang: to_lang.to_string(),
+    }
+}
+
+/// Start a server answering `first` to the first request and `then` to
+/// every other one.
+fn server_answering(first: (u16, &'static str), then: &'static str) -> MockServer {
+    let requests = AtomicUsize::new(0);
+    MockServer::start_with_status(move |_| match requests.fetch_add(1, Ordering::Relaxed) {
+        0 => (first.0, first.1.to_string()),
+        _ => (200, then.to_string()),
+    })
+}
+
+const BAIDU_OK: &str = r#"{"trans_result":[{"src":"Hello","dst":"你好"}]}"#;
+const YOUDAO_OK: &str = r#"{"errorCode":"0","translation":["你好"]}"#;
+const BAD_GATEWAY: &str = "<html><body>502 Bad Gateway</body></html>";
+
+/// Check that `first` gives a transient error of `kind` from the backend
+/// built by `backend`, and that the request is then retried.
+fn assert_retried(
+    backend: impl Fn(&str) -> (Box<dyn Translator>, &'static str),
+    first: (u16, &'static str),
+    then: &'static str,
+    kind: TranslationErrorKind,
+) {
+    let server = server_answering(first, then);
+    let (translator, to_lang) = backend(&server.url);
+    let error = translator.translate("Hello", &en_to(to_lang)).unwrap_err();
+    assert_eq!(error.kind, kind, "{}", error.message);
+    assert!(error.kind.is_transient());
+
+    let server = server_answering(first, then);
+    let (translator, to_lang) = backend(&server.url);
+    let translator = TranslatorBuilder::new()
+        .layer(RetryLayer::new(1).with_backoff(Duration::ZERO))
+        .build(translator);
+    assert_eq!(
+        translator.translate("Hello", &en_to(to_lang)).unwrap(),
+        "你好"
+    );
+}
+
+fn baidu(url: &str) -> (Box<dyn Translator>, &'static str) {
+    let translator = TranslatorBaidu::new("id", "key").with_endpoint(url);
+    (Box::new(translator), "zh")
+}
+
+fn youdao(url: &str) -> (Box<dyn Translator>, &'static str) {
+    let translator = TranslatorYoudao::new("key", "secret").with_endpoint(url);
+    (Box::new(translator), "zh-CHS")
+}
+
+#[test]
+fn bodies_that_are_not_json_are_retried() {
+    for body in [BAD_GATEWAY, r#"{"trans_result":[{"src":"#] {
+        assert_retried(baidu, (200, body), BAIDU_OK, TranslationErrorKind::Server);
+    }
+    for body in [BAD_GATEWAY, r#"{"errorCode":"0","transl"#] {
+        assert_retried(youdao, (200, body), YOUDAO_OK, TranslationErrorKind::Server);
+    }
+}
+
+#[test]
+fn server_errors_and_rate_limits_are_retried() {
+    for (status, kind) in [
+        (502, TranslationErrorKind::Server),
+        (503, TranslationErrorKind::Server),
+        (429, TranslationErrorKind::RateLimited),
+    ] {
+        assert_retried(baidu, (status, BAD_GATEWAY), BAIDU_OK, kind);
+        assert_retried(youdao, (status, BAD_GATEWAY), YOUDAO_OK, kind);
+    }
+}
+
+#[test]
+fn malformed_error_codes_are_api_errors() {
+    let server = MockServer::start(|_| r#"{"error_code":"oops","error_msg":"?"}"#.to_string());
+    let (translator, to_lang) = baidu(&server.url);
+    let error = translator.translate("Hello", &en_to(to_lang)).unwrap_err();
+    assert_eq!(error.kind, TranslationErrorKind::Api);
+    assert_eq!(error.message, "API ERR: oops ?");
+
+    let server = MockServer::start(|_| r#"{"errorCode":"E1","translation":[]}"#.to_string());
+    let (translator, to_lang) = youdao(&server.url);
+    let error = translator.translate("Hello", &en_to(to_lang)).unwrap_err();
+    assert_eq!(error.kind, TranslationErrorKind::Api);
+    assert_eq!(error.message, "API ERR: E1");
+
+    // Baidu's code for success.
+    let server = MockServer::start(|_| {
+        r#"{"error_code":"52000","trans_result":[{"src":"Hello","dst":"你好"}]}"#.to_string()
+    });
+    let (translator, to_lang) = baidu(&server.url);
+    assert_eq!(
+        translator.translate("Hello", &en_to(to_lang)).unwrap(),
+        "你好"
+    );
+}
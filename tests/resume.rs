@@ -0,0 +1,177 @@
+use translator_mangler::testing::{TranslatorPigLatin, TranslatorReverseWords, TranslatorScripted};
+use translator_mangler::{
+    get_seeded_lang_path, mangle, mangle_lockstep, mangle_mixed, mangle_with, resume_mangle,
+    resume_mangle_mixed, resume_mangle_with, CancellationToken, HopFailure, MangleError,
+    MangleOptions, MixedHop, StopReason, Translator,
+};
+
+/// Get the failure of a run that failed at a hop.
+fn hop_failure<T: std::fmt::Debug>(result: Result<T, MangleError>) -> HopFailure {
+    match result {
+        Err(MangleError::HopFailed(failure)) => *failure,
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn failure_keeps_completed_hops() {
+    let translator = TranslatorScripted::new()
+        .then_ok("two one")
+        .then_ok("one two")
+        .then_err("quota exhausted");
+    let path = get_seeded_lang_path("en", &["zh", "ja"], 5, 42);
+
+    let failure = hop_failure(mangle_with(
+        &translator,
+        "one two",
+        &path,
+        &MangleOptions::new(),
+    ));
+    assert_eq!(failure.error.message, "quota exhausted");
+    assert_eq!(failure.index, 2);
+    assert_eq!(failure.direction, path[2]);
+    assert_eq!(failure.text(), "one two");
+    assert_eq!(failure.trace.original, "one two");
+    assert_eq!(failure.trace.steps.len(), 2);
+    assert_eq!(
+        failure
+            .remaining
+            .iter()
+            .map(|hop| hop.direction.clone())
+            .collect::<Vec<_>>(),
+        path[2..]
+    );
+}
+
+#[test]
+fn mangle_still_fails_with_the_translation_error() {
+    let translator = TranslatorScripted::new()
+        .then_ok("two one")
+        .then_err("quota exhausted");
+    let path = get_seeded_lang_path("en", &["zh", "ja"], 3, 42);
+
+    let error = mangle(&translator, "one two", &path, 0).unwrap_err();
+    assert_eq!(error.message, "quota exhausted");
+}
+
+#[test]
+fn resume_completes_the_trace() {
+    let flaky = TranslatorScripted::new()
+        .then_ok("two one")
+        .then_ok("one two")
+        .then_err("quota exhausted");
+    let path = get_seeded_lang_path("en", &["zh", "ja"], 5, 42);
+    let failure = hop_failure(mangle_with(&flaky, "one two", &path, &MangleOptions::new()));
+
+    let trace = resume_mangle(&failure, &TranslatorReverseWords::new()).unwrap();
+    assert_eq!(trace.original, "one two");
+    assert_eq!(trace.result, "two one");
+    assert_eq!(
+        trace
+            .steps
+            .iter()
+            .map(|step| step.direction.clone())
+            .collect::<Vec<_>>(),
+        path
+    );
+    assert_eq!(trace.steps[2].input, "one two");
+    assert!(trace.finished_at >= trace.started_at);
+}
+
+#[test]
+fn repeated_failures_count_hops_from_the_start() {
+    let flaky = TranslatorScripted::new()
+        .then_ok("two one")
+        .then_err("quota exhausted");
+    let path = get_seeded_lang_path("en", &["zh", "ja"], 5, 42);
+    let first = hop_failure(mangle_with(&flaky, "one two", &path, &MangleOptions::new()));
+    assert_eq!(first.index, 1);
+
+    let still_flaky = TranslatorScripted::new()
+        .then_ok("one two")
+        .then_err("quota exhausted");
+    let second = hop_failure(resume_mangle(&first, &still_flaky));
+    assert_eq!(second.index, 2);
+    assert_eq!(second.direction, path[2]);
+    assert_eq!(second.trace.steps.len(), 2);
+    assert_eq!(second.remaining.len(), 3);
+
+    let trace = resume_mangle(&second, &TranslatorReverseWords::new()).unwrap();
+    assert_eq!(trace.steps.len(), 5);
+    assert_eq!(trace.result, "two one");
+}
+
+#[test]
+fn stopped_resume_counts_hops_from_the_start() {
+    let flaky = TranslatorScripted::new()
+        .then_ok("two one")
+        .then_err("quota exhausted");
+    let path = get_seeded_lang_path("en", &["zh", "ja"], 4, 42);
+    let failure = hop_failure(mangle_with(&flaky, "one two", &path, &MangleOptions::new()));
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let options = MangleOptions::new().with_cancellation(token);
+    match resume_mangle_with(&failure, &TranslatorReverseWords::new(), &options) {
+        Err(MangleError::Stopped { reason, text, hops }) => {
+            assert_eq!(reason, StopReason::Cancelled);
+            assert_eq!(text, "two one");
+            assert_eq!(hops, 1);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn mixed_resume_keeps_translators() {
+    let failing = TranslatorScripted::new().then_err("quota exhausted");
+    let path = get_seeded_lang_path("en", &["zh", "ja"], 4, 42)
+        .into_iter()
+        .enumerate()
+        .map(|(index, direction)| MixedHop {
+            direction,
+            translator: index % 2,
+        })
+        .collect::<Vec<MixedHop>>();
+    let reverse = TranslatorReverseWords::new();
+    let translators: [&dyn Translator; 2] = [&reverse, &failing];
+
+    let failure = hop_failure(mangle_mixed(
+        &translators,
+        "one two",
+        &path,
+        &MangleOptions::new(),
+    ));
+    assert_eq!(failure.index, 1);
+    assert_eq!(failure.remaining, path[1..]);
+
+    let pig_latin = TranslatorPigLatin::new();
+    let trace =
+        resume_mangle_mixed(&failure, &[&reverse, &pig_latin], &MangleOptions::new()).unwrap();
+    assert_eq!(
+        trace
+            .steps
+            .iter()
+            .map(|step| step.backend.as_str())
+            .collect::<Vec<_>>(),
+        ["reverse_words", "pig_latin", "reverse_words", "pig_latin"]
+    );
+}
+
+#[test]
+fn lockstep_failures_resume_alone() {
+    let flaky = TranslatorScripted::new()
+        .then_ok("two one")
+        .then_err("quota exhausted");
+    let path = get_seeded_lang_path("en", &["zh", "ja"], 3, 42);
+
+    let mut results = mangle_lockstep(&flaky, &["one two"], &path, &MangleOptions::new());
+    let failure = hop_failure(results.remove(0));
+    assert_eq!(failure.index, 1);
+    assert_eq!(failure.trace.steps.len(), 1);
+    assert_eq!(failure.remaining.len(), 2);
+
+    let trace = resume_mangle(&failure, &TranslatorReverseWords::new()).unwrap();
+    assert_eq!(trace.steps.len(), 3);
+    assert_eq!(trace.result, "two one");
+}
@@ -0,0 +1,77 @@
+use translator_mangler::signing::{baidu_sign, salt, youdao_input, youdao_sign_v3};
+
+#[test]
+fn baidu_documented_example() {
+    // The example from Baidu's "通用翻译API接入文档".
+    assert_eq!(
+        baidu_sign("2015063000000001", "apple", "1435660288", "12345678"),
+        "f89f9594663708c1605f3d736d01d2d4"
+    );
+}
+
+#[test]
+fn youdao_input_short_queries_are_unchanged() {
+    assert_eq!(youdao_input(""), "");
+    assert_eq!(youdao_input("Hello, world!"), "Hello, world!");
+    // Exactly 20 characters, but 60 bytes.
+    let twenty = "一二三四五六七八九十一二三四五六七八九十";
+    assert_eq!(youdao_input(twenty), twenty);
+}
+
+#[test]
+fn youdao_input_counts_characters() {
+    assert_eq!(
+        youdao_input("Welcome to Youdao AI translation service"),
+        "Welcome to40on service"
+    );
+    assert_eq!(
+        youdao_input("先帝创业未半而中道崩殂，今天下三分，益州疲弊"),
+        "先帝创业未半而中道崩22今天下三分，益州疲弊"
+    );
+    assert_eq!(
+        youdao_input("🦀🦀🦀 Rust 🦀 is 🦀 fun 🦀 and 🦀 safe 🦀"),
+        "🦀🦀🦀 Rust 🦀34d 🦀 safe 🦀"
+    );
+}
+
+#[test]
+fn youdao_v3_signatures() {
+    // Each digest is `printf '%s' '<signed>' | sha256sum` over the string to
+    // sign written out by hand: appKey, input, salt, curtime and appSecret.
+    let cases = [
+        (
+            "Hello, world!",
+            "appKeyHello, world!abcdefgh1700000000appSecret",
+            "dab345692c5ea838a8e91374fc01fd3fa85b2146ee0a38776d1d065e7dbf915f",
+        ),
+        (
+            "先帝创业未半而中道崩殂，今天下三分，益州疲弊",
+            "appKey先帝创业未半而中道崩22今天下三分，益州疲弊abcdefgh1700000000appSecret",
+            "abb95f33ce977f08bba295a138ea38a262c4f24ec5699c0a9d871354abc03638",
+        ),
+        (
+            "🦀🦀🦀 Rust 🦀 is 🦀 fun 🦀 and 🦀 safe 🦀",
+            "appKey🦀🦀🦀 Rust 🦀34d 🦀 safe 🦀abcdefgh1700000000appSecret",
+            "d7b0fca7060d487e100a7d2e454693f1a1f6b1311aa4b004c886b3e453c686d1",
+        ),
+    ];
+    for (query, signed, expected) in cases {
+        assert_eq!(
+            format!("appKey{}abcdefgh1700000000appSecret", youdao_input(query)),
+            signed
+        );
+        assert_eq!(
+            youdao_sign_v3("appKey", query, "abcdefgh", 1700000000, "appSecret"),
+            expected
+        );
+    }
+}
+
+#[test]
+fn salt_is_ascii() {
+    for len in [0, 4, 16] {
+        let salt = salt(len);
+        assert_eq!(salt.len(), len);
+        assert!(salt.chars().all(|c| c.is_ascii_alphanumeric()));
+    }
+}
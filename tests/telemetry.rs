@@ -0,0 +1,28 @@
+#![cfg(feature = "metrics")]
+
+mod common;
+
+use common::MockServer;
+use translator_mangler::telemetry::render_metrics;
+use translator_mangler::{mangle, TranslationDirection, TranslatorBaidu};
+
+#[test]
+fn metrics_count_requests_and_runs() {
+    let server = MockServer::start(|_| {
+        r#"{"error_code":"54003","error_msg":"Invalid Access Limit"}"#.to_string()
+    });
+    let translator = TranslatorBaidu::new("id", "key").with_endpoint(&server.url);
+    let path = [TranslationDirection {
+        from_lang: "en".to_string(),
+        to_lang: "zh".to_string(),
+    }];
+
+    assert!(mangle(&translator, "Hello", &path, 0).is_err());
+
+    let metrics = render_metrics();
+    assert!(
+        metrics.contains(r#"translator_requests_total{backend="baidu",outcome="rate_limited"} 1"#)
+    );
+    assert!(metrics.contains(r#"translator_request_duration_seconds_count{backend="baidu"} 1"#));
+    assert!(metrics.contains(r#"mangle_runs_total{outcome="error"} 1"#));
+}
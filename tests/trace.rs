@@ -0,0 +1,126 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use translator_mangler::layer::{RetryLayer, TranslatorBuilder, TranslatorMiddleware};
+use translator_mangler::testing::{TranslatorReverseWords, TranslatorScripted};
+use translator_mangler::{
+    get_random_lang_path, mangle_with_trace, MangleOptions, MangleTrace, TranslateOptions,
+    TranslationDirection, TranslationError, TranslationErrorKind, Translator,
+};
+
+/// Middleware sending each attempt from a thread of its own, retrying once.
+#[derive(Clone)]
+struct RetryOnThread;
+
+impl TranslatorMiddleware for RetryOnThread {
+    fn translate(
+        &self,
+        inner: &dyn Translator,
+        text: &str,
+        lang: &TranslationDirection,
+        options: &TranslateOptions,
+    ) -> Result<String, TranslationError> {
+        let attempt = || {
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| inner.translate_with(text, lang, options))
+                    .join()
+                    .unwrap()
+            })
+        };
+        attempt().or_else(|e| {
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| options.context.notify_retry(1, &e))
+                    .join()
+                    .unwrap()
+            });
+            attempt()
+        })
+    }
+}
+
+#[test]
+fn trace_chains_steps() {
+    let path = get_random_lang_path("en", &["zh", "ja", "ko"], 4);
+    let trace = mangle_with_trace(
+        &TranslatorReverseWords::new(),
+        "one two three",
+        &path,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    assert_eq!(trace.original, "one two three");
+    assert_eq!(trace.steps.len(), 4);
+    assert_eq!(trace.steps[0].input, "one two three");
+    for (step, direction) in trace.steps.iter().zip(&path) {
+        assert_eq!(step.direction, *direction);
+        assert_eq!(step.backend, "reverse_words");
+        assert!(step.finished_at >= step.started_at);
+    }
+    for pair in trace.steps.windows(2) {
+        assert_eq!(pair[0].output, pair[1].input);
+    }
+    assert_eq!(trace.result, trace.steps[3].output);
+    assert!(trace.elapsed >= trace.latency);
+}
+
+#[test]
+fn trace_counts_retries() {
+    let transient = || TranslationError {
+        kind: TranslationErrorKind::Network,
+        message: "NETWORK ERR".to_string(),
+    };
+    let backend = Arc::new(
+        TranslatorScripted::new()
+            .then_ok("你好")
+            .then_error(transient())
+            .then_error(transient())
+            .then_ok("Hi"),
+    );
+    let translator = TranslatorBuilder::new()
+        .layer(RetryLayer::new(3).with_backoff(Duration::ZERO))
+        .build(backend);
+    let path = get_random_lang_path("en", &["zh"], 2);
+
+    let trace =
+        mangle_with_trace(translator.as_ref(), "Hello", &path, &MangleOptions::new()).unwrap();
+
+    assert_eq!(trace.steps[0].retries, 0);
+    assert_eq!(trace.steps[1].retries, 2);
+    assert_eq!(trace.retries, 2);
+    assert_eq!(trace.result, "Hi");
+}
+
+#[test]
+fn trace_counts_retries_of_any_middleware_on_any_thread() {
+    let backend = TranslatorScripted::new()
+        .then_err("reset")
+        .then_ok("你好")
+        .then_ok("Hi");
+    let translator = TranslatorBuilder::new().layer(RetryOnThread).build(backend);
+    let path = get_random_lang_path("en", &["zh"], 2);
+
+    let trace =
+        mangle_with_trace(translator.as_ref(), "Hello", &path, &MangleOptions::new()).unwrap();
+
+    assert_eq!(trace.steps[0].retries, 1);
+    assert_eq!(trace.steps[1].retries, 0);
+    assert_eq!(trace.result, "Hi");
+}
+
+#[test]
+fn trace_round_trips_through_json() {
+    let path = get_random_lang_path("en", &["zh", "ja"], 3);
+    let trace = mangle_with_trace(
+        &TranslatorReverseWords::new(),
+        "one two",
+        &path,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    let json = serde_json::to_string(&trace).unwrap();
+    assert_eq!(serde_json::from_str::<MangleTrace>(&json).unwrap(), trace);
+}
@@ -0,0 +1,345 @@
+use std::sync::{Arc, Mutex};
+
+use translator_mangler::strategy::{LazyPath, PathStrategy, RoundTrip, UniformRandom};
+use translator_mangler::testing::{TranslatorPigLatin, TranslatorReverseWords, TranslatorScripted};
+use translator_mangler::{
+    mangle_mixed_until, mangle_until, resume_mangle_until, MangleError, MangleObserver,
+    MangleOptions, StopCause, StopRule, TranslationDirection, Translator,
+};
+
+fn lazy_path(seed: u64) -> LazyPath<'static> {
+    LazyPath::new(
+        &UniformRandom,
+        "en",
+        &["zh", "ja", "fr"],
+        fastrand::Rng::with_seed(seed),
+    )
+}
+
+#[test]
+fn legs_come_back_to_the_original_language() {
+    let mut path = LazyPath::new(&RoundTrip, "en", &["zh", "ja"], fastrand::Rng::with_seed(1))
+        .with_leg_rounds(4);
+    for max_rounds in [10, 3, 2] {
+        let leg = path.next_leg(max_rounds);
+        assert_eq!(leg.len(), max_rounds.min(4));
+        assert_eq!(leg[0].from_lang, "en");
+        assert_eq!(leg.last().unwrap().to_lang, "en");
+    }
+    assert_eq!(path.original_lang(), "en");
+}
+
+#[test]
+fn stops_at_the_maximum_rounds() {
+    let translator = TranslatorPigLatin::new();
+    let rule = StopRule::new(5)
+        .with_unchanged_for(2)
+        .with_cycle_detection();
+    let outcome = mangle_until(
+        &translator,
+        "one two",
+        &mut lazy_path(2),
+        &rule,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    // The last hop could not have come back, so it is not run.
+    assert_eq!(outcome.cause, StopCause::MaxRounds);
+    assert_eq!(outcome.trace.steps.len(), 4);
+    assert_eq!(outcome.trace.steps[3].direction.to_lang, "en");
+}
+
+#[test]
+fn stops_at_a_fixed_point() {
+    let translator = TranslatorScripted::new()
+        .then_ok("x")
+        .then_ok("same")
+        .then_ok("same")
+        .then_ok("same");
+    let rule = StopRule::new(20).with_unchanged_for(2);
+    let outcome = mangle_until(
+        &translator,
+        "original",
+        &mut lazy_path(3),
+        &rule,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    assert_eq!(outcome.cause, StopCause::Unchanged);
+    assert_eq!(outcome.trace.steps.len(), 4);
+    assert_eq!(outcome.trace.result, "same");
+}
+
+#[test]
+fn coming_back_to_the_last_leg_is_a_cycle() {
+    let translator = TranslatorReverseWords::new();
+    let mut path = lazy_path(3);
+
+    // The words are reversed at every hop, so the text never stops
+    // changing.
+    let rule = StopRule::new(20).with_unchanged_for(4);
+    let outcome = mangle_until(
+        &translator,
+        "a b c",
+        &mut path,
+        &rule,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+    assert_eq!(outcome.cause, StopCause::MaxRounds);
+    assert_eq!(outcome.trace.steps.len(), 20);
+
+    let rule = rule.with_cycle_detection();
+    let outcome = mangle_until(
+        &translator,
+        "a b c",
+        &mut path,
+        &rule,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+    assert_eq!(outcome.cause, StopCause::Cycle);
+    assert_eq!(outcome.trace.steps.len(), 2);
+    assert_eq!(outcome.trace.result, "a b c");
+}
+
+#[test]
+fn stops_when_the_text_cycles() {
+    let translator = TranslatorScripted::new()
+        .then_ok("x")
+        .then_ok("A")
+        .then_ok("y")
+        .then_ok("B")
+        .then_ok("z")
+        .then_ok("A");
+    let rule = StopRule::new(20).with_cycle_detection();
+    let outcome = mangle_until(
+        &translator,
+        "original",
+        &mut lazy_path(4),
+        &rule,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    assert_eq!(outcome.cause, StopCause::Cycle);
+    assert_eq!(outcome.trace.steps.len(), 6);
+    assert_eq!(translator.remaining(), 0);
+}
+
+#[test]
+fn stops_below_the_wanted_similarity() {
+    let translator = TranslatorScripted::new()
+        .then_ok("甲")
+        .then_ok("The quick brown fox leaps over the lazy dog.")
+        .then_ok("乙")
+        .then_ok("A fast fox hopped past a sleepy hound.");
+    let rule = StopRule::new(20).with_similarity_below(0.5);
+    let outcome = mangle_until(
+        &translator,
+        "The quick brown fox jumps over the lazy dog.",
+        &mut lazy_path(5),
+        &rule,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    assert_eq!(outcome.cause, StopCause::SimilarityBelow);
+    assert_eq!(outcome.trace.steps.len(), 4);
+    assert!(outcome.trace.similarity().overall() < 0.5);
+}
+
+#[test]
+fn observers_are_told_the_maximum_rounds() {
+    #[derive(Default)]
+    struct Totals(Mutex<Vec<usize>>);
+
+    impl MangleObserver for Totals {
+        fn on_step_start(&self, _: usize, total: usize, _: &TranslationDirection) {
+            self.0.lock().unwrap().push(total);
+        }
+    }
+
+    let totals = Arc::new(Totals::default());
+    let options = MangleOptions::new().with_shared_observer(totals.clone());
+    let rule = StopRule::new(6);
+    let translator = TranslatorPigLatin::new();
+    mangle_until(&translator, "one", &mut lazy_path(6), &rule, &options).unwrap();
+
+    assert_eq!(*totals.0.lock().unwrap(), [6; 6]);
+}
+
+#[test]
+fn mixed_runs_use_supporting_translators() {
+    let reverse = TranslatorReverseWords::new().with_langs(&["en", "zh"]);
+    let pig_latin = TranslatorPigLatin::new().with_langs(&["en", "ja"]);
+    let translators: [&dyn Translator; 2] = [&reverse, &pig_latin];
+    let mut path = LazyPath::new(
+        &UniformRandom,
+        "en",
+        &["zh", "ja"],
+        fastrand::Rng::with_seed(7),
+    );
+    let outcome = mangle_mixed_until(
+        &translators,
+        "one two",
+        &mut path,
+        &StopRule::new(8),
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    assert_eq!(outcome.trace.steps.len(), 8);
+    for step in &outcome.trace.steps {
+        let other = if step.direction.from_lang == "en" {
+            &step.direction.to_lang
+        } else {
+            &step.direction.from_lang
+        };
+        let expected = if other == "zh" {
+            reverse.name()
+        } else {
+            pig_latin.name()
+        };
+        assert_eq!(step.backend, expected);
+    }
+}
+
+#[test]
+fn failures_stop_the_run() {
+    let translator = TranslatorScripted::new().then_ok("x").then_err("boom");
+    let rule = StopRule::new(20);
+    let result = mangle_until(
+        &translator,
+        "original",
+        &mut lazy_path(8),
+        &rule,
+        &MangleOptions::new(),
+    );
+    assert!(matches!(result, Err(MangleError::HopFailed(f)) if f.error.message.contains("boom")));
+}
+
+#[test]
+fn resumed_runs_follow_the_same_path() {
+    let rule = StopRule::new(6);
+    let uninterrupted = mangle_until(
+        &TranslatorReverseWords::new(),
+        "original",
+        &mut lazy_path(5),
+        &rule,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    let flaky = TranslatorScripted::new()
+        .then_ok("a")
+        .then_ok("b")
+        .then_ok("c")
+        .then_err("boom");
+    let mut path = lazy_path(5);
+    let Err(MangleError::HopFailed(failure)) =
+        mangle_until(&flaky, "original", &mut path, &rule, &MangleOptions::new())
+    else {
+        panic!("the fourth hop should fail");
+    };
+    assert_eq!(failure.index, 3);
+    let outcome = resume_mangle_until(
+        &failure,
+        &[&TranslatorReverseWords::new()],
+        &mut path,
+        &rule,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+
+    assert_eq!(outcome.cause, StopCause::MaxRounds);
+    let directions = |steps: &[translator_mangler::MangleStep]| {
+        steps
+            .iter()
+            .map(|step| step.direction.clone())
+            .collect::<Vec<TranslationDirection>>()
+    };
+    assert_eq!(
+        directions(&outcome.trace.steps),
+        directions(&uninterrupted.trace.steps)
+    );
+    assert_eq!(outcome.trace.steps[2].output, "c");
+}
+
+#[test]
+fn resumed_runs_remember_earlier_legs() {
+    let flaky = TranslatorScripted::new()
+        .then_ok("x")
+        .then_ok("same")
+        .then_ok("y")
+        .then_err("boom");
+    let rule = StopRule::new(20).with_cycle_detection();
+    let mut path = lazy_path(8);
+    let Err(MangleError::HopFailed(failure)) =
+        mangle_until(&flaky, "original", &mut path, &rule, &MangleOptions::new())
+    else {
+        panic!("the fourth hop should fail");
+    };
+
+    // Back to the text at the end of the first leg.
+    let resumed = TranslatorScripted::new().then_ok("same");
+    let outcome = resume_mangle_until(
+        &failure,
+        &[&resumed],
+        &mut path,
+        &rule,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+    assert_eq!(outcome.cause, StopCause::Cycle);
+    assert_eq!(outcome.trace.steps.len(), 4);
+    assert_eq!(outcome.trace.result, "same");
+}
+
+/// A strategy always going to Chinese and back, whatever the rounds asked.
+struct ThereAndBack;
+
+impl PathStrategy for ThereAndBack {
+    fn path(
+        &self,
+        original_lang: &str,
+        _lang_bank: &[&str],
+        _rounds: usize,
+        _rng: &mut fastrand::Rng,
+    ) -> Vec<TranslationDirection> {
+        RoundTrip.path(original_lang, &["zh"], 2, &mut fastrand::Rng::new())
+    }
+}
+
+#[test]
+fn resumed_runs_check_the_legs_actually_made() {
+    let flaky = TranslatorScripted::new()
+        .then_ok("x")
+        .then_ok("A")
+        .then_ok("y")
+        .then_err("boom");
+    let rule = StopRule::new(20).with_cycle_detection();
+    // Legs of 2 hops, although 3 are asked.
+    let mut path =
+        LazyPath::new(&ThereAndBack, "en", &["zh"], fastrand::Rng::with_seed(1)).with_leg_rounds(3);
+    let Err(MangleError::HopFailed(failure)) =
+        mangle_until(&flaky, "original", &mut path, &rule, &MangleOptions::new())
+    else {
+        panic!("the fourth hop should fail");
+    };
+    assert_eq!(failure.leg_ends, [2]);
+
+    let resumed = TranslatorScripted::new().then_ok("A");
+    let outcome = resume_mangle_until(
+        &failure,
+        &[&resumed],
+        &mut path,
+        &rule,
+        &MangleOptions::new(),
+    )
+    .unwrap();
+    assert_eq!(outcome.cause, StopCause::Cycle);
+    assert_eq!(outcome.trace.steps.len(), 4);
+}